@@ -0,0 +1,208 @@
+use crate::block_device::{BlockDevice, DeviceError, Sector, SECTOR_BYTES};
+use crate::port::Port;
+
+const PRIMARY_IO_BASE: u16 = 0x1f0;
+
+const DATA: u16 = 0;
+const SECTOR_COUNT: u16 = 2;
+const LBA_LOW: u16 = 3;
+const LBA_MID: u16 = 4;
+const LBA_HIGH: u16 = 5;
+const DRIVE: u16 = 6;
+const COMMAND: u16 = 7;
+const STATUS: u16 = 7;
+
+const STATUS_ERROR: u8 = 0x01;
+const STATUS_DATA_READY: u8 = 0x08;
+const STATUS_DEVICE_FAULT: u8 = 0x20;
+const STATUS_BUSY: u8 = 0x80;
+/// Read with nothing attached, the bus floats high.
+const NO_DRIVE: u8 = 0xff;
+
+const SELECT_SLAVE: u8 = 0xb0;
+const SELECT_SLAVE_LBA: u8 = 0xf0;
+const READ_SECTORS: u8 = 0x20;
+const WRITE_SECTORS: u8 = 0x30;
+const FLUSH_CACHE: u8 = 0xe7;
+const IDENTIFY: u8 = 0xec;
+
+/// Where IDENTIFY reports the number of 28-bit addressable sectors, as two
+/// 16-bit words, low word first.
+const IDENTIFY_SECTORS_WORD: usize = 60;
+/// Status reads before a busy drive is given up on.
+const MAX_POLLS: usize = 1_000_000;
+
+/// The slave drive on the primary ATA bus, driven by programmed I/O one
+/// sector at a time. Slow, but it needs no interrupts or DMA set up. The
+/// master is the disk the kernel boots from, so QEMU needs a second drive,
+/// `-drive file=swim.img,format=raw,index=1`.
+#[derive(Copy, Clone)]
+pub(crate) struct AtaPio {
+    io_base: u16,
+    sectors: usize
+}
+
+impl AtaPio {
+    /// The drive, if there is an ATA disk to talk to. CD drives and empty
+    /// buses give `None`.
+    pub(crate) fn primary_slave() -> Option<Self> {
+        let mut drive: AtaPio = Self {
+            io_base: PRIMARY_IO_BASE,
+            sectors: 0
+        };
+        drive.sectors = drive.identify()?;
+        Some(drive)
+    }
+
+    /// The `index`th run of `sectors` sectors from the start of the disk,
+    /// if the disk is big enough to hold it.
+    pub(crate) fn partition(&self, index: usize, sectors: usize) -> Option<AtaPartition> {
+        let first: usize = index * sectors;
+        if first + sectors > self.sectors {
+            return None;
+        }
+        Some(AtaPartition { drive: *self, first, sectors })
+    }
+
+    fn read_register(&self, offset: u16) -> u8 {
+        let mut port: Port<u8> = Port::new(self.io_base + offset);
+        unsafe { port.read() }
+    }
+
+    fn write_register(&self, offset: u16, value: u8) {
+        let mut port: Port<u8> = Port::new(self.io_base + offset);
+        unsafe { port.write(value) }
+    }
+
+    fn identify(&mut self) -> Option<usize> {
+        self.write_register(DRIVE, SELECT_SLAVE);
+        for offset in [SECTOR_COUNT, LBA_LOW, LBA_MID, LBA_HIGH] {
+            self.write_register(offset, 0);
+        }
+        self.write_register(COMMAND, IDENTIFY);
+        let status: u8 = self.read_register(STATUS);
+        if status == 0 || status == NO_DRIVE {
+            return None;
+        }
+        self.wait_while_busy().ok()?;
+        // ATAPI and SATA devices answer with a signature here instead.
+        if self.read_register(LBA_MID) != 0 || self.read_register(LBA_HIGH) != 0 {
+            return None;
+        }
+        self.wait_for_data().ok()?;
+        let mut words: [u16; SECTOR_BYTES / 2] = [0; SECTOR_BYTES / 2];
+        let mut data: Port<u16> = Port::new(self.io_base + DATA);
+        for word in words.iter_mut() {
+            *word = unsafe { data.read() };
+        }
+        Some(words[IDENTIFY_SECTORS_WORD] as usize | (words[IDENTIFY_SECTORS_WORD + 1] as usize) << 16)
+    }
+
+    /// The status once the drive is no longer busy.
+    fn wait_while_busy(&self) -> Result<u8, DeviceError> {
+        for _ in 0..MAX_POLLS {
+            let status: u8 = self.read_register(STATUS);
+            if status & STATUS_BUSY == 0 {
+                return Ok(status);
+            }
+        }
+        Err(DeviceError::TimedOut)
+    }
+
+    fn wait_for_data(&self) -> Result<(), DeviceError> {
+        for _ in 0..MAX_POLLS {
+            let status: u8 = self.wait_while_busy()?;
+            if status & (STATUS_ERROR | STATUS_DEVICE_FAULT) != 0 {
+                return Err(DeviceError::Fault);
+            }
+            if status & STATUS_DATA_READY != 0 {
+                return Ok(());
+            }
+        }
+        Err(DeviceError::TimedOut)
+    }
+
+    /// Starts `command` on the one sector at `sector`.
+    fn start(&self, sector: usize, command: u8) -> Result<(), DeviceError> {
+        if sector >= self.sectors {
+            return Err(DeviceError::OutOfRange);
+        }
+        self.write_register(DRIVE, SELECT_SLAVE_LBA | ((sector >> 24) & 0x0f) as u8);
+        self.write_register(SECTOR_COUNT, 1);
+        self.write_register(LBA_LOW, sector as u8);
+        self.write_register(LBA_MID, (sector >> 8) as u8);
+        self.write_register(LBA_HIGH, (sector >> 16) as u8);
+        self.write_register(COMMAND, command);
+        self.wait_for_data()
+    }
+}
+
+impl BlockDevice for AtaPio {
+    fn num_sectors(&self) -> usize {
+        self.sectors
+    }
+
+    fn read_sector(&mut self, sector: usize, buffer: &mut Sector) -> Result<(), DeviceError> {
+        self.start(sector, READ_SECTORS)?;
+        let mut data: Port<u16> = Port::new(self.io_base + DATA);
+        for pair in buffer.chunks_exact_mut(2) {
+            let word: u16 = unsafe { data.read() };
+            pair.copy_from_slice(&word.to_le_bytes());
+        }
+        Ok(())
+    }
+
+    fn write_sector(&mut self, sector: usize, buffer: &Sector) -> Result<(), DeviceError> {
+        self.start(sector, WRITE_SECTORS)?;
+        let mut data: Port<u16> = Port::new(self.io_base + DATA);
+        for pair in buffer.chunks_exact(2) {
+            unsafe { data.write(u16::from_le_bytes([pair[0], pair[1]])) };
+        }
+        if self.wait_while_busy()? & (STATUS_ERROR | STATUS_DEVICE_FAULT) != 0 {
+            return Err(DeviceError::Fault);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), DeviceError> {
+        self.write_register(DRIVE, SELECT_SLAVE);
+        self.write_register(COMMAND, FLUSH_CACHE);
+        if self.wait_while_busy()? & (STATUS_ERROR | STATUS_DEVICE_FAULT) != 0 {
+            return Err(DeviceError::Fault);
+        }
+        Ok(())
+    }
+}
+
+/// Part of the drive, addressed from its own first sector, so each window's
+/// filesystem can have one to itself.
+#[derive(Copy, Clone)]
+pub(crate) struct AtaPartition {
+    drive: AtaPio,
+    first: usize,
+    sectors: usize
+}
+
+impl BlockDevice for AtaPartition {
+    fn num_sectors(&self) -> usize {
+        self.sectors
+    }
+
+    fn read_sector(&mut self, sector: usize, buffer: &mut Sector) -> Result<(), DeviceError> {
+        if sector >= self.sectors {
+            return Err(DeviceError::OutOfRange);
+        }
+        self.drive.read_sector(self.first + sector, buffer)
+    }
+
+    fn write_sector(&mut self, sector: usize, buffer: &Sector) -> Result<(), DeviceError> {
+        if sector >= self.sectors {
+            return Err(DeviceError::OutOfRange);
+        }
+        self.drive.write_sector(self.first + sector, buffer)
+    }
+
+    fn flush(&mut self) -> Result<(), DeviceError> {
+        self.drive.flush()
+    }
+}
@@ -0,0 +1,105 @@
+use core::fmt::Write;
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::byte_writer::ByteWriter;
+use crate::file_name::FileName;
+use crate::observer::Observer;
+use crate::render::Screen;
+use crate::WINDOW_WIDTH;
+
+const AUDIT_LOG_CAPACITY: usize = 16;
+const JOURNAL_LINE_BYTES: usize = 40;
+
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum FileOperation {
+    Create,
+    Write,
+    Delete
+}
+
+impl FileOperation {
+    fn name(self) -> &'static str {
+        match self {
+            FileOperation::Create => "create",
+            FileOperation::Write => "write",
+            FileOperation::Delete => "delete"
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct AuditEntry {
+    tick: usize,
+    window: usize,
+    operation: FileOperation,
+    filename: FileName
+}
+
+/// The most recent file operations, oldest entries overwritten first.
+pub(crate) struct AuditLog {
+    entries: [Option<AuditEntry>; AUDIT_LOG_CAPACITY],
+    next: usize
+}
+
+impl AuditLog {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: [None; AUDIT_LOG_CAPACITY],
+            next: 0
+        }
+    }
+
+    pub(crate) fn record(&mut self, tick: usize, window: usize, operation: FileOperation, filename: &str) {
+        self.entries[self.next] = Some(AuditEntry {
+            tick,
+            window,
+            operation,
+            filename: FileName::truncated(filename)
+        });
+        self.next = (self.next + 1) % AUDIT_LOG_CAPACITY;
+    }
+
+    fn oldest_first(&self) -> impl Iterator<Item = &AuditEntry> + '_ {
+        (0..AUDIT_LOG_CAPACITY)
+            .filter_map(move |i| self.entries[(self.next + i) % AUDIT_LOG_CAPACITY].as_ref())
+    }
+
+    pub(crate) fn draw(&self, screen: &mut dyn Screen, start_col: usize, start_row: usize, rows: usize) {
+        screen.plot_str("tick  win op     file", start_col, start_row, ColorCode::new(Color::White, Color::Black));
+        let shown: usize = rows - 1;
+        let total: usize = self.oldest_first().count();
+        let mut row: usize = start_row + 1;
+        for entry in self.oldest_first().skip(total.saturating_sub(shown)) {
+            let color: ColorCode = ColorCode::new(Color::LightGray, Color::Black);
+            for col in start_col..start_col + WINDOW_WIDTH {
+                screen.plot(' ', col, row, ColorCode::new(Color::Black, Color::Black));
+            }
+            screen.plot_num(entry.tick as isize, start_col, row, color);
+            screen.plot_str("F", start_col + 6, row, color);
+            screen.plot_num(entry.window as isize + 1, start_col + 7, row, color);
+            screen.plot_str(entry.operation.name(), start_col + 10, row, color);
+            screen.plot_str(entry.filename.as_str(), start_col + 17, row, color);
+            row += 1;
+        }
+    }
+
+    /// Writes the log as text lines, oldest first, returning the number of
+    /// bytes used. Lines that do not fit completely are left out.
+    pub(crate) fn write_journal(&self, buffer: &mut [u8]) -> usize {
+        let mut journal: ByteWriter = ByteWriter::new(buffer);
+        for entry in self.oldest_first() {
+            if journal.remaining() < JOURNAL_LINE_BYTES {
+                break;
+            }
+            let _ = writeln!(journal, "{} F{} {} {}", entry.tick, entry.window + 1,
+                             entry.operation.name(), entry.filename);
+        }
+        journal.len()
+    }
+}
+
+impl Observer for AuditLog {
+    fn on_file_saved(&mut self, frame: usize, window: usize, filename: &str) {
+        self.record(frame, window, FileOperation::Write, filename);
+    }
+}
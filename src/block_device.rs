@@ -0,0 +1,133 @@
+use crate::ata::AtaPartition;
+use crate::FS_SECTORS;
+
+/// Bytes in one sector, the unit a `BlockDevice` reads and writes.
+pub(crate) const SECTOR_BYTES: usize = 512;
+
+pub(crate) type Sector = [u8; SECTOR_BYTES];
+
+#[derive(Copy, Clone)]
+pub(crate) enum DeviceError {
+    /// The device stayed busy longer than it should ever take.
+    TimedOut,
+    /// The device reported an error for the last command.
+    Fault,
+    /// A sector past the end of the device.
+    OutOfRange,
+    /// The disk holds something else, such as a boot sector, and is left
+    /// alone.
+    InUse
+}
+
+impl DeviceError {
+    /// What went wrong, in words meant to be followed by the name of the
+    /// file involved, as with `fs_error::describe()`.
+    pub(crate) fn describe(&self) -> &'static str {
+        match self {
+            DeviceError::TimedOut => "disk not responding",
+            DeviceError::Fault => "disk error",
+            DeviceError::OutOfRange => "disk too small",
+            DeviceError::InUse => "disk holds other data"
+        }
+    }
+}
+
+/// Storage that keeps its contents across reboots, addressed by sector.
+pub(crate) trait BlockDevice {
+    fn num_sectors(&self) -> usize;
+
+    fn read_sector(&mut self, sector: usize, buffer: &mut Sector) -> Result<(), DeviceError>;
+
+    /// The sector may sit in the device's cache until the next `flush()`.
+    fn write_sector(&mut self, sector: usize, buffer: &Sector) -> Result<(), DeviceError>;
+
+    /// Returns once everything written so far is stored for good.
+    fn flush(&mut self) -> Result<(), DeviceError>;
+}
+
+/// A device kept in memory, which loses its contents at reboot.
+pub(crate) struct MemoryDisk<const SECTORS: usize> {
+    sectors: [Sector; SECTORS]
+}
+
+impl<const SECTORS: usize> MemoryDisk<SECTORS> {
+    pub(crate) fn new() -> Self {
+        Self {
+            sectors: [[0; SECTOR_BYTES]; SECTORS]
+        }
+    }
+}
+
+impl<const SECTORS: usize> BlockDevice for MemoryDisk<SECTORS> {
+    fn num_sectors(&self) -> usize {
+        SECTORS
+    }
+
+    fn read_sector(&mut self, sector: usize, buffer: &mut Sector) -> Result<(), DeviceError> {
+        *buffer = *self.sectors.get(sector).ok_or(DeviceError::OutOfRange)?;
+        Ok(())
+    }
+
+    fn write_sector(&mut self, sector: usize, buffer: &Sector) -> Result<(), DeviceError> {
+        *self.sectors.get_mut(sector).ok_or(DeviceError::OutOfRange)? = *buffer;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), DeviceError> {
+        Ok(())
+    }
+}
+
+/// Where a window's filesystem keeps its sectors: in memory, as every
+/// window starts out, or on its own part of the ATA disk once one is
+/// found, when the memory goes unused.
+pub(crate) struct WindowDisk {
+    memory: MemoryDisk<FS_SECTORS>,
+    partition: Option<AtaPartition>
+}
+
+impl WindowDisk {
+    pub(crate) fn memory() -> Self {
+        Self {
+            memory: MemoryDisk::new(),
+            partition: None
+        }
+    }
+
+    pub(crate) fn ata(partition: AtaPartition) -> Self {
+        Self {
+            memory: MemoryDisk::new(),
+            partition: Some(partition)
+        }
+    }
+}
+
+impl BlockDevice for WindowDisk {
+    fn num_sectors(&self) -> usize {
+        match &self.partition {
+            Some(partition) => partition.num_sectors(),
+            None => self.memory.num_sectors()
+        }
+    }
+
+    fn read_sector(&mut self, sector: usize, buffer: &mut Sector) -> Result<(), DeviceError> {
+        match &mut self.partition {
+            Some(partition) => partition.read_sector(sector, buffer),
+            None => self.memory.read_sector(sector, buffer)
+        }
+    }
+
+    fn write_sector(&mut self, sector: usize, buffer: &Sector) -> Result<(), DeviceError> {
+        match &mut self.partition {
+            Some(partition) => partition.write_sector(sector, buffer),
+            None => self.memory.write_sector(sector, buffer)
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), DeviceError> {
+        match &mut self.partition {
+            Some(partition) => partition.flush(),
+            None => self.memory.flush()
+        }
+    }
+}
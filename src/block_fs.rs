@@ -0,0 +1,533 @@
+use core::cmp::min;
+use file_system_solution::FileSystemError;
+
+use crate::block_device::{BlockDevice, DeviceError, Sector, SECTOR_BYTES};
+use crate::{FS_SECTORS, MAX_FILE_BYTES, MAX_FILENAME_BYTES, MAX_FILES_STORED, MAX_OPEN};
+
+/// The start of the first sector of a formatted filesystem.
+const MAGIC: &[u8] = b"SWIMFS";
+/// Where the first sector says how long the names in the directory can be.
+const NAME_BYTES_OFFSET: usize = 8;
+/// The last two bytes of a boot sector.
+const BOOT_SIGNATURE: [u8; 2] = [0x55, 0xaa];
+const FILE_SECTORS: usize = MAX_FILE_BYTES.div_ceil(SECTOR_BYTES);
+/// A name, then the file's length as four little-endian bytes, then the
+/// number of each sector holding it as two.
+const ENTRY_BYTES: usize = 128;
+const ENTRIES_PER_SECTOR: usize = SECTOR_BYTES / ENTRY_BYTES;
+const DIRECTORY_SECTORS: usize = MAX_FILES_STORED.div_ceil(ENTRIES_PER_SECTOR);
+const FIRST_DATA_SECTOR: usize = 1 + DIRECTORY_SECTORS;
+const _: () = assert!(FS_SECTORS <= u16::MAX as usize, "sector numbers do not fit a directory entry");
+const _: () = assert!(FIRST_DATA_SECTOR < FS_SECTORS, "the directory leaves no room for files");
+
+/// What the first sector of a device says is on it.
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum Format {
+    /// Nothing that needs keeping.
+    Blank,
+    /// A filesystem whose names can be this many bytes long.
+    Formatted(usize),
+    /// Something else, such as a boot sector, which is left alone.
+    Other
+}
+
+pub(crate) fn probe(device: &mut impl BlockDevice) -> Result<Format, DeviceError> {
+    let mut sector: Sector = [0; SECTOR_BYTES];
+    device.read_sector(0, &mut sector)?;
+    if sector.starts_with(MAGIC) {
+        Ok(Format::Formatted(sector[NAME_BYTES_OFFSET] as usize))
+    } else if sector[SECTOR_BYTES - 2..] == BOOT_SIGNATURE {
+        Ok(Format::Other)
+    } else {
+        Ok(Format::Blank)
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Entry<const NAME_BYTES: usize> {
+    name: [u8; NAME_BYTES],
+    len: usize,
+    sectors: [u16; FILE_SECTORS]
+}
+
+impl<const NAME_BYTES: usize> Entry<NAME_BYTES> {
+    const LEN_OFFSET: usize = NAME_BYTES;
+    const SECTORS_OFFSET: usize = NAME_BYTES + 4;
+
+    fn named(name: &str) -> Self {
+        let mut entry: Entry<NAME_BYTES> = Self {
+            name: [0; NAME_BYTES],
+            len: 0,
+            sectors: [0; FILE_SECTORS]
+        };
+        entry.name[0..name.len()].copy_from_slice(name.as_bytes());
+        entry
+    }
+
+    fn is_named(&self, name: &str) -> bool {
+        name.len() <= NAME_BYTES && self.name[0..name.len()] == *name.as_bytes() &&
+            (name.len() == NAME_BYTES || self.name[name.len()] == 0)
+    }
+
+    fn sectors(&self) -> &[u16] {
+        &self.sectors[0..self.len.div_ceil(SECTOR_BYTES)]
+    }
+
+    fn encode(&self, bytes: &mut [u8]) {
+        bytes[0..NAME_BYTES].copy_from_slice(&self.name);
+        bytes[Self::LEN_OFFSET..Self::LEN_OFFSET + 4].copy_from_slice(&(self.len as u32).to_le_bytes());
+        for (i, sector) in self.sectors().iter().enumerate() {
+            let start: usize = Self::SECTORS_OFFSET + 2 * i;
+            bytes[start..start + 2].copy_from_slice(&sector.to_le_bytes());
+        }
+    }
+
+    /// The entry `bytes` hold, or None if they are empty or name a sector
+    /// outside the ones kept for files.
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes[0] == 0 {
+            return None;
+        }
+        let mut entry: Entry<NAME_BYTES> = Self::named("");
+        entry.name.copy_from_slice(&bytes[0..NAME_BYTES]);
+        let mut len: [u8; 4] = [0; 4];
+        len.copy_from_slice(&bytes[Self::LEN_OFFSET..Self::LEN_OFFSET + 4]);
+        entry.len = min(u32::from_le_bytes(len) as usize, MAX_FILE_BYTES);
+        for i in 0..entry.len.div_ceil(SECTOR_BYTES) {
+            let start: usize = Self::SECTORS_OFFSET + 2 * i;
+            let sector: u16 = u16::from_le_bytes([bytes[start], bytes[start + 1]]);
+            if !(FIRST_DATA_SECTOR..FS_SECTORS).contains(&(sector as usize)) {
+                return None;
+            }
+            entry.sectors[i] = sector;
+        }
+        Some(entry)
+    }
+}
+
+#[derive(Copy, Clone)]
+struct OpenFile {
+    entry: usize,
+    /// Where the next read starts. Writes always add to the end.
+    pos: usize,
+    writing: bool
+}
+
+/// Files kept on a `BlockDevice`, with the same calls as the filesystem
+/// the windows used to keep on their RAM disks.
+///
+/// The first sector marks the device as formatted and the directory
+/// follows, each entry listing the sectors its file is in. A file being
+/// written goes into sectors no version of any file uses, and its entry
+/// is only written when it is closed, so a reset part way through leaves
+/// the old contents whole.
+pub(crate) struct BlockFileSystem<D: BlockDevice, const NAME_BYTES: usize = MAX_FILENAME_BYTES> {
+    device: D,
+    /// The files as they are now, including ones still being written.
+    entries: [Option<Entry<NAME_BYTES>>; MAX_FILES_STORED],
+    /// The files as the directory on the device has them.
+    stored: [Option<Entry<NAME_BYTES>>; MAX_FILES_STORED],
+    /// The sectors either of the above uses.
+    used: [bool; FS_SECTORS],
+    open: [Option<OpenFile>; MAX_OPEN],
+    fault: Option<DeviceError>
+}
+
+impl<D: BlockDevice, const NAME_BYTES: usize> BlockFileSystem<D, NAME_BYTES> {
+    /// Evaluated when a filesystem is made, so names too long for a
+    /// directory entry fail to compile.
+    const ENTRY_CHECK: () = assert!(NAME_BYTES + 4 + 2 * FILE_SECTORS <= ENTRY_BYTES,
+                                    "a directory entry does not fit its name");
+
+    fn empty(device: D) -> Self {
+        let () = Self::ENTRY_CHECK;
+        Self {
+            device,
+            entries: [None; MAX_FILES_STORED],
+            stored: [None; MAX_FILES_STORED],
+            used: [false; FS_SECTORS],
+            open: [None; MAX_OPEN],
+            fault: None
+        }
+    }
+
+    /// An empty filesystem on a device that does not fail, such as a
+    /// `MemoryDisk`. Should it fail anyway, the fault is kept as any
+    /// other would be.
+    pub(crate) fn new(device: D) -> Self {
+        let mut file_system: BlockFileSystem<D, NAME_BYTES> = Self::empty(device);
+        if let Err(error) = file_system.write_format() {
+            file_system.fault = Some(error);
+        }
+        file_system
+    }
+
+    /// An empty filesystem on `device`, in place of whatever it held.
+    pub(crate) fn format(device: D) -> Result<Self, DeviceError> {
+        if device.num_sectors() < FS_SECTORS {
+            return Err(DeviceError::OutOfRange);
+        }
+        let mut file_system: BlockFileSystem<D, NAME_BYTES> = Self::empty(device);
+        file_system.write_format()?;
+        Ok(file_system)
+    }
+
+    /// The filesystem already on `device`, which must have been formatted
+    /// for names `NAME_BYTES` long.
+    pub(crate) fn mount(mut device: D) -> Result<Self, DeviceError> {
+        if device.num_sectors() < FS_SECTORS {
+            return Err(DeviceError::OutOfRange);
+        }
+        if probe(&mut device)? != Format::Formatted(NAME_BYTES) {
+            return Err(DeviceError::InUse);
+        }
+        let mut file_system: BlockFileSystem<D, NAME_BYTES> = Self::empty(device);
+        let mut sector: Sector = [0; SECTOR_BYTES];
+        for i in 0..MAX_FILES_STORED {
+            if i % ENTRIES_PER_SECTOR == 0 {
+                file_system.device.read_sector(1 + i / ENTRIES_PER_SECTOR, &mut sector)?;
+            }
+            let start: usize = (i % ENTRIES_PER_SECTOR) * ENTRY_BYTES;
+            file_system.stored[i] = Entry::decode(&sector[start..start + ENTRY_BYTES]);
+        }
+        file_system.entries = file_system.stored;
+        file_system.find_used();
+        Ok(file_system)
+    }
+
+    /// Writes the whole directory, then the header saying how to read it.
+    fn write_format(&mut self) -> Result<(), DeviceError> {
+        for directory_sector in 1..FIRST_DATA_SECTOR {
+            self.write_directory_sector(directory_sector)?;
+        }
+        self.device.flush()?;
+        let mut header: Sector = [0; SECTOR_BYTES];
+        header[0..MAGIC.len()].copy_from_slice(MAGIC);
+        header[NAME_BYTES_OFFSET] = NAME_BYTES as u8;
+        self.device.write_sector(0, &header)?;
+        self.device.flush()
+    }
+
+    /// The last error the device gave, since the read or write it failed
+    /// could only say `DiskFull`.
+    pub(crate) fn take_fault(&mut self) -> Option<DeviceError> {
+        self.fault.take()
+    }
+
+    /// Keeps `error` for `take_fault()`. The filesystem's errors have no
+    /// word for a failing device.
+    fn failed(&mut self, error: DeviceError) -> FileSystemError {
+        self.fault = Some(error);
+        FileSystemError::DiskFull
+    }
+
+    pub(crate) fn open_read(&mut self, name: &str) -> Result<usize, FileSystemError> {
+        let entry: usize = self.find(name).ok_or(FileSystemError::FileNotFound)?;
+        if self.open.iter().flatten().any(|file| file.entry == entry && file.writing) {
+            return Err(FileSystemError::AlreadyOpen);
+        }
+        self.open_entry(entry, false)
+    }
+
+    /// Opens `name` to be written from the start, creating it if need be.
+    pub(crate) fn open_create(&mut self, name: &str) -> Result<usize, FileSystemError> {
+        if name.len() > NAME_BYTES {
+            return Err(FileSystemError::FilenameTooLong);
+        }
+        let entry: usize = match self.find(name) {
+            Some(entry) if self.open.iter().flatten().any(|file| file.entry == entry) => {
+                return Err(FileSystemError::AlreadyOpen);
+            },
+            Some(entry) => entry,
+            None => self.entries.iter().position(Option::is_none).ok_or(FileSystemError::TooManyFiles)?
+        };
+        let fd: usize = self.open_entry(entry, true)?;
+        self.entries[entry] = Some(Entry::named(name));
+        Ok(fd)
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.is_some_and(|entry| entry.is_named(name)))
+    }
+
+    fn open_entry(&mut self, entry: usize, writing: bool) -> Result<usize, FileSystemError> {
+        let fd: usize = self.open.iter().position(Option::is_none).ok_or(FileSystemError::TooManyOpen)?;
+        self.open[fd] = Some(OpenFile { entry, pos: 0, writing });
+        Ok(fd)
+    }
+
+    fn open_file(&self, fd: usize) -> Result<OpenFile, FileSystemError> {
+        self.open.get(fd).copied().flatten().ok_or(FileSystemError::FileNotOpen)
+    }
+
+    /// Reads on from where the last read stopped, returning how much of
+    /// `buffer` was filled. The rest of it is left as it was.
+    pub(crate) fn read(&mut self, fd: usize, buffer: &mut [u8]) -> Result<usize, FileSystemError> {
+        let file: OpenFile = self.open_file(fd)?;
+        if file.writing {
+            return Err(FileSystemError::NotOpenForRead);
+        }
+        let entry: Entry<NAME_BYTES> = self.entries[file.entry].ok_or(FileSystemError::FileNotOpen)?;
+        let len: usize = min(buffer.len(), entry.len.saturating_sub(file.pos));
+        let mut sector: Sector = [0; SECTOR_BYTES];
+        let mut done: usize = 0;
+        while done < len {
+            let pos: usize = file.pos + done;
+            let offset: usize = pos % SECTOR_BYTES;
+            let chunk: usize = min(len - done, SECTOR_BYTES - offset);
+            if let Err(error) = self.device.read_sector(entry.sectors[pos / SECTOR_BYTES] as usize, &mut sector) {
+                return Err(self.failed(error));
+            }
+            buffer[done..done + chunk].copy_from_slice(&sector[offset..offset + chunk]);
+            done += chunk;
+        }
+        self.open[fd] = Some(OpenFile { pos: file.pos + len, ..file });
+        Ok(len)
+    }
+
+    /// Adds `bytes` to the end of the file, each new sector going where no
+    /// version of any file is.
+    pub(crate) fn write(&mut self, fd: usize, bytes: &[u8]) -> Result<(), FileSystemError> {
+        let file: OpenFile = self.open_file(fd)?;
+        if !file.writing {
+            return Err(FileSystemError::NotOpenForWrite);
+        }
+        let mut entry: Entry<NAME_BYTES> = self.entries[file.entry].ok_or(FileSystemError::FileNotOpen)?;
+        if entry.len + bytes.len() > MAX_FILE_BYTES {
+            return Err(FileSystemError::FileTooBig);
+        }
+        let mut sector: Sector = [0; SECTOR_BYTES];
+        let mut done: usize = 0;
+        while done < bytes.len() {
+            let index: usize = entry.len / SECTOR_BYTES;
+            let offset: usize = entry.len % SECTOR_BYTES;
+            let chunk: usize = min(bytes.len() - done, SECTOR_BYTES - offset);
+            if offset == 0 {
+                entry.sectors[index] = self.allocate().ok_or(FileSystemError::DiskFull)?;
+                sector.fill(0);
+            } else if let Err(error) = self.device.read_sector(entry.sectors[index] as usize, &mut sector) {
+                return Err(self.failed(error));
+            }
+            sector[offset..offset + chunk].copy_from_slice(&bytes[done..done + chunk]);
+            if let Err(error) = self.device.write_sector(entry.sectors[index] as usize, &sector) {
+                return Err(self.failed(error));
+            }
+            entry.len += chunk;
+            done += chunk;
+            self.entries[file.entry] = Some(entry);
+        }
+        Ok(())
+    }
+
+    /// Closes `fd`. A file written through it is only recorded in the
+    /// directory now, so until the close succeeds the device keeps its
+    /// old contents.
+    pub(crate) fn close(&mut self, fd: usize) -> Result<(), FileSystemError> {
+        let file: OpenFile = self.open.get_mut(fd).and_then(Option::take).ok_or(FileSystemError::FileNotOpen)?;
+        if file.writing {
+            if let Err(error) = self.commit(file.entry) {
+                return Err(self.failed(error));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn list_directory(&self) -> Result<(usize, [[u8; NAME_BYTES]; MAX_FILES_STORED]), FileSystemError> {
+        let mut names: [[u8; NAME_BYTES]; MAX_FILES_STORED] = [[0; NAME_BYTES]; MAX_FILES_STORED];
+        let mut num_files: usize = 0;
+        for entry in self.entries.iter().flatten() {
+            names[num_files] = entry.name;
+            num_files += 1;
+        }
+        Ok((num_files, names))
+    }
+
+    fn allocate(&mut self) -> Option<u16> {
+        let sector: usize = (FIRST_DATA_SECTOR..FS_SECTORS).find(|&sector| !self.used[sector])?;
+        self.used[sector] = true;
+        Some(sector as u16)
+    }
+
+    /// Marks the sectors some version of a file still needs, freeing the
+    /// rest.
+    fn find_used(&mut self) {
+        self.used = [false; FS_SECTORS];
+        for entry in self.entries.iter().chain(self.stored.iter()).flatten() {
+            for &sector in entry.sectors() {
+                self.used[sector as usize] = true;
+            }
+        }
+    }
+
+    /// Records `entry` in the directory once its sectors are out of the
+    /// device's cache. Only its directory sector is written, so the change
+    /// happens all at once or not at all.
+    fn commit(&mut self, entry: usize) -> Result<(), DeviceError> {
+        self.device.flush()?;
+        let previous: Option<Entry<NAME_BYTES>> = self.stored[entry];
+        self.stored[entry] = self.entries[entry];
+        if let Err(error) = self.write_directory_sector(1 + entry / ENTRIES_PER_SECTOR) {
+            self.stored[entry] = previous;
+            return Err(error);
+        }
+        self.device.flush()?;
+        self.find_used();
+        Ok(())
+    }
+
+    fn write_directory_sector(&mut self, directory_sector: usize) -> Result<(), DeviceError> {
+        let mut sector: Sector = [0; SECTOR_BYTES];
+        let first: usize = (directory_sector - 1) * ENTRIES_PER_SECTOR;
+        for i in first..min(first + ENTRIES_PER_SECTOR, MAX_FILES_STORED) {
+            if let Some(entry) = self.stored[i] {
+                let start: usize = (i - first) * ENTRY_BYTES;
+                entry.encode(&mut sector[start..start + ENTRY_BYTES]);
+            }
+        }
+        self.device.write_sector(directory_sector, &sector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_device::MemoryDisk;
+
+    type TestFileSystem = BlockFileSystem<MemoryDisk<FS_SECTORS>>;
+
+    /// Contents that differ from one byte to the next and from file to file.
+    fn pattern(len: usize, seed: u8) -> Vec<u8> {
+        (0..len).map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed) | 1).collect()
+    }
+
+    fn write_file(file_system: &mut TestFileSystem, name: &str, contents: &[u8]) -> Result<(), FileSystemError> {
+        let fd: usize = file_system.open_create(name)?;
+        let written: Result<(), FileSystemError> = file_system.write(fd, contents);
+        file_system.close(fd)?;
+        written
+    }
+
+    fn read_file(file_system: &mut TestFileSystem, name: &str) -> Option<Vec<u8>> {
+        let fd: usize = file_system.open_read(name).ok()?;
+        let mut contents: Vec<u8> = vec![0; MAX_FILE_BYTES];
+        let len: usize = file_system.read(fd, &mut contents).ok()?;
+        file_system.close(fd).ok()?;
+        contents.truncate(len);
+        Some(contents)
+    }
+
+    fn remount(file_system: TestFileSystem) -> TestFileSystem {
+        match BlockFileSystem::mount(file_system.device) {
+            Ok(file_system) => file_system,
+            Err(error) => panic!("remount failed: {}", error.describe())
+        }
+    }
+
+    #[test]
+    fn files_survive_a_remount() {
+        let mut file_system: TestFileSystem = TestFileSystem::new(MemoryDisk::new());
+        let big: Vec<u8> = pattern(3 * SECTOR_BYTES + 17, 5);
+        assert!(write_file(&mut file_system, "big", &big).is_ok());
+        assert!(write_file(&mut file_system, "small", b"hi").is_ok());
+        assert!(write_file(&mut file_system, "empty", b"").is_ok());
+        let mut file_system: TestFileSystem = remount(file_system);
+        assert_eq!(read_file(&mut file_system, "big"), Some(big));
+        assert_eq!(read_file(&mut file_system, "small"), Some(b"hi".to_vec()));
+        assert_eq!(read_file(&mut file_system, "empty"), Some(Vec::new()));
+        assert_eq!(file_system.list_directory().ok().map(|(num_files, _)| num_files), Some(3));
+    }
+
+    #[test]
+    fn small_writes_and_reads_cross_sectors() {
+        let mut file_system: TestFileSystem = TestFileSystem::new(MemoryDisk::new());
+        let contents: Vec<u8> = pattern(2 * SECTOR_BYTES + 100, 9);
+        let fd: usize = file_system.open_create("pieces").unwrap_or(MAX_OPEN);
+        for chunk in contents.chunks(37) {
+            assert!(file_system.write(fd, chunk).is_ok());
+        }
+        assert!(file_system.close(fd).is_ok());
+        let fd: usize = file_system.open_read("pieces").unwrap_or(MAX_OPEN);
+        let mut read: Vec<u8> = Vec::new();
+        let mut chunk: [u8; 100] = [0; 100];
+        while let Ok(len @ 1..) = file_system.read(fd, &mut chunk) {
+            read.extend_from_slice(&chunk[0..len]);
+        }
+        assert!(file_system.close(fd).is_ok());
+        assert_eq!(read, contents);
+    }
+
+    #[test]
+    fn unfinished_write_leaves_the_old_contents() {
+        let mut file_system: TestFileSystem = TestFileSystem::new(MemoryDisk::new());
+        let old: Vec<u8> = pattern(SECTOR_BYTES + 1, 1);
+        assert!(write_file(&mut file_system, "notes", &old).is_ok());
+        let fd: usize = file_system.open_create("notes").unwrap_or(MAX_OPEN);
+        assert!(file_system.write(fd, &pattern(2 * SECTOR_BYTES, 2)).is_ok());
+        let mut file_system: TestFileSystem = remount(file_system);
+        assert_eq!(read_file(&mut file_system, "notes"), Some(old));
+    }
+
+    #[test]
+    fn rewriting_frees_the_old_sectors() {
+        let mut file_system: TestFileSystem = TestFileSystem::new(MemoryDisk::new());
+        let contents: Vec<u8> = pattern(MAX_FILE_BYTES, 3);
+        for _ in 0..FS_SECTORS {
+            assert!(write_file(&mut file_system, "big", &contents).is_ok());
+        }
+        let mut file_system: TestFileSystem = remount(file_system);
+        assert_eq!(read_file(&mut file_system, "big"), Some(contents));
+    }
+
+    #[test]
+    fn full_disk_keeps_what_fits() {
+        let mut file_system: TestFileSystem = TestFileSystem::new(MemoryDisk::new());
+        let contents: Vec<u8> = pattern(MAX_FILE_BYTES, 4);
+        let names: [&str; 8] = ["f0", "f1", "f2", "f3", "f4", "f5", "f6", "f7"];
+        let stored: usize = names.iter()
+            .take_while(|name| write_file(&mut file_system, name, &contents).is_ok())
+            .count();
+        assert!(stored < names.len());
+        assert!(matches!(write_file(&mut file_system, "more", &contents), Err(FileSystemError::DiskFull)));
+        let mut file_system: TestFileSystem = remount(file_system);
+        for name in names.iter().take(stored) {
+            assert_eq!(read_file(&mut file_system, name), Some(contents.clone()));
+        }
+    }
+
+    #[test]
+    fn limits_are_errors() {
+        let mut file_system: TestFileSystem = TestFileSystem::new(MemoryDisk::new());
+        assert!(matches!(file_system.open_create("a name far too long"), Err(FileSystemError::FilenameTooLong)));
+        assert!(matches!(file_system.open_read("missing"), Err(FileSystemError::FileNotFound)));
+        let fd: usize = file_system.open_create("open").unwrap_or(MAX_OPEN);
+        assert!(matches!(file_system.open_read("open"), Err(FileSystemError::AlreadyOpen)));
+        assert!(matches!(file_system.write(fd, &pattern(MAX_FILE_BYTES + 1, 6)), Err(FileSystemError::FileTooBig)));
+        assert!(matches!(file_system.read(fd, &mut [0; 4]), Err(FileSystemError::NotOpenForRead)));
+        assert!(file_system.close(fd).is_ok());
+        assert!(matches!(file_system.close(fd), Err(FileSystemError::FileNotOpen)));
+    }
+
+    #[test]
+    fn mount_only_takes_its_own_format() {
+        let mut disk: MemoryDisk<FS_SECTORS> = MemoryDisk::new();
+        assert!(matches!(probe(&mut disk), Ok(Format::Blank)));
+        assert!(matches!(TestFileSystem::mount(disk), Err(DeviceError::InUse)));
+
+        let narrow: BlockFileSystem<MemoryDisk<FS_SECTORS>, 10> = BlockFileSystem::new(MemoryDisk::new());
+        let mut disk: MemoryDisk<FS_SECTORS> = narrow.device;
+        assert!(matches!(probe(&mut disk), Ok(Format::Formatted(10))));
+        assert!(matches!(TestFileSystem::mount(disk), Err(DeviceError::InUse)));
+
+        let mut disk: MemoryDisk<FS_SECTORS> = MemoryDisk::new();
+        let mut boot_sector: Sector = [0; SECTOR_BYTES];
+        boot_sector[SECTOR_BYTES - 2..].copy_from_slice(&BOOT_SIGNATURE);
+        assert!(disk.write_sector(0, &boot_sector).is_ok());
+        assert!(matches!(probe(&mut disk), Ok(Format::Other)));
+    }
+
+    #[test]
+    fn small_device_is_refused() {
+        let disk: MemoryDisk<{ FS_SECTORS - 1 }> = MemoryDisk::new();
+        assert!(matches!(BlockFileSystem::<_>::format(disk), Err(DeviceError::OutOfRange)));
+    }
+}
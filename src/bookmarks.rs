@@ -0,0 +1,89 @@
+const NUM_BOOKMARKS: usize = 10;
+const CHORD_START: char = '\u{1b}';
+const SET_KEY: char = 'm';
+
+#[derive(Copy, Clone, PartialEq)]
+enum Chord {
+    Idle,
+    Started,
+    Setting
+}
+
+/// What the editor should do with a key after the bookmarks have seen it.
+pub(crate) enum BookmarkKey {
+    Typed,
+    Consumed,
+    Jump(usize),
+    /// Escape and a key the bookmarks have no use for, left to the editor.
+    Chord(char)
+}
+
+/// Numbered rows in the file being edited. Escape, `m` and a digit sets a
+/// bookmark on the current row; Escape and a digit jumps back to it. Any
+/// other key after Escape is handed back as a chord of the editor's own.
+pub(crate) struct Bookmarks {
+    rows: [Option<usize>; NUM_BOOKMARKS],
+    chord: Chord
+}
+
+impl Bookmarks {
+    pub(crate) fn new() -> Self {
+        Self {
+            rows: [None; NUM_BOOKMARKS],
+            chord: Chord::Idle
+        }
+    }
+
+    pub(crate) fn key(&mut self, key: char, current_row: usize) -> BookmarkKey {
+        let chord: Chord = self.chord;
+        self.chord = Chord::Idle;
+        let number: Option<usize> = key.to_digit(10).map(|digit| digit as usize);
+        match (chord, number) {
+            (Chord::Idle, _) if key == CHORD_START => {
+                self.chord = Chord::Started;
+                BookmarkKey::Consumed
+            },
+            (Chord::Idle, _) => BookmarkKey::Typed,
+            (Chord::Started, _) if key == SET_KEY => {
+                self.chord = Chord::Setting;
+                BookmarkKey::Consumed
+            },
+            (Chord::Started, Some(number)) => match self.rows[number] {
+                Some(row) => BookmarkKey::Jump(row),
+                None => BookmarkKey::Consumed
+            },
+            (Chord::Setting, Some(number)) => {
+                self.rows[number] = Some(current_row);
+                BookmarkKey::Consumed
+            },
+            (Chord::Started, None) => BookmarkKey::Chord(key),
+            (Chord::Setting, None) => BookmarkKey::Consumed
+        }
+    }
+
+    /// Keeps bookmarks on their text when a line is inserted at `row`.
+    pub(crate) fn line_inserted(&mut self, row: usize) {
+        for bookmark in self.rows.iter_mut().flatten() {
+            if *bookmark >= row {
+                *bookmark += 1;
+            }
+        }
+    }
+
+    /// Keeps bookmarks on their text when the line at `row` is joined to
+    /// the one above it.
+    pub(crate) fn line_removed(&mut self, row: usize) {
+        for bookmark in self.rows.iter_mut().flatten() {
+            if *bookmark >= row {
+                *bookmark -= 1;
+            }
+        }
+    }
+
+    /// Each bookmark's digit and row, for the gutter.
+    pub(crate) fn markers(&self) -> impl Iterator<Item = (char, usize)> + '_ {
+        self.rows.iter().enumerate().filter_map(|(number, row)| {
+            row.map(|row| (char::from_digit(number as u32, 10).unwrap_or('?'), row))
+        })
+    }
+}
@@ -0,0 +1,43 @@
+use core::cmp::min;
+
+use crate::{MAX_EDITOR_LINES, WINDOW_WIDTH};
+
+/// An index into one dimension of a window or of the editor's text, always
+/// less than `LIMIT`. Stepping past either edge stops at the edge rather
+/// than running off the end.
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) struct Bounded<const LIMIT: usize>(usize);
+
+/// A line of the editor's text, counted from the top of the file.
+pub(crate) type Line = Bounded<MAX_EDITOR_LINES>;
+/// A column of a window's text, counted from its left edge.
+pub(crate) type Col = Bounded<WINDOW_WIDTH>;
+
+impl<const LIMIT: usize> Bounded<LIMIT> {
+    pub(crate) const FIRST: Self = Self(0);
+
+    /// Clamps `value` to the last index.
+    pub(crate) fn new(value: usize) -> Self {
+        Self(min(value, LIMIT - 1))
+    }
+
+    pub(crate) fn get(self) -> usize {
+        self.0
+    }
+
+    pub(crate) fn is_first(self) -> bool {
+        self.0 == 0
+    }
+
+    pub(crate) fn is_last(self) -> bool {
+        self.0 == LIMIT - 1
+    }
+
+    pub(crate) fn next(self) -> Self {
+        Self::new(self.0 + 1)
+    }
+
+    pub(crate) fn previous(self) -> Self {
+        Self(self.0.saturating_sub(1))
+    }
+}
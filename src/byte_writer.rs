@@ -0,0 +1,34 @@
+use core::fmt::{self, Write};
+
+/// Formats text into a fixed byte buffer, silently dropping whatever does
+/// not fit.
+pub(crate) struct ByteWriter<'a> {
+    buffer: &'a mut [u8],
+    len: usize
+}
+
+impl<'a> ByteWriter<'a> {
+    pub(crate) fn new(buffer: &'a mut [u8]) -> Self {
+        Self { buffer, len: 0 }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.buffer.len() - self.len
+    }
+}
+
+impl Write for ByteWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            if self.len < self.buffer.len() {
+                self.buffer[self.len] = byte;
+                self.len += 1;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use crate::WINDOW_WIDTH;
+
+/// One line of editor text, copied or cut in one window and pasted in any.
+pub(crate) struct Clipboard {
+    line: [char; WINDOW_WIDTH],
+    len: usize,
+    holding: bool
+}
+
+impl Clipboard {
+    pub(crate) fn new() -> Self {
+        Self {
+            line: ['\0'; WINDOW_WIDTH],
+            len: 0,
+            holding: false
+        }
+    }
+
+    /// Replaces whatever was held with `line`.
+    pub(crate) fn copy(&mut self, line: &[char]) {
+        self.len = line.len().min(WINDOW_WIDTH);
+        self.line[0..self.len].copy_from_slice(&line[0..self.len]);
+        self.holding = true;
+    }
+
+    /// The line held, which may be blank, or None before anything is
+    /// copied.
+    pub(crate) fn line(&self) -> Option<&[char]> {
+        self.holding.then_some(&self.line[0..self.len])
+    }
+}
@@ -0,0 +1,76 @@
+use pc_keyboard::{DecodedKey, KeyCode};
+
+use crate::text_mode::TextMode;
+
+/// Everything that can be asked of a `SwimDocManager`, so the keyboard,
+/// the demo and anything driving the manager from outside go through the
+/// same code. Filenames refer to the active window's file list.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Command<'a> {
+    /// Makes a window active, counting from 0.
+    FocusWindow(usize),
+    /// Creates an empty file in every window, owned by the active one.
+    CreateFile(&'a str),
+    /// Asks on the top line for the name of a file to create, as F5 does
+    /// when there are fewer than five windows.
+    NewFile,
+    /// Closes whatever the active window shows, as F6 does when there are
+    /// fewer than six windows. An editor asks how to save first.
+    Close,
+    OpenForEdit(&'a str),
+    Run(&'a str),
+    /// A typed character, handled as if it had been pressed.
+    Key(char),
+    /// A key with no character of its own, such as F6 or an arrow.
+    RawKey(KeyCode),
+    /// Queues the active window's editor to be saved, leaving it open.
+    Save,
+    /// Stops whatever program a window is running, counting from 0.
+    Kill(usize),
+    /// Switches the screen to a text mode and lays the windows out for it.
+    SetTextMode(TextMode),
+    /// Writes a `Snapshot` of every window to the serial port.
+    DumpState
+}
+
+impl<'a> Command<'a> {
+    /// The file the command names, if any, for error messages.
+    pub(crate) fn filename(&self) -> Option<&'a str> {
+        match self {
+            Command::CreateFile(filename) | Command::OpenForEdit(filename) | Command::Run(filename) => Some(filename),
+            _ => None
+        }
+    }
+}
+
+impl From<DecodedKey> for Command<'_> {
+    fn from(key: DecodedKey) -> Self {
+        match key {
+            DecodedKey::Unicode(char) => Command::Key(char),
+            DecodedKey::RawKey(code) => Command::RawKey(code)
+        }
+    }
+}
+
+/// What a `Command` did when it succeeded.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Effect {
+    /// The key went wherever keys go in the current mode.
+    KeyHandled,
+    Focused(usize),
+    FileCreated,
+    EditorOpened,
+    /// Data files open in the viewer instead of the editor.
+    ViewerOpened,
+    ProgramStarted,
+    /// The save itself happens over the next few frames.
+    SaveQueued,
+    /// A prompt on the top line is waiting for keys.
+    PromptOpened,
+    /// The window went back to its file list.
+    Closed,
+    ProgramKilled,
+    /// The windows are back in their places, sized for the new mode.
+    TextModeSet,
+    StateDumped
+}
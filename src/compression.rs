@@ -0,0 +1,119 @@
+//! Run-length encoding for stored text files. A compressed file starts with
+//! `COMPRESSED_MARKER`; after it, `RUN_MARKER, count, byte` stands for `count`
+//! copies of `byte` and any other byte stands for itself. No encoded byte is
+//! ever zero, so compressed files can be measured like plain text.
+
+const COMPRESSED_MARKER: u8 = 0x01;
+const RUN_MARKER: u8 = 0x02;
+const MIN_RUN: usize = 4;
+const MAX_RUN: usize = u8::MAX as usize;
+
+pub(crate) fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.first() == Some(&COMPRESSED_MARKER)
+}
+
+/// Returns the compressed length, or `None` if `output` is too small.
+pub(crate) fn compress(input: &[u8], output: &mut [u8]) -> Option<usize> {
+    let mut len: usize = 0;
+    push(output, &mut len, COMPRESSED_MARKER)?;
+    let mut i: usize = 0;
+    while i < input.len() {
+        let byte: u8 = input[i];
+        let mut run: usize = 1;
+        while i + run < input.len() && input[i + run] == byte && run < MAX_RUN {
+            run += 1;
+        }
+        if run >= MIN_RUN || byte == RUN_MARKER {
+            push(output, &mut len, RUN_MARKER)?;
+            push(output, &mut len, run as u8)?;
+            push(output, &mut len, byte)?;
+        } else {
+            for _ in 0..run {
+                push(output, &mut len, byte)?;
+            }
+        }
+        i += run;
+    }
+    Some(len)
+}
+
+/// Returns the decompressed length, or `None` if `output` is too small or
+/// `input` ends in the middle of a run.
+pub(crate) fn decompress(input: &[u8], output: &mut [u8]) -> Option<usize> {
+    let mut len: usize = 0;
+    let mut i: usize = 1;
+    while i < input.len() {
+        if input[i] == RUN_MARKER {
+            let count: u8 = *input.get(i + 1)?;
+            let byte: u8 = *input.get(i + 2)?;
+            for _ in 0..count {
+                push(output, &mut len, byte)?;
+            }
+            i += 3;
+        } else {
+            push(output, &mut len, input[i])?;
+            i += 1;
+        }
+    }
+    Some(len)
+}
+
+fn push(output: &mut [u8], len: &mut usize, byte: u8) -> Option<()> {
+    *output.get_mut(*len)? = byte;
+    *len += 1;
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(input: &[u8]) -> Vec<u8> {
+        let mut packed: Vec<u8> = vec![0; 3 * input.len() + 1];
+        let packed_len: usize = compress(input, &mut packed).expect("room to compress");
+        assert!(is_compressed(&packed[0..packed_len]));
+        assert!(!packed[0..packed_len].contains(&0));
+        let mut unpacked: Vec<u8> = vec![0; input.len()];
+        let unpacked_len: usize = decompress(&packed[0..packed_len], &mut unpacked).expect("room to decompress");
+        unpacked.truncate(unpacked_len);
+        unpacked
+    }
+
+    #[test]
+    fn runs_shrink_and_come_back() {
+        let input: Vec<u8> = [b"ab".as_slice(), &[b' '; 40], b"cd\n"].concat();
+        let mut packed: [u8; 64] = [0; 64];
+        assert_eq!(compress(&input, &mut packed), Some(1 + 2 + 3 + 3));
+        assert_eq!(round_trip(&input), input);
+    }
+
+    #[test]
+    fn edge_cases_come_back() {
+        assert_eq!(round_trip(b""), b"");
+        assert_eq!(round_trip(b"abc"), b"abc");
+        assert_eq!(round_trip(b"aaa"), b"aaa");
+        assert_eq!(round_trip(b"aaaa"), b"aaaa");
+        assert_eq!(round_trip(&[RUN_MARKER, b'x', RUN_MARKER]), [RUN_MARKER, b'x', RUN_MARKER]);
+        assert_eq!(round_trip(&[COMPRESSED_MARKER; 3]), [COMPRESSED_MARKER; 3]);
+        assert_eq!(round_trip(&[b'z'; MAX_RUN + 1]), [b'z'; MAX_RUN + 1]);
+        let text: Vec<u8> = (1..=255).collect();
+        assert_eq!(round_trip(&text), text);
+    }
+
+    #[test]
+    fn short_output_is_none() {
+        let mut packed: [u8; 3] = [0; 3];
+        assert_eq!(compress(b"abcd", &mut packed), None);
+        assert_eq!(compress(b"", &mut []), None);
+        let mut unpacked: [u8; 3] = [0; 3];
+        assert_eq!(decompress(&[COMPRESSED_MARKER, RUN_MARKER, 4, b'a'], &mut unpacked), None);
+    }
+
+    #[test]
+    fn cut_off_run_is_none() {
+        let mut unpacked: [u8; 8] = [0; 8];
+        assert_eq!(decompress(&[COMPRESSED_MARKER, b'a', RUN_MARKER, 4], &mut unpacked), None);
+        assert_eq!(decompress(&[COMPRESSED_MARKER, RUN_MARKER], &mut unpacked), None);
+        assert_eq!(decompress(&[COMPRESSED_MARKER], &mut unpacked), Some(0));
+    }
+}
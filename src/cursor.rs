@@ -0,0 +1,89 @@
+use pluggable_interrupt_os::vga_buffer::BUFFER_WIDTH;
+
+use crate::port::Port;
+
+const CRTC_INDEX_PORT: u16 = 0x3d4;
+const CRTC_DATA_PORT: u16 = 0x3d5;
+const CURSOR_START_REGISTER: u8 = 0x0a;
+const CURSOR_END_REGISTER: u8 = 0x0b;
+const CURSOR_LOCATION_HIGH_REGISTER: u8 = 0x0e;
+const CURSOR_LOCATION_LOW_REGISTER: u8 = 0x0f;
+/// Set in the cursor start register to switch the cursor off.
+const CURSOR_DISABLE_BIT: u8 = 0x20;
+
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum CursorShape {
+    Hidden,
+    Underline,
+    Block
+}
+
+impl CursorShape {
+    /// First and last scanline the cursor covers, in a character cell whose
+    /// last scanline is `last`.
+    fn scanlines(self, last: u8) -> (u8, u8) {
+        match self {
+            CursorShape::Hidden => (CURSOR_DISABLE_BIT, 0),
+            CursorShape::Underline => (last - 1, last),
+            CursorShape::Block => (0, last)
+        }
+    }
+}
+
+/// The blinking text-mode cursor, moved and reshaped through the CRTC
+/// registers. Registers are only written when something has changed.
+pub(crate) struct HardwareCursor {
+    shape: Option<CursorShape>,
+    location: Option<usize>,
+    /// The last scanline of a character cell in the current text mode.
+    last_scanline: u8
+}
+
+impl HardwareCursor {
+    pub(crate) fn new() -> Self {
+        Self {
+            shape: None,
+            location: None,
+            last_scanline: 15
+        }
+    }
+
+    /// Fits the cursor to character cells `lines` scanlines tall, after the
+    /// text mode changes.
+    pub(crate) fn set_cell_lines(&mut self, lines: usize) {
+        self.last_scanline = (lines - 1) as u8;
+        self.shape = None;
+    }
+
+    pub(crate) fn hide(&mut self) {
+        self.set_shape(CursorShape::Hidden);
+    }
+
+    pub(crate) fn place(&mut self, shape: CursorShape, col: usize, row: usize) {
+        self.set_shape(shape);
+        let location: usize = row * BUFFER_WIDTH + col;
+        if self.location != Some(location) {
+            self.location = Some(location);
+            write_register(CURSOR_LOCATION_HIGH_REGISTER, (location >> 8) as u8);
+            write_register(CURSOR_LOCATION_LOW_REGISTER, location as u8);
+        }
+    }
+
+    fn set_shape(&mut self, shape: CursorShape) {
+        if self.shape != Some(shape) {
+            self.shape = Some(shape);
+            let (start, end): (u8, u8) = shape.scanlines(self.last_scanline);
+            write_register(CURSOR_START_REGISTER, start);
+            write_register(CURSOR_END_REGISTER, end);
+        }
+    }
+}
+
+fn write_register(register: u8, value: u8) {
+    let mut index: Port<u8> = Port::new(CRTC_INDEX_PORT);
+    let mut data: Port<u8> = Port::new(CRTC_DATA_PORT);
+    unsafe {
+        index.write(register);
+        data.write(value);
+    }
+}
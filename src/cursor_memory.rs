@@ -0,0 +1,44 @@
+use crate::file_name::FileName;
+
+/// Files whose cursor position is kept. Opening another file forgets the
+/// one edited longest ago.
+const REMEMBERED_FILES: usize = 8;
+
+#[derive(Copy, Clone)]
+struct Position {
+    filename: FileName,
+    row: usize,
+    col: usize
+}
+
+/// Where the cursor was when each recently edited file was last closed,
+/// most recently closed first.
+pub(crate) struct CursorMemory {
+    positions: [Option<Position>; REMEMBERED_FILES]
+}
+
+impl CursorMemory {
+    pub(crate) fn new() -> Self {
+        Self {
+            positions: [None; REMEMBERED_FILES]
+        }
+    }
+
+    fn index_of(&self, filename: &str) -> Option<usize> {
+        self.positions.iter().position(|position| {
+            position.is_some_and(|position| position.filename.as_str() == filename)
+        })
+    }
+
+    pub(crate) fn remember(&mut self, filename: FileName, row: usize, col: usize) {
+        let end: usize = self.index_of(filename.as_str()).unwrap_or(REMEMBERED_FILES - 1);
+        self.positions[0..=end].rotate_right(1);
+        self.positions[0] = Some(Position { filename, row, col });
+    }
+
+    /// The row and column `filename` was left at, if it is still remembered.
+    pub(crate) fn recall(&self, filename: &str) -> Option<(usize, usize)> {
+        let position: Position = self.positions[self.index_of(filename)?]?;
+        Some((position.row, position.col))
+    }
+}
@@ -0,0 +1,22 @@
+use crate::SwimDocument;
+
+/// Files every window's disk starts with, embedded from `assets/` at build
+/// time. Add, remove or edit files there and list them here to change what
+/// students see on first boot.
+static DEFAULT_FILES: &[(&str, &[u8])] = &[
+    ("hello", include_bytes!("../assets/hello")),
+    ("nums", include_bytes!("../assets/nums")),
+    ("average", include_bytes!("../assets/average")),
+    ("pi", include_bytes!("../assets/pi"))
+];
+
+/// Writes each default file into `doc`'s filesystem, owned by its window.
+/// A file that cannot be written is left out, and the window shows why.
+pub(crate) fn install(doc: &mut SwimDocument) {
+    for (filename, contents) in DEFAULT_FILES {
+        let owner: usize = doc.window;
+        if let Err(error) = doc.write_file(filename, contents, owner) {
+            doc.error = Some(error.into());
+        }
+    }
+}
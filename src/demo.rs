@@ -0,0 +1,91 @@
+use pc_keyboard::DecodedKey;
+
+/// Frames without a keypress before the demo starts on its own.
+pub(crate) const DEMO_IDLE_FRAMES: usize = 2000;
+
+#[derive(Copy, Clone)]
+pub(crate) enum DemoAction {
+    Focus(usize),
+    Select(&'static str),
+    Key(DecodedKey),
+    Type(&'static str),
+    /// Goes back to the file list, whatever F6 is bound to.
+    Close
+}
+
+struct DemoStep {
+    delay: usize,
+    action: DemoAction
+}
+
+static SCRIPT: [DemoStep; 24] = [
+    DemoStep { delay: 10, action: DemoAction::Focus(0) },
+    DemoStep { delay: 10, action: DemoAction::Select("hello") },
+    DemoStep { delay: 10, action: DemoAction::Key(DecodedKey::Unicode('r')) },
+    DemoStep { delay: 10, action: DemoAction::Focus(1) },
+    DemoStep { delay: 10, action: DemoAction::Select("nums") },
+    DemoStep { delay: 10, action: DemoAction::Key(DecodedKey::Unicode('r')) },
+    DemoStep { delay: 10, action: DemoAction::Focus(2) },
+    DemoStep { delay: 10, action: DemoAction::Select("average") },
+    DemoStep { delay: 10, action: DemoAction::Key(DecodedKey::Unicode('r')) },
+    DemoStep { delay: 10, action: DemoAction::Focus(3) },
+    DemoStep { delay: 10, action: DemoAction::Select("pi") },
+    DemoStep { delay: 10, action: DemoAction::Key(DecodedKey::Unicode('r')) },
+    DemoStep { delay: 60, action: DemoAction::Focus(2) },
+    DemoStep { delay: 10, action: DemoAction::Type("4\n") },
+    DemoStep { delay: 40, action: DemoAction::Type("8\n") },
+    DemoStep { delay: 40, action: DemoAction::Type("quit\n") },
+    DemoStep { delay: 200, action: DemoAction::Focus(0) },
+    DemoStep { delay: 10, action: DemoAction::Close },
+    DemoStep { delay: 10, action: DemoAction::Focus(1) },
+    DemoStep { delay: 10, action: DemoAction::Close },
+    DemoStep { delay: 10, action: DemoAction::Focus(2) },
+    DemoStep { delay: 10, action: DemoAction::Close },
+    DemoStep { delay: 10, action: DemoAction::Focus(3) },
+    DemoStep { delay: 10, action: DemoAction::Close }
+];
+
+/// Plays `SCRIPT` back one action at a time, waiting each step's delay in
+/// frames before handing its action to the manager.
+pub(crate) struct Demo {
+    step: Option<usize>,
+    wait: usize
+}
+
+impl Demo {
+    pub(crate) fn new() -> Self {
+        Self {
+            step: None,
+            wait: 0
+        }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.step.is_some()
+    }
+
+    pub(crate) fn start(&mut self) {
+        self.step = Some(0);
+        self.wait = SCRIPT[0].delay;
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.step = None;
+    }
+
+    /// Call once per frame. Returns the action that has come due, if any.
+    pub(crate) fn next_action(&mut self) -> Option<DemoAction> {
+        let step: usize = self.step?;
+        if self.wait > 0 {
+            self.wait -= 1;
+            return None;
+        }
+        if step + 1 < SCRIPT.len() {
+            self.step = Some(step + 1);
+            self.wait = SCRIPT[step + 1].delay;
+        } else {
+            self.step = None;
+        }
+        Some(SCRIPT[step].action)
+    }
+}
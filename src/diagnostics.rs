@@ -0,0 +1,83 @@
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::render::Screen;
+use crate::{MAX_FILES_STORED, NUM_BLOCKS, WINDOW_WIDTH};
+
+const BLOCK_MAP_TITLE: &str = "blocks (est.) ";
+
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum DiagnosticsPage {
+    BlockMap,
+    OpenFiles,
+    AuditLog,
+    Scheduler,
+    Jobs,
+    Stack,
+    Memory
+}
+
+impl DiagnosticsPage {
+    pub(crate) fn next(self) -> Option<Self> {
+        match self {
+            DiagnosticsPage::BlockMap => Some(DiagnosticsPage::OpenFiles),
+            DiagnosticsPage::OpenFiles => Some(DiagnosticsPage::AuditLog),
+            DiagnosticsPage::AuditLog => Some(DiagnosticsPage::Scheduler),
+            DiagnosticsPage::Scheduler => Some(DiagnosticsPage::Jobs),
+            DiagnosticsPage::Jobs => Some(DiagnosticsPage::Stack),
+            DiagnosticsPage::Stack => Some(DiagnosticsPage::Memory),
+            DiagnosticsPage::Memory => None
+        }
+    }
+}
+
+/// Block usage of each file in directory order. The filesystem does not
+/// expose its allocation bitmap, so files are laid out contiguously from
+/// block 0 using the number of blocks their contents need.
+pub(crate) struct BlockMap {
+    pub(crate) file_blocks: [usize; MAX_FILES_STORED],
+    pub(crate) num_files: usize
+}
+
+impl BlockMap {
+    pub(crate) fn new() -> Self {
+        Self {
+            file_blocks: [0; MAX_FILES_STORED],
+            num_files: 0
+        }
+    }
+
+    fn owner(&self, block: usize) -> Option<usize> {
+        let mut first_block: usize = 0;
+        for file in 0..self.num_files {
+            first_block += self.file_blocks[file];
+            if block < first_block {
+                return Some(file);
+            }
+        }
+        None
+    }
+
+    fn used_blocks(&self) -> usize {
+        self.file_blocks[0..self.num_files].iter().sum()
+    }
+
+    pub(crate) fn draw(&self, screen: &mut dyn Screen, start_col: usize, start_row: usize, rows: usize,
+                       selected_file: usize) {
+        screen.plot_str(BLOCK_MAP_TITLE, start_col, start_row, ColorCode::new(Color::White, Color::Black));
+        screen.plot_num(self.used_blocks() as isize, start_col + BLOCK_MAP_TITLE.len(), start_row,
+                        ColorCode::new(Color::White, Color::Black));
+        for block in 0..NUM_BLOCKS {
+            let row: usize = 1 + block / WINDOW_WIDTH;
+            if row >= rows {
+                break;
+            }
+            let col: usize = block % WINDOW_WIDTH;
+            let (cell, color): (char, ColorCode) = match self.owner(block) {
+                Some(file) if file == selected_file => ('#', ColorCode::new(Color::Yellow, Color::Black)),
+                Some(_) => ('#', ColorCode::new(Color::LightGray, Color::Black)),
+                None => ('.', ColorCode::new(Color::DarkGray, Color::Black))
+            };
+            screen.plot(cell, start_col + col, start_row + row, color);
+        }
+    }
+}
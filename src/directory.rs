@@ -0,0 +1,151 @@
+use file_system_solution::FileSystemError;
+
+use crate::file_name::FileName;
+use crate::name_table::NameTable;
+use crate::{HIDDEN_FILE_PREFIX, MAX_FILES_STORED};
+
+/// Ends each directory in a path. The filesystem itself is flat, so a
+/// directory is just the part of some filenames before a separator, and
+/// goes away with the last file in it.
+pub(crate) const PATH_SEPARATOR: char = '/';
+/// Listed first in every directory below the top one, to go back up.
+const PARENT_ENTRY: &str = "..";
+
+/// The last directory listing that was read successfully, without hidden
+/// files such as the save swap file, or deleted ones. A failed read keeps the previous
+/// listing and holds on to the error until someone reports it.
+pub(crate) struct DirectorySnapshot {
+    files: [FileName; MAX_FILES_STORED],
+    num_files: usize,
+    /// The directory being browsed, ending in a separator, or empty at the
+    /// top.
+    path: FileName,
+    /// What the file grid shows of `path`: the way up, the directories in
+    /// it, and its files.
+    entries: [FileName; MAX_FILES_STORED],
+    num_entries: usize,
+    error: Option<FileSystemError>
+}
+
+impl DirectorySnapshot {
+    pub(crate) fn new() -> Self {
+        Self {
+            files: [FileName::empty(); MAX_FILES_STORED],
+            num_files: 0,
+            path: FileName::empty(),
+            entries: [FileName::empty(); MAX_FILES_STORED],
+            num_entries: 0,
+            error: None
+        }
+    }
+
+    /// Whether `entry` from `entries()` is a directory rather than a file.
+    pub(crate) fn is_directory(entry: &str) -> bool {
+        entry == PARENT_ENTRY || entry.ends_with(PATH_SEPARATOR)
+    }
+
+    /// The directory a file is in, with its separator, or "" at the top.
+    pub(crate) fn directory_of(filename: &str) -> &str {
+        filename.rfind(PATH_SEPARATOR).map_or("", |end| &filename[0..=end])
+    }
+
+    /// Whether `filename` can be created: directories in it must have names.
+    pub(crate) fn is_valid_path(filename: &str) -> bool {
+        !filename.starts_with(PATH_SEPARATOR) && !filename.ends_with(PATH_SEPARATOR) && !filename.contains("//")
+    }
+
+    /// `listing` has the directory entries, which `names` says the names
+    /// of.
+    pub(crate) fn refresh(&mut self, listing: Result<(usize, [FileName; MAX_FILES_STORED]), FileSystemError>,
+                          names: &NameTable) {
+        let (num_files, entries) = match listing {
+            Ok(listing) => listing,
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            }
+        };
+        self.files = [FileName::empty(); MAX_FILES_STORED];
+        self.num_files = 0;
+        for entry in entries.iter().take(num_files) {
+            let file_name: FileName = match names.name_of(*entry) {
+                Some(file_name) => file_name,
+                None => continue
+            };
+            if file_name.as_str().as_bytes().first() != Some(&HIDDEN_FILE_PREFIX) {
+                self.files[self.num_files] = file_name;
+                self.num_files += 1;
+            }
+        }
+        self.list_path();
+    }
+
+    /// Every file, in every directory.
+    pub(crate) fn files(&self) -> (usize, [FileName; MAX_FILES_STORED]) {
+        (self.num_files, self.files)
+    }
+
+    pub(crate) fn entries(&self) -> (usize, [FileName; MAX_FILES_STORED]) {
+        (self.num_entries, self.entries)
+    }
+
+    pub(crate) fn entry(&self, index: usize) -> Option<FileName> {
+        self.entries[0..self.num_entries].get(index).copied()
+    }
+
+    pub(crate) fn path(&self) -> FileName {
+        self.path
+    }
+
+    /// Moves into the directory `entry` names, or up a level for "..".
+    pub(crate) fn enter(&mut self, entry: FileName) {
+        if entry.as_str() == PARENT_ENTRY {
+            let path: &str = self.path.as_str();
+            let parent: &str = Self::directory_of(&path[0..path.len().saturating_sub(1)]);
+            self.path = FileName::truncated(parent);
+        } else if Self::is_directory(entry.as_str()) {
+            self.path = entry;
+        }
+        self.list_path();
+    }
+
+    /// Browses the directory `filename` is in.
+    pub(crate) fn enter_directory_of(&mut self, filename: &str) {
+        self.path = FileName::truncated(Self::directory_of(filename));
+        self.list_path();
+    }
+
+    fn list_path(&mut self) {
+        self.entries = [FileName::empty(); MAX_FILES_STORED];
+        self.num_entries = 0;
+        if !self.path.is_empty() {
+            self.add_entry(FileName::truncated(PARENT_ENTRY));
+        }
+        let files: [FileName; MAX_FILES_STORED] = self.files;
+        for file_name in files.iter().take(self.num_files).copied() {
+            let name: &str = file_name.as_str();
+            let rest: &str = match name.strip_prefix(self.path.as_str()) {
+                Some(rest) => rest,
+                None => continue
+            };
+            let entry: FileName = match rest.find(PATH_SEPARATOR) {
+                Some(end) => FileName::truncated(&name[0..=self.path.len() + end]),
+                None => file_name
+            };
+            if !self.entries[0..self.num_entries].contains(&entry) {
+                self.add_entry(entry);
+            }
+        }
+    }
+
+    fn add_entry(&mut self, entry: FileName) {
+        if self.num_entries < self.entries.len() {
+            self.entries[self.num_entries] = entry;
+            self.num_entries += 1;
+        }
+    }
+
+    pub(crate) fn take_error(&mut self) -> Option<FileSystemError> {
+        self.error.take()
+    }
+}
@@ -0,0 +1,80 @@
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::file_name::FileName;
+use crate::render::Screen;
+use crate::{plot_abbreviated, MAX_OPEN, WINDOW_WIDTH};
+
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum OpenMode {
+    Read,
+    Create
+}
+
+#[derive(Copy, Clone)]
+struct OpenFile {
+    fd: usize,
+    filename: FileName,
+    mode: OpenMode,
+    owner: usize
+}
+
+/// Mirror of the descriptors a filesystem currently has open, recorded as
+/// this crate opens and closes them, along with the window that did so.
+pub(crate) struct OpenFileTable {
+    entries: [Option<OpenFile>; MAX_OPEN]
+}
+
+impl OpenFileTable {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: [None; MAX_OPEN]
+        }
+    }
+
+    pub(crate) fn record_open(&mut self, fd: usize, filename: &str, mode: OpenMode, owner: usize) {
+        let entry: OpenFile = OpenFile {
+            fd,
+            filename: FileName::truncated(filename),
+            mode,
+            owner
+        };
+        if let Some(slot) = self.entries.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(entry);
+        }
+    }
+
+    pub(crate) fn record_close(&mut self, fd: usize) {
+        for slot in self.entries.iter_mut() {
+            if slot.is_some_and(|entry| entry.fd == fd) {
+                *slot = None;
+            }
+        }
+    }
+
+    pub(crate) fn draw(&self, screen: &mut dyn Screen, start_col: usize, start_row: usize, rows: usize) {
+        screen.plot_str("fd file       mode win", start_col, start_row, ColorCode::new(Color::White, Color::Black));
+        let mut row: usize = start_row + 1;
+        for entry in self.entries.iter().flatten() {
+            if row >= start_row + rows {
+                break;
+            }
+            let color: ColorCode = ColorCode::new(Color::LightGray, Color::Black);
+            screen.plot_num(entry.fd as isize, start_col, row, color);
+            plot_abbreviated(screen, entry.filename.as_str(), 10, start_col + 3, row, color);
+            let mode: &str = match entry.mode {
+                OpenMode::Read => "read",
+                OpenMode::Create => "new"
+            };
+            screen.plot_str(mode, start_col + 14, row, color);
+            screen.plot_str("F", start_col + 19, row, color);
+            screen.plot_num(entry.owner as isize + 1, start_col + 20, row, color);
+            row += 1;
+        }
+        while row < start_row + rows {
+            for col in start_col..start_col + WINDOW_WIDTH {
+                screen.plot(' ', col, row, ColorCode::new(Color::Black, Color::Black));
+            }
+            row += 1;
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::file_name::FileName;
+use crate::text_len;
+
+const READ_AHEAD_BYTES: usize = 1024;
+
+/// Holds the bytes of the file highlighted in a window's file list, so that
+/// opening or running it does not have to go back to the filesystem. Files
+/// larger than `READ_AHEAD_BYTES` are never cached.
+pub(crate) struct FileCache {
+    filename: FileName,
+    contents: [u8; READ_AHEAD_BYTES],
+    contents_len: usize,
+    tried: bool,
+    valid: bool
+}
+
+impl FileCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            filename: FileName::empty(),
+            contents: [0; READ_AHEAD_BYTES],
+            contents_len: 0,
+            tried: false,
+            valid: false
+        }
+    }
+
+    fn names(&self, filename: &str) -> bool {
+        self.filename.as_str() == filename
+    }
+
+    /// True once `filename` has been read ahead, even if it turned out too
+    /// large to keep.
+    pub(crate) fn has_tried(&self, filename: &str) -> bool {
+        self.tried && self.names(filename)
+    }
+
+    pub(crate) fn holds(&self, filename: &str) -> bool {
+        self.valid && self.names(filename)
+    }
+
+    pub(crate) fn contents_of(&self, filename: &str) -> Option<&[u8]> {
+        if self.holds(filename) {
+            Some(&self.contents[0..self.contents_len])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the buffer to read `filename` into. The cache stays invalid
+    /// until `finish_fill()` is called.
+    pub(crate) fn start_fill(&mut self, filename: &str) -> &mut [u8] {
+        self.tried = true;
+        self.valid = false;
+        self.filename = FileName::truncated(filename);
+        self.contents = [0; READ_AHEAD_BYTES];
+        &mut self.contents
+    }
+
+    pub(crate) fn finish_fill(&mut self) {
+        self.contents_len = text_len(&self.contents);
+        self.valid = true;
+    }
+
+    pub(crate) fn invalidate(&mut self, filename: &str) {
+        if self.names(filename) {
+            self.tried = false;
+            self.valid = false;
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use file_system_solution::FileSystemError;
+
+use crate::fd_table::{OpenFileTable, OpenMode};
+use crate::SwimFileSystem;
+
+/// An open file descriptor that is closed when the handle is dropped, so an
+/// early return or failed read cannot leak it. Call `finish()` instead of
+/// dropping to find out whether the close succeeded.
+pub(crate) struct FileHandle<'a> {
+    file_system: &'a mut SwimFileSystem,
+    open_files: &'a mut OpenFileTable,
+    fd: usize,
+    closed: bool
+}
+
+impl<'a> FileHandle<'a> {
+    /// Opens the directory entry `entry`, recording it as open under the
+    /// name `filename` its file goes by.
+    pub(crate) fn open(file_system: &'a mut SwimFileSystem, open_files: &'a mut OpenFileTable,
+                       filename: &str, entry: &str, mode: OpenMode, owner: usize) -> Result<Self, FileSystemError> {
+        let fd: usize = match mode {
+            OpenMode::Read => file_system.open_read(entry)?,
+            OpenMode::Create => file_system.open_create(entry)?
+        };
+        open_files.record_open(fd, filename, mode, owner);
+        Ok(Self { file_system, open_files, fd, closed: false })
+    }
+
+    /// Takes back a descriptor previously released with `detach()`.
+    pub(crate) fn resume(file_system: &'a mut SwimFileSystem, open_files: &'a mut OpenFileTable, fd: usize) -> Self {
+        Self { file_system, open_files, fd, closed: false }
+    }
+
+    pub(crate) fn read(&mut self, buffer: &mut [u8]) -> Result<(), FileSystemError> {
+        self.file_system.read(self.fd, buffer)?;
+        Ok(())
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> Result<(), FileSystemError> {
+        self.file_system.write(self.fd, bytes)
+    }
+
+    /// Leaves the descriptor open and returns it, for work that spans several
+    /// frames. The caller must `resume()` and `finish()` it later.
+    pub(crate) fn detach(mut self) -> usize {
+        self.closed = true;
+        self.fd
+    }
+
+    pub(crate) fn finish(mut self) -> Result<(), FileSystemError> {
+        self.close()
+    }
+
+    fn close(&mut self) -> Result<(), FileSystemError> {
+        self.closed = true;
+        self.file_system.close(self.fd)?;
+        self.open_files.record_close(self.fd);
+        Ok(())
+    }
+}
+
+impl Drop for FileHandle<'_> {
+    fn drop(&mut self) {
+        if !self.closed {
+            let _ = self.close();
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use core::fmt;
+use core::str;
+
+use crate::{text_len, MAX_FILENAME_BYTES};
+
+/// A filename in the fixed-size form the filesystem stores. Always valid
+/// UTF-8 and never longer than `MAX_FILENAME_BYTES`.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(crate) struct FileName {
+    bytes: [u8; MAX_FILENAME_BYTES],
+    len: usize
+}
+
+impl FileName {
+    pub(crate) const fn empty() -> Self {
+        Self {
+            bytes: [0; MAX_FILENAME_BYTES],
+            len: 0
+        }
+    }
+
+    /// Keeps as many whole characters of `name` as fit.
+    pub(crate) fn truncated(name: &str) -> Self {
+        let mut file_name: FileName = FileName::empty();
+        for char in name.chars() {
+            if !file_name.push(char) {
+                break;
+            }
+        }
+        file_name
+    }
+
+    /// Reads a NUL-padded directory entry, dropping anything after the first
+    /// NUL or the first invalid UTF-8 sequence. Entries from filesystems with
+    /// a shorter filename limit are accepted as they are.
+    pub(crate) fn from_entry(entry: &[u8]) -> Self {
+        let len: usize = text_len(entry);
+        let valid: &str = match str::from_utf8(&entry[0..len]) {
+            Ok(valid) => valid,
+            Err(error) => str::from_utf8(&entry[0..error.valid_up_to()]).unwrap_or("")
+        };
+        Self::truncated(valid)
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        str::from_utf8(&self.bytes[0..self.len]).unwrap_or("")
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(crate) fn char_count(&self) -> usize {
+        self.as_str().chars().count()
+    }
+
+    /// Appends `char` if it fits, returning whether it did.
+    pub(crate) fn push(&mut self, char: char) -> bool {
+        let char_len: usize = char.len_utf8();
+        if self.len + char_len > MAX_FILENAME_BYTES {
+            return false;
+        }
+        char.encode_utf8(&mut self.bytes[self.len..self.len + char_len]);
+        self.len += char_len;
+        true
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<char> {
+        let last: char = self.as_str().chars().next_back()?;
+        self.len -= last.len_utf8();
+        self.bytes[self.len..].fill(0);
+        Some(last)
+    }
+}
+
+impl fmt::Display for FileName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
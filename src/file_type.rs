@@ -0,0 +1,22 @@
+/// What a file holds, judged from its extension: no extension means a SWIM
+/// program, `.txt`, `.in`, `.out` and numbered backups are text, and
+/// anything else is data.
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum FileType {
+    Program,
+    Text,
+    Data
+}
+
+impl FileType {
+    pub(crate) fn of(filename: &str) -> Self {
+        match filename.rsplit_once('.') {
+            None => FileType::Program,
+            Some((_, "txt")) | Some((_, "in")) | Some((_, "out")) => FileType::Text,
+            Some((_, extension)) if !extension.is_empty() && extension.bytes().all(|b| b.is_ascii_digit()) => {
+                FileType::Text
+            },
+            Some(_) => FileType::Data
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use file_system_solution::FileSystemError;
+
+/// What went wrong, in words meant to be followed by the name of the file
+/// involved: "file not found: avg".
+pub(crate) fn describe(error: &FileSystemError) -> &'static str {
+    match error {
+        FileSystemError::FileNotFound => "file not found",
+        FileSystemError::FileNotOpen => "file not open",
+        FileSystemError::NotOpenForRead => "not open for reading",
+        FileSystemError::NotOpenForWrite => "not open for writing",
+        FileSystemError::TooManyOpen => "too many files open",
+        FileSystemError::TooManyFiles => "too many files",
+        FileSystemError::AlreadyOpen => "already open",
+        FileSystemError::DiskFull => "disk full",
+        FileSystemError::FileTooBig => "file too big",
+        FileSystemError::FilenameTooLong => "filename too long"
+    }
+}
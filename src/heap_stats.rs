@@ -0,0 +1,127 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use gc_headers::{GarbageCollectingHeap, HeapResult, Pointer, Tracer};
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::render::Screen;
+use crate::{MAX_WINDOWS, WINDOW_LABELS};
+
+/// Rows each window's figures take in the task manager.
+pub(crate) const HEAP_ROWS: usize = 3;
+/// Widest number the task manager column has room for after a label.
+const MAX_SHOWN: usize = 999_999;
+
+/// Which window's program the heaps are working for. Interpreters build
+/// their heaps themselves, so a heap cannot be told its window directly.
+static CURRENT_WINDOW: AtomicUsize = AtomicUsize::new(0);
+
+struct Counters {
+    allocations: AtomicUsize,
+    collections: AtomicUsize,
+    live_blocks: AtomicUsize,
+    /// Heap calls that failed since the window's interpreter last ticked.
+    faults: AtomicUsize
+}
+
+static COUNTERS: [Counters; MAX_WINDOWS] = [const {
+    Counters {
+        allocations: AtomicUsize::new(0),
+        collections: AtomicUsize::new(0),
+        live_blocks: AtomicUsize::new(0),
+        faults: AtomicUsize::new(0)
+    }
+}; MAX_WINDOWS];
+
+/// Charges heap activity to `window` until another window is charged.
+/// Called before anything that might run `window`'s interpreter.
+pub(crate) fn charge_to(window: usize) {
+    CURRENT_WINDOW.store(window, Ordering::Relaxed);
+}
+
+/// Zeroes `window`'s figures, for a program starting on a fresh heap.
+pub(crate) fn reset(window: usize) {
+    let counters: &Counters = &COUNTERS[window];
+    counters.allocations.store(0, Ordering::Relaxed);
+    counters.collections.store(0, Ordering::Relaxed);
+    counters.live_blocks.store(0, Ordering::Relaxed);
+    counters.faults.store(0, Ordering::Relaxed);
+}
+
+/// How many heap calls failed for `window` since the last call. A program
+/// whose heap has failed it cannot go on.
+pub(crate) fn take_faults(window: usize) -> usize {
+    COUNTERS[window].faults.swap(0, Ordering::Relaxed)
+}
+
+/// Draws `window`'s allocations, collections, and the blocks found in use
+/// by the latest collection.
+pub(crate) fn draw(screen: &mut dyn Screen, window: usize, col: usize, row: usize) {
+    let counters: &Counters = &COUNTERS[window];
+    let label_color: ColorCode = ColorCode::new(Color::DarkGray, Color::Black);
+    let value_color: ColorCode = ColorCode::new(Color::Pink, Color::Black);
+    let lines: [(&str, usize); HEAP_ROWS] = [
+        ("al", counters.allocations.load(Ordering::Relaxed)),
+        ("gc", counters.collections.load(Ordering::Relaxed)),
+        ("lv", counters.live_blocks.load(Ordering::Relaxed))
+    ];
+    for (i, (label, value)) in lines.iter().enumerate() {
+        screen.plot_str("         ", col, row + i, label_color);
+        let label: &str = if i == 0 { WINDOW_LABELS[window] } else { label };
+        screen.plot_str(label, col, row + i, label_color);
+        screen.plot_num((*value).min(MAX_SHOWN) as isize, col + 3, row + i, value_color);
+    }
+}
+
+/// A heap that counts what the heap inside it does for the current window.
+/// Collections are seen through the tracer, which a heap only calls when
+/// it collects.
+#[derive(Copy, Clone)]
+pub(crate) struct CountingHeap<H> {
+    heap: H
+}
+
+impl<H: GarbageCollectingHeap> GarbageCollectingHeap for CountingHeap<H> {
+    fn new() -> Self {
+        Self { heap: H::new() }
+    }
+
+    fn load(&self, p: Pointer) -> HeapResult<u64> {
+        counted(self.heap.load(p))
+    }
+
+    fn store(&mut self, p: Pointer, value: u64) -> HeapResult<()> {
+        counted(self.heap.store(p, value))
+    }
+
+    fn malloc<T: Tracer>(&mut self, num_words: usize, tracer: &T) -> HeapResult<Pointer> {
+        let pointer: HeapResult<Pointer> = self.heap.malloc(num_words, &CountingTracer { tracer });
+        if pointer.is_ok() {
+            current().allocations.fetch_add(1, Ordering::Relaxed);
+        }
+        counted(pointer)
+    }
+}
+
+/// Notes `result` against the current window if it failed.
+fn counted<T>(result: HeapResult<T>) -> HeapResult<T> {
+    if result.is_err() {
+        current().faults.fetch_add(1, Ordering::Relaxed);
+    }
+    result
+}
+
+struct CountingTracer<'a, T> {
+    tracer: &'a T
+}
+
+impl<T: Tracer> Tracer for CountingTracer<'_, T> {
+    fn trace(&self, blocks_used: &mut [bool]) {
+        self.tracer.trace(blocks_used);
+        let counters: &Counters = current();
+        counters.collections.fetch_add(1, Ordering::Relaxed);
+        counters.live_blocks.store(blocks_used.iter().filter(|&&used| used).count(), Ordering::Relaxed);
+    }
+}
+
+fn current() -> &'static Counters {
+    &COUNTERS[CURRENT_WINDOW.load(Ordering::Relaxed) % MAX_WINDOWS]
+}
@@ -0,0 +1,56 @@
+use core::str;
+
+use crate::MAX_SAVE_BYTES;
+
+/// A script as long as the longest file the editor saves. Anything past
+/// this is dropped, and the program prompts for it as usual.
+const MAX_SCRIPT_BYTES: usize = MAX_SAVE_BYTES;
+
+/// Lines fed to a program in place of typed input, read from the file
+/// "name.in" next to the program "name".
+pub(crate) struct InputScript {
+    bytes: [u8; MAX_SCRIPT_BYTES],
+    len: usize,
+    next: usize
+}
+
+impl InputScript {
+    pub(crate) fn new() -> Self {
+        Self {
+            bytes: [0; MAX_SCRIPT_BYTES],
+            len: 0,
+            next: 0
+        }
+    }
+
+    /// Replaces the script with the lines of `contents`. A line cut short
+    /// by the size limit is dropped with everything after it.
+    pub(crate) fn load(&mut self, contents: &[u8]) {
+        let mut len: usize = contents.len();
+        if len > MAX_SCRIPT_BYTES {
+            len = contents[0..MAX_SCRIPT_BYTES].iter().rposition(|&byte| byte == b'\n').map_or(0, |end| end + 1);
+        }
+        self.bytes[0..len].copy_from_slice(&contents[0..len]);
+        self.len = len;
+        self.next = 0;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+
+    /// The next line without its newline, or `None` once every line has
+    /// been used.
+    pub(crate) fn next_line(&mut self) -> Option<&str> {
+        if self.next >= self.len {
+            return None;
+        }
+        let start: usize = self.next;
+        let end: usize = self.bytes[start..self.len].iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(self.len, |offset| start + offset);
+        self.next = end + 1;
+        Some(str::from_utf8(&self.bytes[start..end]).unwrap_or(""))
+    }
+}
@@ -0,0 +1,16 @@
+use crate::progress::Progress;
+
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum JobStep {
+    Continue,
+    Finished
+}
+
+/// Work too long to finish within one update. The manager calls `step()`
+/// once a frame, each call doing a bounded slice of the work on `C`, and
+/// shows `progress()` while the job is still going.
+pub(crate) trait Job<C> {
+    fn step(&mut self, context: &mut C) -> JobStep;
+
+    fn progress(&self, frame: usize) -> Progress;
+}
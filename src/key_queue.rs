@@ -0,0 +1,114 @@
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use pc_keyboard::DecodedKey;
+
+/// A fixed-size queue of key events with one producer, the keyboard
+/// interrupt, and one consumer, the CPU loop. Neither side ever waits on
+/// the other, so an interrupt can push while the loop is popping.
+pub struct KeyQueue<const N: usize> {
+    slots: [UnsafeCell<Option<DecodedKey>>; N],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    /// Keys pushed while the queue was full, since the consumer last asked.
+    dropped: AtomicUsize
+}
+
+// Only the producer writes the slot at `tail` and only the consumer reads
+// the slot at `head`, and each publishes its side with a release store.
+unsafe impl<const N: usize> Sync for KeyQueue<N> {}
+
+impl<const N: usize> KeyQueue<N> {
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { UnsafeCell::new(None) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0)
+        }
+    }
+
+    /// Adds `key` at the back, or counts it as dropped if the queue is full.
+    ///
+    /// # Safety
+    ///
+    /// Only one context may ever push, such as the keyboard interrupt
+    /// handler, and it must not push again before a push returns.
+    pub unsafe fn push(&self, key: DecodedKey) {
+        let tail: usize = self.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) == N {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        unsafe {
+            *self.slots[tail % N].get() = Some(key);
+        }
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+    }
+
+    /// Takes the key at the front, if there is one.
+    ///
+    /// # Safety
+    ///
+    /// Only one context may ever pop, such as the CPU loop, and it must
+    /// not pop again before a pop returns.
+    pub unsafe fn pop(&self) -> Option<DecodedKey> {
+        let head: usize = self.head.load(Ordering::Relaxed);
+        if head == self.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        let key: Option<DecodedKey> = unsafe { (*self.slots[head % N].get()).take() };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        key
+    }
+
+    /// How many keys were dropped since the last call.
+    pub fn take_dropped(&self) -> usize {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+impl<const N: usize> Default for KeyQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_come_out_in_order_around_the_end() {
+        let queue: KeyQueue<3> = KeyQueue::new();
+        unsafe {
+            for round in 0..4 {
+                let first: char = (b'a' + 2 * round) as char;
+                let second: char = (b'b' + 2 * round) as char;
+                queue.push(DecodedKey::Unicode(first));
+                queue.push(DecodedKey::Unicode(second));
+                assert_eq!(queue.pop(), Some(DecodedKey::Unicode(first)));
+                assert_eq!(queue.pop(), Some(DecodedKey::Unicode(second)));
+                assert_eq!(queue.pop(), None);
+            }
+        }
+        assert_eq!(queue.take_dropped(), 0);
+    }
+
+    #[test]
+    fn full_queue_counts_what_it_drops() {
+        let queue: KeyQueue<2> = KeyQueue::new();
+        unsafe {
+            for key in ['a', 'b', 'c', 'd'] {
+                queue.push(DecodedKey::Unicode(key));
+            }
+            assert_eq!(queue.take_dropped(), 2);
+            assert_eq!(queue.take_dropped(), 0);
+            assert_eq!(queue.pop(), Some(DecodedKey::Unicode('a')));
+            queue.push(DecodedKey::Unicode('e'));
+            assert_eq!(queue.pop(), Some(DecodedKey::Unicode('b')));
+            assert_eq!(queue.pop(), Some(DecodedKey::Unicode('e')));
+            assert_eq!(queue.pop(), None);
+        }
+        assert_eq!(queue.take_dropped(), 0);
+    }
+}
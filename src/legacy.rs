@@ -0,0 +1,34 @@
+use file_system_solution::FileSystemError;
+
+use crate::block_device::BlockDevice;
+use crate::block_fs::BlockFileSystem;
+use crate::file_name::FileName;
+use crate::{text_len, MAX_FILES_STORED};
+
+/// Filename limit of filesystems formatted before names were widened to
+/// `MAX_FILENAME_BYTES`.
+pub(crate) const LEGACY_MAX_FILENAME_BYTES: usize = 10;
+
+/// A filesystem laid out with the old filename limit. Everything else about
+/// the layout is unchanged.
+pub(crate) type LegacyFileSystem<D> = BlockFileSystem<D, LEGACY_MAX_FILENAME_BYTES>;
+
+pub(crate) fn list_files<D: BlockDevice>(legacy: &mut LegacyFileSystem<D>)
+                                         -> Result<(usize, [FileName; MAX_FILES_STORED]), FileSystemError> {
+    let (num_files, entries) = legacy.list_directory()?;
+    let mut files: [FileName; MAX_FILES_STORED] = [FileName::empty(); MAX_FILES_STORED];
+    for (file, entry) in files.iter_mut().zip(entries.iter()).take(num_files) {
+        *file = FileName::from_entry(entry);
+    }
+    Ok((num_files, files))
+}
+
+/// Reads `filename` into `buffer` and returns the length of its contents.
+pub(crate) fn read_file<D: BlockDevice>(legacy: &mut LegacyFileSystem<D>, filename: &str,
+                                        buffer: &mut [u8]) -> Result<usize, FileSystemError> {
+    let fd: usize = legacy.open_read(filename)?;
+    let read: Result<(), FileSystemError> = legacy.read(fd, buffer).map(|_| ());
+    legacy.close(fd)?;
+    read?;
+    Ok(text_len(buffer))
+}
@@ -1,539 +1,3843 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
-use file_system_solution::FileSystem;
+mod ata;
+mod audit;
+mod block_device;
+mod block_fs;
+mod bookmarks;
+mod bounded;
+mod byte_writer;
+mod clipboard;
+mod command;
+mod compression;
+mod cursor;
+mod cursor_memory;
+mod default_files;
+mod demo;
+mod diagnostics;
+mod directory;
+mod fd_table;
+mod file_cache;
+mod file_handle;
+mod file_name;
+mod file_type;
+mod fs_error;
+mod heap_stats;
+mod input_script;
+mod job;
+mod key_queue;
+mod legacy;
+mod line_tools;
+mod marks;
+mod memory_map;
+mod name_table;
+mod mouse;
+mod notifications;
+mod observer;
+mod output;
+mod palette;
+mod picker;
+mod port;
+mod power;
+mod progress;
+mod render;
+mod sandbox;
+mod scheduler;
+mod screen;
+mod search;
+mod scratch;
+mod screensaver;
+mod self_test;
+mod snapshot;
+mod sound;
+mod speaker;
+mod stack;
+mod swim_error;
+mod save_queue;
+mod status;
+mod text_mode;
+mod text_stats;
+mod tutorial;
+mod uart;
+mod transcript_diff;
+
+use file_system_solution::FileSystemError;
 use gc_heap_template::GenerationalHeap;
-use ramdisk::RamDisk;
-use num::Integer;
 use pc_keyboard::{DecodedKey, KeyCode};
-use pluggable_interrupt_os::vga_buffer::{
-    is_drawable, plot, Color, ColorCode, plot_str, plot_num, BUFFER_WIDTH
-};
+use pluggable_interrupt_os::vga_buffer::{is_drawable, Color, ColorCode, BUFFER_WIDTH};
 use core::cmp::min;
+use core::ops::Range;
+use core::fmt::{self, Write};
 use core::str;
-use simple_interp::{Interpreter, InterpreterOutput, ArrayString};
+use simple_interp::{Interpreter, ArrayString};
+use ata::{AtaPartition, AtaPio};
+use audit::{AuditLog, FileOperation};
+use bookmarks::{BookmarkKey, Bookmarks};
+use block_device::{DeviceError, WindowDisk, SECTOR_BYTES};
+use block_fs::{probe, BlockFileSystem, Format};
+use bounded::{Col, Line};
+use byte_writer::ByteWriter;
+use clipboard::Clipboard;
+use cursor::{CursorShape, HardwareCursor};
+use cursor_memory::CursorMemory;
+use demo::{Demo, DemoAction, DEMO_IDLE_FRAMES};
+use diagnostics::{BlockMap, DiagnosticsPage};
+use directory::{DirectorySnapshot, PATH_SEPARATOR};
+use fd_table::{OpenFileTable, OpenMode};
+use file_cache::FileCache;
+use file_handle::FileHandle;
+use file_name::FileName;
+use file_type::FileType;
+use heap_stats::{CountingHeap, HEAP_ROWS};
+use input_script::InputScript;
+use job::{Job, JobStep};
+use legacy::{LegacyFileSystem, LEGACY_MAX_FILENAME_BYTES};
+use line_tools::LineTool;
+use marks::FileMarks;
+use mouse::Mouse;
+use name_table::{NameTable, NAMES_FILENAME, NAME_TABLE_BYTES};
+use notifications::{Notification, Notifications};
+use observer::{Observer, SerialEvents};
+use output::ProgramOutput;
+use palette::{CommandPalette, PaletteAction};
+use picker::{FilePicker, PickerAction};
+pub use command::{Command, Effect};
+pub use key_queue::KeyQueue;
+pub use render::{MemoryScreen, Screen, TextGrid, TextLine, VgaScreen};
+pub use snapshot::{Snapshot, WindowSnapshot};
+pub use stack::paint_stack;
+pub use swim_error::SwimError;
+pub use text_mode::{map_physical_memory, TextMode};
+use render::{Canvas, Region};
+use sandbox::FileOwners;
+use scheduler::{read_time_stamp, FrameBudget, SchedulerStats, DEFAULT_PRIORITY, DEFAULT_QUANTUM, MAX_PRIORITY, MAX_QUANTUM,
+                MIN_QUANTUM, QUANTUM_STEP};
+use screen::ScreenInfo;
+use search::{SearchHit, SearchJob, SearchQuery, SearchResults};
+use scratch::{ScratchBuffer, SCRATCH};
+use screensaver::{Screensaver, SCREENSAVER_IDLE_FRAMES};
+use self_test::SelfTestReport;
+use sound::SoundScheduler;
+use speaker::{Beep, Speaker};
+use save_queue::{PendingSave, SaveJob, SaveQueue, SAVE_TEMP_FILENAME};
+use status::StatusLine;
+use text_stats::TextStats;
+use tutorial::{Highlight, Tutorial, TutorialEvent};
+
+/// One line of the jobs page.
+#[derive(Copy, Clone)]
+enum JobEntry {
+    ActiveSave,
+    QueuedSave(usize),
+    Search
+}
+
+// Size Profiles
+struct SizeProfile {
+    num_windows: usize,
+    num_blocks: usize,
+    max_files_stored: usize
+}
+
+#[cfg(all(feature = "tiny", feature = "big"))]
+compile_error!("the \"tiny\" and \"big\" features cannot both be enabled");
+
+/// Two windows and half the disk, for targets short on memory.
+#[cfg(feature = "tiny")]
+const SIZE_PROFILE: SizeProfile = SizeProfile {
+    num_windows: 2,
+    num_blocks: 127,
+    max_files_stored: 15
+};
+
+#[cfg(not(feature = "tiny"))]
+const SIZE_PROFILE: SizeProfile = SizeProfile {
+    num_windows: 4,
+    num_blocks: 255,
+    max_files_stored: 31
+};
+
+const NUM_WINDOWS: usize = SIZE_PROFILE.num_windows;
+/// Windows are laid out at most two across and three down at their fixed
+/// width, so a manager can have at most this many.
+const MAX_WINDOWS: usize = 6;
 
 // Window Constants
 const WINDOW_WIDTH: usize = (WIN_REGION_WIDTH - 3) / 2;
-const WINDOW_HEIGHT: usize = 10;
-const WINDOW_1_START_COL: usize = 1;
-const WINDOW_1_START_ROW: usize = 2;
-const WINDOW_2_START_COL: usize = 36;
-const WINDOW_2_START_ROW: usize = 2;
-const WINDOW_3_START_COL: usize = 1;
-const WINDOW_3_START_ROW: usize = 14;
-const WINDOW_4_START_COL: usize = 36;
-const WINDOW_4_START_ROW: usize = 14;
+const WINDOW_LABELS: [&str; MAX_WINDOWS] = ["F1", "F2", "F3", "F4", "F5", "F6"];
+/// Frames the task manager's waiting-for-input marker stays on, then off.
+const INPUT_BLINK_FRAMES: usize = 8;
+/// Width programs' printed lines are cut to.
+const OUTPUT_WIDTH: usize = WINDOW_WIDTH;
+/// Where the editor marks the output width. At `WINDOW_WIDTH` the guide is
+/// drawn on the window's right border.
+const DEFAULT_LINE_GUIDE_COL: usize = OUTPUT_WIDTH;
 
 // File System Constants
 const TASK_MANAGER_WIDTH: usize = 10;
 const WIN_REGION_WIDTH: usize = BUFFER_WIDTH - TASK_MANAGER_WIDTH;
 const MAX_OPEN: usize = 16;
 const BLOCK_SIZE: usize = 256;
-const NUM_BLOCKS: usize = 255;
+const NUM_BLOCKS: usize = SIZE_PROFILE.num_blocks;
 const MAX_FILE_BLOCKS: usize = 64;
 const MAX_FILE_BYTES: usize = MAX_FILE_BLOCKS * BLOCK_SIZE;
-const MAX_FILES_STORED: usize = 31;
-const MAX_FILENAME_BYTES: usize = 10;
+const MAX_FILES_STORED: usize = SIZE_PROFILE.max_files_stored;
+const MAX_FILENAME_BYTES: usize = 16;
+/// Sectors in each window's filesystem, as much as the RAM disk it used to
+/// be on held.
+const FS_SECTORS: usize = NUM_BLOCKS * BLOCK_SIZE / SECTOR_BYTES;
+/// Lines the editor holds: enough for a file of `MAX_FILE_BYTES` written
+/// as full lines, each with its newline. Files with more lines are refused.
+const MAX_EDITOR_LINES: usize = MAX_FILE_BYTES.div_ceil(WINDOW_WIDTH + 1);
+const MAX_SAVE_BYTES: usize = MAX_EDITOR_LINES * (WINDOW_WIDTH + 1);
+const _: () = assert!(MAX_FILE_BYTES <= MAX_SAVE_BYTES, "a full file would not fit in the editor");
+const JOURNAL_FILENAME: &str = "audit.log";
+const HIDDEN_FILE_PREFIX: u8 = b'~';
+const MAX_BACKUPS: usize = 2;
+const HEX_BYTES_PER_ROW: usize = 8;
+const FILE_GRID_COL_WIDTH: usize = 10;
+const FILE_GRID_NAME_WIDTH: usize = FILE_GRID_COL_WIDTH - 1;
+const ELLIPSIS: &str = "..";
+const SCROLL_GAP: usize = 3;
+/// Room on the bottom border for " 100% ".
+const SCROLL_PERCENT_WIDTH: usize = 6;
+const SCROLL_FRAMES_PER_STEP: usize = 8;
+const VISUAL_BELL_FRAMES: usize = 6;
+const DEFAULT_FRAMES_PER_REFRESH: usize = 1;
+const MAX_FRAMES_PER_REFRESH: usize = 8;
+const SEARCH_PROMPT: &str = "Search: ";
+const FIND_PROMPT: &str = "Find: ";
+const DELETE_PROMPT: &str = "Delete ";
+const DELETE_PROMPT_END: &str = "? (y/n)";
+const BULK_DELETE_PROMPT: &str = "Delete marked: ";
+/// Room left on the top line for the marked files' names.
+const BULK_DELETE_NAMES_WIDTH: usize = WIN_REGION_WIDTH - BULK_DELETE_PROMPT.len() - DELETE_PROMPT_END.len();
+const SHUTDOWN_PROMPT: &str = "Shut down? (y/n)";
+const DISCARD_PROMPT: &str = "Not saved. Close anyway? (y/n)";
+const SWITCH_PROMPT: &str = "Not saved. Switch anyway? (y/n)";
+/// The same width as the new file prompt, which it replaces when renaming.
+const RENAME_PROMPT: &str = "New name: ";
+const COPY_PROMPT: &str = "Prefix: ";
+const SAVE_MENU_PROMPT: &str = "s=save  a=save as  c=save a copy  F6=save and close";
+const SAVE_AS_PROMPT: &str = "Save as: ";
+const SAVE_COPY_PROMPT: &str = "Copy to: ";
+const FILE_LIST_KEYS: [char; 21] = ['e', 'r', 'p', 'a', 'v', 'u', 'z', 's', 'o', 'd', 'q', '/', 't', 'c', 'w', 'n', 'i',
+                                    'x', ' ', 'y', 'm'];
+/// Transcripts of each window's runs rotate through this many files.
+const TRANSCRIPT_SLOTS: usize = 3;
 
 // Program Execution Constants
-const MAX_TOKENS: usize = 100;
-const MAX_LITERAL_CHARS: usize = 15;
-const STACK_DEPTH: usize = 20;
-const MAX_LOCAL_VARS: usize = 10;
-const HEAP_SIZE: usize = 256;
+struct ProgramLimits {
+    max_tokens: usize,
+    max_literal_chars: usize,
+    stack_depth: usize,
+    max_local_vars: usize,
+    heap_size: usize
+}
+
+#[cfg(all(feature = "small-programs", feature = "large-programs"))]
+compile_error!("the \"small-programs\" and \"large-programs\" features cannot both be enabled");
+
+#[cfg(feature = "small-programs")]
+const PROGRAM_LIMITS: ProgramLimits = ProgramLimits {
+    max_tokens: 50,
+    max_literal_chars: 15,
+    stack_depth: 10,
+    max_local_vars: 5,
+    heap_size: 128
+};
+
+#[cfg(feature = "large-programs")]
+const PROGRAM_LIMITS: ProgramLimits = ProgramLimits {
+    max_tokens: 250,
+    max_literal_chars: 31,
+    stack_depth: 40,
+    max_local_vars: 20,
+    heap_size: 1024
+};
+
+#[cfg(not(any(feature = "small-programs", feature = "large-programs")))]
+const PROGRAM_LIMITS: ProgramLimits = ProgramLimits {
+    max_tokens: 100,
+    max_literal_chars: 15,
+    stack_depth: 20,
+    max_local_vars: 10,
+    heap_size: 256
+};
+
+const MAX_TOKENS: usize = PROGRAM_LIMITS.max_tokens;
+const MAX_LITERAL_CHARS: usize = PROGRAM_LIMITS.max_literal_chars;
+const STACK_DEPTH: usize = PROGRAM_LIMITS.stack_depth;
+const MAX_LOCAL_VARS: usize = PROGRAM_LIMITS.max_local_vars;
+const HEAP_SIZE: usize = PROGRAM_LIMITS.heap_size;
 const MAX_HEAP_BLOCKS: usize = HEAP_SIZE;
+const HEAP_GENERATIONS: usize = 2;
+
+type SwimFileSystem = BlockFileSystem<WindowDisk>;
+type SwimHeap = GenerationalHeap<HEAP_SIZE, MAX_HEAP_BLOCKS, HEAP_GENERATIONS>;
+type SwimInterpreter = Interpreter<MAX_TOKENS, MAX_LITERAL_CHARS, STACK_DEPTH, MAX_LOCAL_VARS, OUTPUT_WIDTH, CountingHeap<SwimHeap>>;
 
-pub struct SwimDocManager {
-    documents: [SwimDocument; 4],
-    interpreters: [Option<Interpreter<MAX_TOKENS, MAX_LITERAL_CHARS, STACK_DEPTH, MAX_LOCAL_VARS, WINDOW_WIDTH, GenerationalHeap<HEAP_SIZE, MAX_HEAP_BLOCKS, 2>>>; 4],
+pub struct SwimDocManager<const N: usize = NUM_WINDOWS, S: Screen = VgaScreen> {
+    documents: [SwimDocument; N],
+    interpreters: [Option<SwimInterpreter>; N],
     active_window: usize,
-    f1_ticks: usize,
-    f2_ticks: usize,
-    f3_ticks: usize,
-    f4_ticks: usize,
+    /// Windows from the bottom of the stack to the top, which is the
+    /// active one. Windows are drawn in this order.
+    z_order: [usize; N],
+    ticks: [usize; N],
     next_tick: usize,
+    /// How many ticks in a row each window's program gets on its turn.
+    priorities: [usize; N],
+    /// Ticks given so far in the current turn.
+    turn_ticks: usize,
     creating_file: bool,
-    new_filename: [char; MAX_FILENAME_BYTES],
-    new_filename_length: usize
+    /// The file being renamed, while the filename prompt is renaming
+    /// instead of creating.
+    renaming: Option<FileName>,
+    confirming_delete: Option<FileName>,
+    /// Set while asking whether to delete every file marked in the active
+    /// window.
+    confirming_bulk_delete: bool,
+    /// Set while the filename prompt is asking for the prefix to copy the
+    /// marked files under.
+    copying_marked: bool,
+    confirming_shutdown: bool,
+    /// Set while F6 is asking how to save the active editor.
+    save_menu: bool,
+    /// Set while the filename prompt is asking where to save the active
+    /// editor.
+    saving_to: Option<SaveMode>,
+    /// Set while asking whether to close an editor whose changes could not
+    /// be saved.
+    confirming_discard: bool,
+    /// The file the active editor switches to if its changes are
+    /// discarded, while asking.
+    switching_to: Option<FileName>,
+    moving_window: bool,
+    /// Where the zoomed window goes back to when the zoom is undone.
+    zoomed: Option<(usize, usize)>,
+    new_filename: FileName,
+    search_query: Option<SearchQuery>,
+    /// Set while the prompt for text to find in the active editor is open.
+    finding: bool,
+    /// The last text looked for in an editor, kept for finding it again.
+    find_query: SearchQuery,
+    clipboard: Clipboard,
+    search_job: Option<SearchJob>,
+    save_queue: SaveQueue,
+    save_job: Option<SaveJob>,
+    status: StatusLine,
+    notifications: Notifications,
+    serial_events: SerialEvents,
+    /// Each window's mode as of the last frame, to spot changes.
+    modes: [WindowStatus; N],
+    file_owners: FileOwners,
+    sandboxed: [bool; N],
+    asserting: [bool; N],
+    verdicts: [Option<bool>; N],
+    transcripts: bool,
+    next_transcript: [usize; N],
+    frame: usize,
+    /// Set while every program and background job is paused.
+    frozen: bool,
+    audit_log: AuditLog,
+    palette: CommandPalette,
+    picker: FilePicker,
+    cursor: HardwareCursor,
+    screen: ScreenInfo,
+    /// Everything is drawn here, and shown on the screen it wraps once a
+    /// frame.
+    canvas: Canvas<S>,
+    tutorial: Tutorial,
+    demo: Demo,
+    idle_frames: usize,
+    bell_frames: usize,
+    frames_per_refresh: usize,
+    /// Most interpreter ticks handed out in one frame.
+    quantum: usize,
+    scheduler_stats: SchedulerStats,
+    screensaver: Screensaver,
+    speaker: Speaker,
+    sound: SoundScheduler,
+    /// Set by `attach_mouse()` if there is a mouse to poll.
+    mouse: Option<Mouse>
 }
 
 pub struct SwimDocument {
-    letters: [[char; WINDOW_WIDTH]; WINDOW_HEIGHT],
+    window: usize,
+    letters: [[char; WINDOW_WIDTH]; MAX_EDITOR_LINES],
     num_letters: usize,
     next_letter: usize,
     start_col: usize,
     start_row: usize,
-    current_row: usize,
-    cursor_position: usize,
+    /// Rows inside the border. Taller than the screen's `window_rows()`
+    /// while zoomed.
+    rows: usize,
+    /// Set for the windows a zoomed window covers. They keep running but
+    /// draw nothing.
+    hidden: bool,
+    current_row: Line,
+    top_line: usize,
+    cursor_position: Col,
     active: bool,
-    file_system: FileSystem<MAX_OPEN, BLOCK_SIZE, NUM_BLOCKS, MAX_FILE_BLOCKS, MAX_FILE_BYTES, MAX_FILES_STORED, MAX_FILENAME_BYTES>,
+    highlighted: bool,
+    flashing: bool,
+    file_system: SwimFileSystem,
+    open_files: OpenFileTable,
     window_status: WindowStatus,
+    /// Where `selected_name` is in the file grid, found again whenever the
+    /// listing is read.
     active_file: usize,
+    /// The highlighted file or directory. The highlight follows the name,
+    /// so files coming and going around it never move it onto another.
+    selected_name: FileName,
     program_running: bool,
-    output_line: usize,
+    output: ProgramOutput,
+    input_script: InputScript,
     array_string: ArrayString<WINDOW_WIDTH>,
-    current_editing_file: [u8; MAX_FILENAME_BYTES],
-    current_editing_file_len: usize,
-    input_row: usize
+    current_editing_file: FileName,
+    /// Set once the editor's text differs from what was last saved.
+    modified: bool,
+    /// Set by one of the editor's Escape chords for the manager to act on.
+    chord: Option<EditorChord>,
+    /// Where the last text found is, as (row, col, len), to highlight it
+    /// until the next key.
+    found: Option<(usize, usize, usize)>,
+    cursor_memory: CursorMemory,
+    bookmarks: Bookmarks,
+    line_guide_col: usize,
+    input_row: usize,
+    read_ahead: FileCache,
+    diagnostics_page: DiagnosticsPage,
+    block_map: BlockMap,
+    directory: DirectorySnapshot,
+    names: NameTable,
+    search_results: SearchResults,
+    error: Option<SwimError>,
+    compare_mark: Option<FileName>,
+    marks: FileMarks,
+    compared: (FileName, FileName)
 }
 
-#[derive(PartialEq)]
+#[derive(Copy, Clone, PartialEq)]
 enum WindowStatus {
     DisplayingFiles,
     EditingFile,
     ExecutingFile,
     AwaitingInput,
-    DisplayingOutput
+    /// A program stopped by 'p' where it was, until 'p' is pressed again.
+    Paused,
+    DisplayingOutput,
+    Diagnostics,
+    ViewingFile,
+    SearchResults,
+    ComparingFiles,
+    Notifications
+}
+
+impl WindowStatus {
+    /// One word for the mode, as written to the serial port.
+    fn name(self) -> &'static str {
+        match self {
+            WindowStatus::DisplayingFiles => "files",
+            WindowStatus::EditingFile => "editing",
+            WindowStatus::ExecutingFile => "running",
+            WindowStatus::AwaitingInput => "input",
+            WindowStatus::Paused => "paused",
+            WindowStatus::DisplayingOutput => "output",
+            WindowStatus::Diagnostics => "diagnostics",
+            WindowStatus::ViewingFile => "viewing",
+            WindowStatus::SearchResults => "search",
+            WindowStatus::ComparingFiles => "comparing",
+            WindowStatus::Notifications => "notifications"
+        }
+    }
+}
+
+/// Where F6's save menu puts the editor's text besides its own file.
+#[derive(Copy, Clone, PartialEq)]
+enum SaveMode {
+    /// Under a new name, which the editor then edits.
+    As,
+    /// Under a new name, leaving the editor on the file it had.
+    Copy
+}
+
+/// What the editor's Escape chords ask of the manager, beyond the
+/// bookmarks.
+#[derive(Copy, Clone, PartialEq)]
+enum EditorChord {
+    /// Escape and `o`: switch to another file.
+    PickFile,
+    /// Escape and `/`: find text in the buffer.
+    Find,
+    /// Escape and `n`: find the same text again.
+    FindNext,
+    /// Escape and `c`: copy the cursor's line to the clipboard.
+    Copy,
+    /// Escape and `x`: move the cursor's line to the clipboard.
+    Cut,
+    /// Escape and `v`: paste the clipboard above the cursor's line.
+    Paste
+}
+
+impl EditorChord {
+    fn of(key: char) -> Option<Self> {
+        match key {
+            'o' => Some(EditorChord::PickFile),
+            '/' => Some(EditorChord::Find),
+            'n' => Some(EditorChord::FindNext),
+            'c' => Some(EditorChord::Copy),
+            'x' => Some(EditorChord::Cut),
+            'v' => Some(EditorChord::Paste),
+            _ => None
+        }
+    }
+}
+
+/// `name` with `prefix` in front, if the two fit in one filename.
+fn prefixed(prefix: FileName, name: FileName) -> Option<FileName> {
+    if prefix.len() + name.len() >= MAX_FILENAME_BYTES {
+        return None;
+    }
+    let mut prefixed: FileName = prefix;
+    for char in name.as_str().chars() {
+        prefixed.push(char);
+    }
+    Some(prefixed)
+}
+
+/// At least as many tokens as the interpreter makes of `program`, so a
+/// program within `MAX_TOKENS` by this count always fits. Each quoted
+/// string counts once, as does each run of letters or of digits. Every
+/// other character counts by itself, so ":=" counts as two.
+fn token_count(program: &str) -> usize {
+    let mut count: usize = 0;
+    let mut run: Option<bool> = None;
+    let mut in_quotes: bool = false;
+    for char in program.chars() {
+        if in_quotes {
+            in_quotes = char != '"';
+            continue;
+        }
+        let letter: bool = char.is_alphabetic() || char == '_';
+        if char.is_whitespace() {
+            run = None;
+        } else if (letter || char.is_ascii_digit()) && run == Some(letter) {
+            continue;
+        } else {
+            count += 1;
+            in_quotes = char == '"';
+            run = if letter || char.is_ascii_digit() { Some(letter) } else { None };
+        }
+    }
+    count
+}
+
+/// Whether the editor holds all of `text`: no more than
+/// `MAX_EDITOR_LINES` lines with anything on them, and none wider than the
+/// window.
+fn fits_editor(text: &str) -> bool {
+    text.split('\n').enumerate().all(|(row, line)| {
+        let width: usize = line.chars().filter(|&char| is_drawable(char)).count();
+        width == 0 || (row < MAX_EDITOR_LINES && width <= WINDOW_WIDTH)
+    })
+}
+
+fn hex_digit(value: u8) -> char {
+    char::from_digit(value as u32, 16).unwrap_or('?')
+}
+
+fn text_len(buffer: &[u8]) -> usize {
+    buffer.iter().position(|&byte| byte == 0).unwrap_or(buffer.len())
+}
+
+/// Plots `text` in at most `width` columns, ending it with an ellipsis when
+/// it has to be cut short.
+fn plot_abbreviated(screen: &mut dyn Screen, text: &str, width: usize, col: usize, row: usize, color: ColorCode) {
+    if text.chars().count() <= width {
+        screen.plot_str(text, col, row, color);
+        return;
+    }
+    let kept: usize = width.saturating_sub(ELLIPSIS.len());
+    for (i, char) in text.chars().take(kept).enumerate() {
+        screen.plot(char, col + i, row, color);
+    }
+    screen.plot_str(ELLIPSIS, col + kept, row, color);
+}
+
+/// Draws `text` in a field `width` cells wide. Text that does not fit
+/// scrolls through the field instead of being cut short.
+fn plot_scrolling(screen: &mut dyn Screen, text: &str, width: usize, col: usize, row: usize, color: ColorCode,
+                  frame: usize) {
+    let text_len: usize = text.chars().count();
+    if text_len <= width {
+        screen.plot_str(text, col, row, color);
+        return;
+    }
+    let loop_len: usize = text_len + SCROLL_GAP;
+    let offset: usize = frame / SCROLL_FRAMES_PER_STEP;
+    for i in 0..width {
+        let char: char = text.chars().nth((offset + i) % loop_len).unwrap_or(' ');
+        screen.plot(char, col + i, row, color);
+    }
+}
+
+/// `filename` with ".suffix" on the end. Long names are shortened so the
+/// suffix always fits.
+fn suffixed_name(filename: &str, suffix: impl fmt::Display) -> FileName {
+    let mut buffer: [u8; MAX_FILENAME_BYTES - 1] = [0; MAX_FILENAME_BYTES - 1];
+    let mut ending: ByteWriter = ByteWriter::new(&mut buffer);
+    let _ = write!(ending, ".{}", suffix);
+    let ending_len: usize = ending.len();
+    let mut base_len: usize = min(filename.len(), MAX_FILENAME_BYTES - 1 - ending_len);
+    while !filename.is_char_boundary(base_len) {
+        base_len -= 1;
+    }
+    let mut name: FileName = FileName::truncated(&filename[0..base_len]);
+    for char in str::from_utf8(&buffer[0..ending_len]).unwrap_or("").chars() {
+        name.push(char);
+    }
+    name
 }
 
-fn safe_add<const LIMIT: usize>(a: usize, b: usize) -> usize {
-    (a + b).mod_floor(&LIMIT)
+/// Transcript `slot` of `window`'s runs: "log1-1.txt", "log1-2.txt", ...
+fn transcript_name(window: usize, slot: usize) -> FileName {
+    let mut buffer: [u8; MAX_FILENAME_BYTES] = [0; MAX_FILENAME_BYTES];
+    let mut name: ByteWriter = ByteWriter::new(&mut buffer);
+    let _ = write!(name, "log{}-{}.txt", window + 1, slot);
+    let len: usize = name.len();
+    FileName::truncated(str::from_utf8(&buffer[0..len]).unwrap_or(""))
 }
 
-fn add1<const LIMIT: usize>(value: usize) -> usize {
-    safe_add::<LIMIT>(value, 1)
+/// The name of backup `generation` of `filename`: "name.1", "name.2", ...
+fn backup_name(filename: &str, generation: usize) -> FileName {
+    suffixed_name(filename, generation)
 }
 
-impl Default for SwimDocManager {
+impl<const N: usize, S: Screen + Default> Default for SwimDocManager<N, S> {
     fn default() -> Self {
+        let () = Self::WINDOW_COUNT_CHECK;
+        let screen: ScreenInfo = ScreenInfo::new(TextMode::Text80x25, N);
         Self {
-            documents: [SwimDocument::new(WINDOW_1_START_COL, WINDOW_1_START_ROW),
-                        SwimDocument::new(WINDOW_2_START_COL, WINDOW_2_START_ROW),
-                        SwimDocument::new(WINDOW_3_START_COL, WINDOW_3_START_ROW),
-                        SwimDocument::new(WINDOW_4_START_COL, WINDOW_4_START_ROW)],
-            interpreters: [None; 4],
+            documents: core::array::from_fn(|window| SwimDocument::new(window, &screen)),
+            interpreters: [None; N],
             active_window: 0,
-            f1_ticks: 0,
-            f2_ticks: 0,
-            f3_ticks: 0,
-            f4_ticks: 0,
+            z_order: core::array::from_fn(|i| (i + 1) % N),
+            ticks: [0; N],
             next_tick: 0,
+            priorities: [DEFAULT_PRIORITY; N],
+            turn_ticks: 0,
             creating_file: false,
-            new_filename: ['\0'; MAX_FILENAME_BYTES],
-            new_filename_length: 0
+            renaming: None,
+            confirming_delete: None,
+            confirming_bulk_delete: false,
+            copying_marked: false,
+            confirming_shutdown: false,
+            save_menu: false,
+            saving_to: None,
+            confirming_discard: false,
+            switching_to: None,
+            moving_window: false,
+            zoomed: None,
+            new_filename: FileName::empty(),
+            search_query: None,
+            finding: false,
+            find_query: SearchQuery::new(),
+            clipboard: Clipboard::new(),
+            search_job: None,
+            save_queue: SaveQueue::new(),
+            save_job: None,
+            status: StatusLine::new(),
+            notifications: Notifications::new(),
+            serial_events: SerialEvents,
+            modes: [WindowStatus::DisplayingFiles; N],
+            file_owners: FileOwners::new(),
+            sandboxed: [false; N],
+            asserting: [false; N],
+            verdicts: [None; N],
+            transcripts: false,
+            next_transcript: [1; N],
+            frame: 0,
+            frozen: false,
+            audit_log: AuditLog::new(),
+            palette: CommandPalette::new(N),
+            picker: FilePicker::new(),
+            cursor: HardwareCursor::new(),
+            screen,
+            canvas: Canvas::new(S::default()),
+            tutorial: Tutorial::new(),
+            demo: Demo::new(),
+            idle_frames: 0,
+            bell_frames: 0,
+            frames_per_refresh: DEFAULT_FRAMES_PER_REFRESH,
+            quantum: DEFAULT_QUANTUM,
+            scheduler_stats: SchedulerStats::new(),
+            screensaver: Screensaver::new(),
+            speaker: Speaker::new(),
+            sound: SoundScheduler::new(),
+            mouse: None
         }
     }
 }
 
-impl SwimDocManager {
+impl<const N: usize, S: Screen> SwimDocManager<N, S> {
+    /// Evaluated when a manager is built, so unsupported window counts fail
+    /// to compile.
+    const WINDOW_COUNT_CHECK: () = assert!(matches!(N, 1 | 2 | 4 | 6), "a manager has 1, 2, 4 or 6 windows");
+
+    /// Looks for a second ATA disk to keep files on across reboots and gives
+    /// each window its own part of it to keep its filesystem on. A part
+    /// already holding a filesystem is mounted in place of the files the
+    /// window booted with; a blank one gets those files. Without a disk,
+    /// files last until the next reboot, as they always have.
+    pub fn attach_disk(&mut self) {
+        let drive: AtaPio = match AtaPio::primary_slave() {
+            Some(drive) => drive,
+            None => return
+        };
+        let mut kept: usize = 0;
+        for window in 0..N {
+            let mounted: Result<(), &'static str> = match drive.partition(window, FS_SECTORS) {
+                Some(partition) => self.mount_partition(window, partition),
+                None => Err(DeviceError::OutOfRange.describe())
+            };
+            match mounted {
+                Ok(()) => kept += 1,
+                Err(reason) => self.report_error(format_args!("F{}: {}, files will not be kept", window + 1, reason))
+            }
+        }
+        if kept > 0 {
+            self.status.notify(format_args!("keeping files on disk for {} windows", kept));
+        }
+    }
+
+    /// Moves `window`'s filesystem onto `partition`. One formatted before
+    /// names were widened has its files imported and is formatted again
+    /// to hold them. Returns what went wrong, in words, if it could not be.
+    fn mount_partition(&mut self, window: usize, mut partition: AtaPartition) -> Result<(), &'static str> {
+        match probe(&mut partition).map_err(|error| error.describe())? {
+            Format::Formatted(MAX_FILENAME_BYTES) => {
+                let file_system: SwimFileSystem = BlockFileSystem::mount(WindowDisk::ata(partition))
+                    .map_err(|error| error.describe())?;
+                self.documents[window].mount(file_system);
+                return Ok(());
+            },
+            Format::Formatted(LEGACY_MAX_FILENAME_BYTES) => {
+                let mut legacy: LegacyFileSystem<AtaPartition> = LegacyFileSystem::mount(partition)
+                    .map_err(|error| error.describe())?;
+                self.import_legacy(window, &mut legacy).map_err(|error| fs_error::describe(&error))?;
+            },
+            Format::Blank => {},
+            Format::Formatted(_) | Format::Other => return Err(DeviceError::InUse.describe())
+        }
+        let file_system: SwimFileSystem = BlockFileSystem::format(WindowDisk::ata(partition))
+            .map_err(|error| error.describe())?;
+        self.documents[window].move_files_to(file_system).map_err(|error| fs_error::describe(&error))
+    }
+
+    /// Copies every file of a filesystem formatted with the old filename
+    /// limit into `window`'s filesystem, returning how many were copied.
+    fn import_legacy(&mut self, window: usize, legacy: &mut LegacyFileSystem<AtaPartition>)
+                     -> Result<usize, FileSystemError> {
+        let (num_files, files) = legacy::list_files(legacy)?;
+        for filename in files.iter().take(num_files) {
+            let mut buffer: ScratchBuffer = SCRATCH.checkout()?;
+            let len: usize = legacy::read_file(legacy, filename.as_str(), &mut buffer)?;
+            self.documents[window].write_file(filename.as_str(), &buffer[0..len], window)?;
+        }
+        self.status.notify(format_args!("F{}: imported {} files from legacy filesystem", window + 1, num_files));
+        Ok(num_files)
+    }
+
+    /// Reports any disk error a window's filesystem ran into. The read or
+    /// write it broke has already failed as `DiskFull`.
+    fn report_disk_faults(&mut self) {
+        for window in 0..N {
+            if let Some(fault) = self.documents[window].file_system.take_fault() {
+                self.report_error(format_args!("F{}: {}", window + 1, fault.describe()));
+            }
+        }
+    }
+
+    /// Looks for a PS/2 mouse to click windows with. Without one, everything
+    /// is done from the keyboard, as it always has been.
+    pub fn attach_mouse(&mut self) {
+        self.mouse = Mouse::attach(self.screen.rows);
+    }
+
+    /// Checks the filesystem, interpreter and heap, reporting PASS or FAIL
+    /// for each on the status line and the serial port. Returns whether
+    /// everything passed.
+    pub fn self_test(&mut self) -> bool {
+        let report: SelfTestReport = SelfTestReport::run();
+        report.write_serial();
+        if report.passed() {
+            self.status.notify(format_args!("{}", report));
+        } else {
+            self.report_error(format_args!("{}", report));
+        }
+        report.passed()
+    }
+
+    /// Advances one frame, then shows whatever changed on screen.
     pub fn update(&mut self) {
-        if self.creating_file {
-            plot_str("Filename: ", 0, 0, ColorCode::new(Color::White, Color::Black));
-            for i in 0..self.new_filename_length {
-                plot(self.new_filename[i], 10 + i, 0, ColorCode::new(Color::White, Color::Black));
+        self.mouse_input();
+        self.draw_frame();
+        self.canvas.set_pointer(self.mouse.as_ref().map(Mouse::cell));
+        self.canvas.flush();
+    }
+
+    /// The screen everything is shown on, as of the last `update()`.
+    pub fn screen(&self) -> &S {
+        self.canvas.screen()
+    }
+
+    /// A click makes the topmost window under the pointer active and, in an
+    /// editor, moves the cursor to the clicked cell. Clicks count as keys
+    /// for the screensaver and are ignored while a prompt is open.
+    fn mouse_input(&mut self) {
+        let (col, row): (usize, usize) = match self.mouse.as_mut().and_then(Mouse::poll) {
+            Some(cell) => cell,
+            None => return
+        };
+        self.idle_frames = 0;
+        if self.screensaver.is_active() {
+            self.screensaver.stop();
+            self.redraw_all();
+            return;
+        }
+        if self.prompt_open() {
+            return;
+        }
+        let clicked: Option<usize> = self.z_order.iter().rev().copied().find(|&window| {
+            let doc: &SwimDocument = &self.documents[window];
+            !doc.hidden && (doc.start_col - 1..=doc.start_col + WINDOW_WIDTH).contains(&col) &&
+                (doc.start_row - 1..=doc.start_row + doc.rows).contains(&row)
+        });
+        if let Some(window) = clicked {
+            if window != self.active_window {
+                self.focus(window);
             }
-            plot(' ', 10 + self.new_filename_length, 0, ColorCode::new(Color::White, Color::White));
+            self.documents[window].click(&mut self.canvas, col, row);
         }
-        for i in 0..self.documents.len() {
-            self.documents[i].active = i == self.active_window;
-            self.documents[i].draw_outline();
-            if self.documents[i].window_status == WindowStatus::DisplayingFiles {
-                self.documents[i].display_files();
+    }
+
+    fn draw_frame(&mut self) {
+        self.frame += 1;
+        self.idle_frames += 1;
+        self.bell_frames = self.bell_frames.saturating_sub(1);
+        self.speaker.update();
+        self.sound.update(&mut self.speaker);
+        if !self.screensaver.is_active() && !self.frozen && self.idle_frames >= SCREENSAVER_IDLE_FRAMES {
+            self.demo.stop();
+            self.screensaver.start(&mut self.canvas);
+        }
+        if self.screensaver.is_active() {
+            self.screensaver.draw(&mut self.canvas, self.frame, self.screen.rows);
+            self.cursor.hide();
+            return;
+        }
+        if !self.demo.is_running() && !self.tutorial.is_running() && !self.frozen &&
+           self.idle_frames >= DEMO_IDLE_FRAMES && self.all_windows_idle() {
+            self.demo.start();
+        }
+        if let Some(action) = self.demo.next_action() {
+            self.run_demo_action(action);
+        }
+        if !self.frozen {
+            self.advance_save_job();
+            self.advance_search_job();
+            self.report_disk_faults();
+        }
+        if self.frame % self.frames_per_refresh == 0 {
+            self.refresh();
+        }
+        if !self.frozen {
+            self.run_programs();
+        }
+        self.observe_modes();
+    }
+
+    /// The subsystems told about changes, in the order they hear of them.
+    fn observers(&mut self) -> [&mut dyn Observer; 4] {
+        [&mut self.notifications, &mut self.audit_log, &mut self.tutorial, &mut self.serial_events]
+    }
+
+    /// Tells the observers about each window whose mode is not what it was
+    /// at the end of the last frame. A change undone within one frame goes
+    /// unnoticed.
+    fn observe_modes(&mut self) {
+        for window in 0..N {
+            let from: WindowStatus = self.modes[window];
+            let to: WindowStatus = self.documents[window].window_status;
+            if to != from {
+                self.modes[window] = to;
+                for observer in self.observers() {
+                    observer.on_mode_change(window, from, to);
+                }
+            }
+        }
+    }
+
+    /// Hands out ticks round-robin until every program has had its share
+    /// for the frame or the frame's time budget runs out.
+    fn run_programs(&mut self) {
+        let budget: FrameBudget = FrameBudget::start();
+        let mut ticks: usize = 0;
+        while ticks < self.quantum && !budget.is_spent() && self.tick_next_program() {
+            ticks += 1;
+        }
+        self.scheduler_stats.record(ticks);
+    }
+
+    /// Redraw the screen only every `frames` frames, from 1 to
+    /// `MAX_FRAMES_PER_REFRESH`. Programs still get their ticks every frame,
+    /// so they run faster when the screen is drawn less.
+    pub fn set_frames_per_refresh(&mut self, frames: usize) {
+        self.frames_per_refresh = frames.clamp(1, MAX_FRAMES_PER_REFRESH);
+    }
+
+    /// Hands out at most `ticks` interpreter ticks a frame, from
+    /// `MIN_QUANTUM` to `MAX_QUANTUM`. A frame still stops ticking early once
+    /// it has used its share of time, so keys are never kept waiting long.
+    pub fn set_quantum(&mut self, ticks: usize) {
+        self.quantum = ticks.clamp(MIN_QUANTUM, MAX_QUANTUM);
+    }
+
+    /// Moves the editor's line-length guide to column `col`, counted from
+    /// the left edge of the window.
+    pub fn set_line_guide(&mut self, col: usize) {
+        for doc in self.documents.iter_mut() {
+            doc.line_guide_col = col.clamp(1, WINDOW_WIDTH);
+        }
+    }
+
+    fn refresh(&mut self) {
+        if self.creating_file {
+            let prompt: &str = if self.renaming.is_some() {
+                RENAME_PROMPT
+            } else if self.saving_to == Some(SaveMode::As) {
+                SAVE_AS_PROMPT
+            } else if self.saving_to == Some(SaveMode::Copy) {
+                SAVE_COPY_PROMPT
+            } else if self.copying_marked {
+                COPY_PROMPT
+            } else {
+                "Filename: "
+            };
+            self.canvas.plot_str(prompt, 0, 0, ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot_str(self.new_filename.as_str(), 10, 0, ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot(' ', 10 + self.new_filename.char_count(), 0, ColorCode::new(Color::Black, Color::Black));
+        } else if let Some(file_name) = &self.confirming_delete {
+            self.canvas.plot_str(DELETE_PROMPT, 0, 0, ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot_str(file_name.as_str(), DELETE_PROMPT.len(), 0,
+                                 ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot_str(DELETE_PROMPT_END, DELETE_PROMPT.len() + file_name.char_count(), 0,
+                                 ColorCode::new(Color::White, Color::Black));
+        } else if self.confirming_bulk_delete {
+            let (buffer, len): ([u8; WIN_REGION_WIDTH], usize) = self.marked_names();
+            self.canvas.plot_str(BULK_DELETE_PROMPT, 0, 0, ColorCode::new(Color::White, Color::Black));
+            plot_abbreviated(&mut self.canvas, str::from_utf8(&buffer[0..len]).unwrap_or(""), BULK_DELETE_NAMES_WIDTH,
+                             BULK_DELETE_PROMPT.len(), 0, ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot_str(DELETE_PROMPT_END, BULK_DELETE_PROMPT.len() + min(len, BULK_DELETE_NAMES_WIDTH), 0,
+                                 ColorCode::new(Color::White, Color::Black));
+        } else if self.confirming_shutdown {
+            self.canvas.plot_str(SHUTDOWN_PROMPT, 0, 0, ColorCode::new(Color::White, Color::Black));
+        } else if self.save_menu {
+            self.canvas.plot_str(SAVE_MENU_PROMPT, 0, 0, ColorCode::new(Color::White, Color::Black));
+        } else if self.confirming_discard {
+            self.canvas.plot_str(self.discard_prompt(), 0, 0, ColorCode::new(Color::White, Color::Black));
+        } else if self.finding {
+            self.canvas.plot_str(FIND_PROMPT, 0, 0, ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot_str(self.find_query.as_str(), FIND_PROMPT.len(), 0,
+                                 ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot(' ', FIND_PROMPT.len() + self.find_query.as_str().len(), 0,
+                             ColorCode::new(Color::Black, Color::Black));
+        } else if let Some(query) = &self.search_query {
+            self.canvas.plot_str(SEARCH_PROMPT, 0, 0, ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot_str(query.as_str(), SEARCH_PROMPT.len(), 0, ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot(' ', SEARCH_PROMPT.len() + query.as_str().len(), 0,
+                             ColorCode::new(Color::Black, Color::Black));
+        } else {
+            self.status.draw(&mut self.canvas);
+            let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
+            let selected: Option<FileName> = active_doc.abbreviated_selection();
+            self.status.draw_marquee(&mut self.canvas, selected.as_ref().map(FileName::as_str), self.frame);
+        }
+        if self.status.is_idle() {
+            self.tutorial.draw(&mut self.canvas);
+        }
+        let highlight: Highlight = self.tutorial.highlight();
+        let z_order: [usize; N] = self.z_order;
+        for i in z_order {
+            if let Some(error) = self.documents[i].directory.take_error() {
+                self.report_error(format_args!("F{}: {}: directory, showing last listing",
+                                                i + 1, fs_error::describe(&error)));
             }
-            if self.documents[i].window_status == WindowStatus::AwaitingInput {
-                self.documents[i].clear_line(self.documents[i].start_row + 1);
-                self.documents[i].draw_current(1);
+            if self.documents[i].hidden {
+                continue;
             }
+            self.documents[i].active = i == self.active_window;
+            self.documents[i].highlighted = highlight == Highlight::ActiveWindow && i == self.active_window;
+            self.documents[i].flashing = self.bell_frames > 0 && i == self.active_window;
+            self.canvas.clip_to(self.documents[i].region());
+            self.draw_window(i);
+            self.canvas.unclip();
+        }
+        self.draw_background_output();
+        self.draw_program_ticks();
+        self.place_cursor();
+    }
+
+    /// Draws window `i`, its border and whatever its mode shows.
+    fn draw_window(&mut self, i: usize) {
+        self.documents[i].draw_outline(&mut self.canvas, self.frame);
+        if self.palette.window() == Some(i) {
+            let doc: &SwimDocument = &self.documents[i];
+            self.palette.draw(&mut self.canvas, &doc.window_status, doc.start_col, doc.start_row, doc.rows);
+            return;
+        }
+        if self.picker.window() == Some(i) {
+            let (num_files, files): (usize, [FileName; MAX_FILES_STORED]) = self.documents[i].list_files();
+            let doc: &SwimDocument = &self.documents[i];
+            self.picker.draw(&mut self.canvas, &files[0..num_files], doc.start_col, doc.start_row, doc.rows);
+            return;
         }
-        let mut running_programs: [usize; 4] = [0; 4];
+        if self.documents[i].window_status == WindowStatus::DisplayingFiles {
+            self.documents[i].display_files(&mut self.canvas);
+        }
+        if self.documents[i].window_status == WindowStatus::Diagnostics {
+            self.draw_diagnostics(i);
+        }
+        if self.documents[i].window_status == WindowStatus::SearchResults {
+            let doc: &SwimDocument = &self.documents[i];
+            doc.search_results.draw(&mut self.canvas, doc.start_col, doc.start_row, doc.rows);
+        }
+        if self.documents[i].window_status == WindowStatus::Notifications {
+            let doc: &SwimDocument = &self.documents[i];
+            self.notifications.draw(&mut self.canvas, doc.start_col, doc.start_row, doc.rows);
+        }
+        if self.documents[i].window_status == WindowStatus::AwaitingInput {
+            self.documents[i].clear_line(&mut self.canvas, self.documents[i].start_row + 1);
+            self.documents[i].draw_current(&mut self.canvas, 1);
+        }
+    }
+
+    /// Shows the latest line from a program running in another window on
+    /// the active window's bottom border, unless it is showing an error.
+    /// With several running, the lowest-numbered window is shown.
+    fn draw_background_output(&mut self) {
+        let active_doc: &SwimDocument = &self.documents[self.active_window];
+        if active_doc.error.is_some() {
+            return;
+        }
+        let running: Option<usize> = (0..N).find(|&i| {
+            i != self.active_window && self.documents[i].program_running &&
+                !self.documents[i].output.last_line().is_empty()
+        });
+        if let Some(window) = running {
+            let mut buffer: [u8; 2 * WINDOW_WIDTH] = [0; 2 * WINDOW_WIDTH];
+            let mut strip: ByteWriter = ByteWriter::new(&mut buffer);
+            let _ = write!(strip, "F{}> {}", window + 1, self.documents[window].output.last_line());
+            let len: usize = strip.len();
+            plot_abbreviated(&mut self.canvas, str::from_utf8(&buffer[0..len]).unwrap_or(""), WINDOW_WIDTH,
+                             active_doc.start_col, active_doc.start_row + active_doc.rows,
+                             ColorCode::new(Color::LightGreen, Color::Black));
+        }
+    }
+
+    /// Returns false if no program was ready to run.
+    fn tick_next_program(&mut self) -> bool {
+        let mut running_programs: [usize; N] = [0; N];
         let mut count: usize = 0;
         for i in 0..self.documents.len() {
             if self.documents[i].program_running &&
-               self.documents[i].window_status != WindowStatus::AwaitingInput {
+               self.documents[i].window_status != WindowStatus::AwaitingInput &&
+               self.documents[i].window_status != WindowStatus::Paused {
                 if count < running_programs.len() {
                     running_programs[count] = i;
                     count += 1;
                 }
             }
         }
-        if count > 0 {
-            let doc_to_tick: usize = running_programs[self.next_tick % count];
-            match doc_to_tick {
-                0 => self.f1_ticks += 1,
-                1 => self.f2_ticks += 1,
-                2 => self.f3_ticks += 1,
-                3 => self.f4_ticks += 1,
-                _ => {}
+        if count == 0 {
+            return false;
+        }
+        let doc_to_tick: usize = running_programs[self.next_tick % count];
+        self.ticks[doc_to_tick] += 1;
+        heap_stats::charge_to(doc_to_tick);
+        self.documents[doc_to_tick].tick(&mut self.canvas, &mut self.interpreters[doc_to_tick]);
+        if let Some(tone) = self.documents[doc_to_tick].output.take_tone() {
+            self.sound.push(tone);
+        }
+        if self.documents[doc_to_tick].window_status == WindowStatus::DisplayingOutput {
+            if let Some(save) = self.documents[doc_to_tick].output.take_capture() {
+                if self.asserting[doc_to_tick] {
+                    self.check_output(doc_to_tick, &save);
+                } else {
+                    self.save_captured_output(doc_to_tick, save);
+                }
             }
-            self.documents[doc_to_tick].tick(&mut self.interpreters[doc_to_tick]);
+            let stopped_early: bool = self.documents[doc_to_tick].error.is_some();
+            if let Some(error) = self.documents[doc_to_tick].error {
+                output::mirror_error(format_args!("F{}: {}", doc_to_tick + 1, error.describe()));
+            }
+            let status: &str = match self.verdicts[doc_to_tick] {
+                _ if stopped_early => "stopped on an error",
+                Some(true) => "passed",
+                Some(false) => "failed",
+                None => "finished"
+            };
+            if let Some(save) = self.documents[doc_to_tick].output.take_transcript(status) {
+                self.save_captured_output(doc_to_tick, save);
+            }
+            let output: &ProgramOutput = &self.documents[doc_to_tick].output;
+            let finished: Notification = Notification::new(doc_to_tick, output.program(), status, output.lines());
+            let watched: bool = doc_to_tick == self.active_window && !self.documents[doc_to_tick].hidden;
+            if !watched {
+                self.status.notify(format_args!("{}", finished));
+            }
+            for observer in self.observers() {
+                observer.on_program_finished(&finished, watched);
+            }
+            self.speaker.beep(if stopped_early { Beep::Error } else { Beep::Finished });
+            self.turn_ticks = self.priorities[doc_to_tick];
+        }
+        self.turn_ticks += 1;
+        if self.turn_ticks >= self.priorities[doc_to_tick] {
+            self.turn_ticks = 0;
             self.next_tick = (self.next_tick + 1) % count;
         }
-        self.draw_program_ticks();
+        true
     }
 
-    pub fn key(&mut self, key: DecodedKey) {
+    /// Puts the hardware cursor wherever typing would go: an underline in
+    /// prompts and program input, a block in the editor, and nothing while
+    /// browsing.
+    fn place_cursor(&mut self) {
         if self.creating_file {
-            self.file_creation_input(key);
+            self.cursor.place(CursorShape::Underline, 10 + self.new_filename.char_count(), 0);
             return;
         }
-        match key {
-            DecodedKey::RawKey(KeyCode::F1) => self.active_window = 0,
-            DecodedKey::RawKey(KeyCode::F2) => self.active_window = 1,
-            DecodedKey::RawKey(KeyCode::F3) => self.active_window = 2,
-            DecodedKey::RawKey(KeyCode::F4) => self.active_window = 3,
-            DecodedKey::RawKey(KeyCode::F5) => {
-                self.creating_file = true;
-                self.new_filename = ['\0'; MAX_FILENAME_BYTES];
-                self.new_filename_length = 0;
-                for col in 0..WIN_REGION_WIDTH {
-                    plot(' ', col, 0, ColorCode::new(Color::Black, Color::Black));
-                }
+        if let Some(file_name) = &self.confirming_delete {
+            self.cursor.place(CursorShape::Underline,
+                              DELETE_PROMPT.len() + file_name.char_count() + DELETE_PROMPT_END.len(), 0);
+            return;
+        }
+        if self.confirming_bulk_delete {
+            let len: usize = min(self.marked_names().1, BULK_DELETE_NAMES_WIDTH);
+            self.cursor.place(CursorShape::Underline, BULK_DELETE_PROMPT.len() + len + DELETE_PROMPT_END.len(), 0);
+            return;
+        }
+        if self.confirming_shutdown {
+            self.cursor.place(CursorShape::Underline, SHUTDOWN_PROMPT.len(), 0);
+            return;
+        }
+        if self.save_menu {
+            self.cursor.place(CursorShape::Underline, SAVE_MENU_PROMPT.len(), 0);
+            return;
+        }
+        if self.confirming_discard {
+            self.cursor.place(CursorShape::Underline, self.discard_prompt().len(), 0);
+            return;
+        }
+        if self.finding {
+            self.cursor.place(CursorShape::Underline, FIND_PROMPT.len() + self.find_query.as_str().len(), 0);
+            return;
+        }
+        if let Some(query) = &self.search_query {
+            self.cursor.place(CursorShape::Underline, SEARCH_PROMPT.len() + query.as_str().len(), 0);
+            return;
+        }
+        if let Some(window) = self.palette.window() {
+            let doc: &SwimDocument = &self.documents[window];
+            self.cursor.place(CursorShape::Underline, doc.start_col + self.palette.cursor_col(), doc.start_row);
+            return;
+        }
+        if let Some(window) = self.picker.window() {
+            let doc: &SwimDocument = &self.documents[window];
+            self.cursor.place(CursorShape::Underline, doc.start_col + self.picker.cursor_col(), doc.start_row);
+            return;
+        }
+        let active_doc: &SwimDocument = &self.documents[self.active_window];
+        match active_doc.window_status {
+            WindowStatus::EditingFile => {
+                self.cursor.place(CursorShape::Block, active_doc.start_col + active_doc.cursor_position.get(),
+                                  active_doc.get_actual_row());
             },
-            DecodedKey::RawKey(KeyCode::F6) => {
-                let mut save: bool = false;
-                let mut filename: [u8; MAX_FILENAME_BYTES] = [0u8; MAX_FILENAME_BYTES];
-                let mut filename_len: usize = 0;
-                let mut buffer: [u8; MAX_FILE_BYTES] = [0; MAX_FILE_BYTES];
-                let mut buffer_position: usize = 0;
-                {
-                    let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
-                    
-                    if active_doc.window_status == WindowStatus::EditingFile && active_doc.current_editing_file_len > 0 {
-                        save = true;
-                        filename_len = active_doc.current_editing_file_len;
-                        for i in 0..filename_len {
-                            filename[i] = active_doc.current_editing_file[i];
-                        }
-                        for row in 0..WINDOW_HEIGHT {
-                            if !active_doc.is_line_empty(row) {
-                                for col in 0..active_doc.get_line_length(row) {
-                                    if buffer_position >= MAX_FILE_BYTES - 2 {
-                                        break;
-                                    }
-                                    buffer[buffer_position] = active_doc.letters[row][col] as u8;
-                                    buffer_position += 1;
-                                }
-                                if buffer_position < MAX_FILE_BYTES - 2 {
-                                    let mut next_non_empty_row: usize = row + 1;
-                                    while next_non_empty_row < WINDOW_HEIGHT && 
-                                        active_doc.is_line_empty(next_non_empty_row) {
-                                        next_non_empty_row += 1;
-                                    }
-                                    if next_non_empty_row < WINDOW_HEIGHT {
-                                        buffer[buffer_position] = b'\n';
-                                        buffer_position += 1;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    active_doc.clear_window();
-                    active_doc.program_running = false;
-                    active_doc.window_status = WindowStatus::DisplayingFiles;
+            WindowStatus::AwaitingInput => {
+                self.cursor.place(CursorShape::Underline, active_doc.start_col + active_doc.cursor_position.get(),
+                                  active_doc.get_actual_row() + 1);
+            },
+            _ => self.cursor.hide()
+        }
+    }
+
+    pub fn key(&mut self, key: DecodedKey) {
+        self.idle_frames = 0;
+        if self.screensaver.is_active() {
+            self.screensaver.stop();
+            self.redraw_all();
+            return;
+        }
+        if self.demo.is_running() {
+            self.demo.stop();
+            self.status.notify(format_args!("demo stopped"));
+            return;
+        }
+        // Keys show their own problems and never fail.
+        let _ = self.execute(Command::from(key));
+    }
+
+    /// Carries out `command`, showing any failure on the status line as
+    /// well as returning it. Keys go through whatever prompt or mode is
+    /// showing, as typed ones do, and always succeed. The other commands
+    /// fail with `WindowBusy` while a prompt is open.
+    pub fn execute(&mut self, command: Command) -> Result<Effect, SwimError> {
+        let done: Result<Effect, SwimError> = self.carry_out(command);
+        if let Err(error) = done {
+            match command.filename() {
+                Some(filename) => self.report_file_error(error, filename),
+                None => self.report_error(format_args!("F{}: {}", self.active_window + 1, error.describe()))
+            }
+        }
+        done
+    }
+
+    fn carry_out(&mut self, command: Command) -> Result<Effect, SwimError> {
+        match command {
+            Command::Key(char) => {
+                self.handle_key(DecodedKey::Unicode(char));
+                Ok(Effect::KeyHandled)
+            },
+            Command::RawKey(code) => {
+                self.handle_key(DecodedKey::RawKey(code));
+                Ok(Effect::KeyHandled)
+            },
+            _ if self.prompt_open() => Err(SwimError::WindowBusy),
+            Command::FocusWindow(window) if window >= N => Err(SwimError::NoSuchWindow),
+            Command::FocusWindow(window) => {
+                self.focus(window);
+                Ok(Effect::Focused(window))
+            },
+            Command::CreateFile(filename) => {
+                if filename.is_empty() || filename.len() >= MAX_FILENAME_BYTES || !filename.chars().all(is_drawable) ||
+                   !DirectorySnapshot::is_valid_path(filename) {
+                    return Err(SwimError::InvalidFilename);
                 }
-                if save {
-                    if let Ok(active_filename) = str::from_utf8(&filename[0..filename_len]) {
-                        let filename: &str = active_filename.trim_matches(char::from(0));
-                        for doc in self.documents.iter_mut() {
-                            if let Ok(fd) = doc.file_system.open_create(filename) {
-                                doc.file_system.write(fd, &buffer[0..buffer_position]).unwrap();
-                                doc.file_system.close(fd).unwrap();
-                            }
-                        }
-                    }
+                self.create_file(FileName::truncated(filename))?;
+                Ok(Effect::FileCreated)
+            },
+            Command::NewFile => {
+                self.prompt_new_file();
+                Ok(Effect::PromptOpened)
+            },
+            Command::Close => Ok(self.close_active()),
+            Command::OpenForEdit(filename) => self.open_by_name(filename, 'e'),
+            Command::Run(filename) => self.open_by_name(filename, 'r'),
+            Command::Save => {
+                if self.documents[self.active_window].window_status != WindowStatus::EditingFile {
+                    return Err(SwimError::InvalidMode);
                 }
+                self.save_editor(self.active_window, false);
+                Ok(Effect::SaveQueued)
             },
-            DecodedKey::Unicode(char) => {
-                let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
-                if active_doc.window_status == WindowStatus::DisplayingFiles {
-                    if char == 'e' {
-                        let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
-                        if active_doc.window_status != WindowStatus::DisplayingFiles {
-                            return;
-                        }
-                        let files: [[u8; 10]; MAX_FILES_STORED] = active_doc.file_system.list_directory().unwrap().1;
-                        active_doc.current_editing_file_len = 0;
-                        for &byte in files[active_doc.active_file].iter() {
-                            if byte == 0 {
-                                break;
-                            }
-                            active_doc.current_editing_file[active_doc.current_editing_file_len] = byte;
-                            active_doc.current_editing_file_len += 1;
-                        }
-                        let file_name: &str = str::from_utf8(&active_doc.current_editing_file[0..active_doc.current_editing_file_len]).unwrap().trim_matches(char::from(0));
-                        let fd: usize = active_doc.file_system.open_read(file_name).unwrap();
-                        let mut buffer: [u8; MAX_FILE_BYTES] = [0; MAX_FILE_BYTES];
-                        active_doc.file_system.read(fd, &mut buffer).unwrap();
-                        let file_content: &str = str::from_utf8(&buffer).unwrap().trim_matches(char::from(0));
-                        active_doc.file_system.close(fd).unwrap();
-                        active_doc.window_status = WindowStatus::EditingFile;
-                        active_doc.clear_window();
-                        for row in 0..WINDOW_HEIGHT {
-                            for col in 0..WINDOW_WIDTH {
-                                active_doc.letters[row][col] = '\0';
-                            }
-                        }
-                        let mut row: usize = 0;
-                        let mut col: usize = 0;
-                        for char in file_content.chars() {
-                            if char == '\n' {
-                                for i in 0..col {
-                                    plot(
-                                        active_doc.letters[row][i],
-                                        active_doc.start_col + i,
-                                        active_doc.start_row + row,
-                                        ColorCode::new(Color::White, Color::Black),
-                                    );
-                                }
-                                row += 1;
-                                col = 0;
-                                if row >= WINDOW_HEIGHT {
-                                    break;
-                                }
-                            } else if is_drawable(char) {
-                                if col < WINDOW_WIDTH {
-                                    active_doc.letters[row][col] = char;
-                                    col += 1;
-                                }
-                            }
-                        }
-                        if row < WINDOW_HEIGHT {
-                            for i in 0..col {
-                                plot(
-                                    active_doc.letters[row][i],
-                                    active_doc.start_col + i,
-                                    active_doc.start_row + row,
-                                    ColorCode::new(Color::White, Color::Black),
-                                );
-                            }
-                        }
-                        active_doc.current_row = 0;
-                        active_doc.cursor_position = 0;
-                        let first_line_length: usize = col;
-                        active_doc.num_letters = first_line_length;
-                        active_doc.next_letter = first_line_length;
-                        plot(' ', 
-                            active_doc.start_col + active_doc.cursor_position,
-                            active_doc.start_row + active_doc.current_row, 
-                            ColorCode::new(Color::White, Color::White));
-                        return;
-                    }
-                    if char == 'r' {
-                        let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
-                        if active_doc.window_status != WindowStatus::DisplayingFiles {
-                            return;
-                        }
-                        if active_doc.window_status == WindowStatus::DisplayingOutput {
-                            active_doc.clear_window();
-                            active_doc.program_running = false;
-                            active_doc.window_status = WindowStatus::DisplayingFiles;
-                            return;
-                        }
-                        let files: [[u8; 10]; MAX_FILES_STORED] = active_doc.file_system.list_directory().unwrap().1;
-                        let file_name: &str = str::from_utf8(&files[active_doc.active_file]).unwrap().trim_matches(char::from(0));
-                        let fd: usize = active_doc.file_system.open_read(file_name.trim()).unwrap();
-                        let mut buffer: [u8; MAX_FILE_BYTES] = [0; MAX_FILE_BYTES];
-                        active_doc.file_system.read(fd, &mut buffer).unwrap();
-                        let file: &str = str::from_utf8(&buffer).unwrap().trim_matches(char::from(0));
-                        active_doc.file_system.close(fd).unwrap();
-                        active_doc.window_status = WindowStatus::ExecutingFile;
-                        active_doc.clear_window();
-                        active_doc.output_line = 0;
-                        active_doc.current_row = 0;
-                        active_doc.cursor_position = 0;
-                        active_doc.num_letters = 0;
-                        active_doc.next_letter = 0;
-                        active_doc.program_running = true;
-                        self.interpreters[self.active_window] = Some(Interpreter::new(file));
-                    }
+            Command::Kill(window) if window >= N => Err(SwimError::NoSuchWindow),
+            Command::Kill(window) => {
+                if !self.documents[window].program_running {
+                    return Err(SwimError::InvalidMode);
                 }
+                self.kill_program(window);
+                Ok(Effect::ProgramKilled)
+            },
+            Command::SetTextMode(mode) => {
+                self.set_text_mode(mode)?;
+                Ok(Effect::TextModeSet)
+            },
+            Command::DumpState => {
+                self.snapshot().write_serial();
+                Ok(Effect::StateDumped)
             }
-            _ => {}
         }
-        self.documents[self.active_window].key(key);
     }
 
-    fn draw_program_ticks(&self) {
-        plot_str("F1", 71, 0, ColorCode::new(Color::White, Color::Black));
-        plot_num(self.f1_ticks as isize, 71, 1, ColorCode::new(Color::White, Color::Black));
-        plot_str("F2", 71, 2, ColorCode::new(Color::White, Color::Black));
-        plot_num(self.f2_ticks as isize, 71, 3, ColorCode::new(Color::White, Color::Black));
-        plot_str("F3", 71, 4, ColorCode::new(Color::White, Color::Black));
-        plot_num(self.f3_ticks as isize, 71, 5, ColorCode::new(Color::White, Color::Black));
-        plot_str("F4", 71, 6, ColorCode::new(Color::White, Color::Black));
-        plot_num(self.f4_ticks as isize, 71, 7, ColorCode::new(Color::White, Color::Black));
+    /// What every window is doing right now, for the serial port or for
+    /// checking from outside.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut snapshot: Snapshot = Snapshot::new(self.active_window, self.frozen, self.zoomed.is_some());
+        for (window, doc) in self.documents.iter().enumerate() {
+            snapshot.add(WindowSnapshot::new(window, doc.window_status.name(), doc.shown_file(),
+                                             (doc.current_row.get(), doc.cursor_position.get()), self.ticks[window],
+                                             doc.program_running));
+        }
+        snapshot
+    }
+
+    /// The text of the whole screen as drawn so far, for checking what is
+    /// shown without reading the VGA buffer.
+    pub fn screen_text(&self) -> TextGrid {
+        self.canvas.text_grid()
+    }
+
+    /// The text shown inside `window`'s border, one line per row. Nothing
+    /// for a hidden window.
+    pub fn window_lines(&self, window: usize) -> impl Iterator<Item = TextLine> + '_ {
+        let doc: &SwimDocument = &self.documents[window];
+        doc.visible_rows().map(move |row| self.canvas.text_line(doc.start_col, row, WINDOW_WIDTH))
+    }
+
+    /// Highlights `filename` in the active window's file list and opens it
+    /// with `key`, 'e' or 'r', as if that key had been pressed.
+    fn open_by_name(&mut self, filename: &str, key: char) -> Result<Effect, SwimError> {
+        let doc: &mut SwimDocument = &mut self.documents[self.active_window];
+        if doc.program_running {
+            return Err(SwimError::WindowBusy);
+        }
+        if doc.window_status != WindowStatus::DisplayingFiles {
+            return Err(SwimError::InvalidMode);
+        }
+        doc.select_file(filename);
+        if doc.selected_file().as_str() != filename {
+            return Err(SwimError::NoSuchFile);
+        }
+        if key == 'e' {
+            self.open_selected(false)
+        } else {
+            self.run_selected(key)
+        }
+    }
+
+    fn kill_program(&mut self, window: usize) {
+        self.stop_program(window, "killed");
+        self.status.notify(format_args!("F{}: program killed", window + 1));
+    }
+
+    /// True while a prompt on the top line, the palette or the file picker
+    /// is taking keys.
+    fn prompt_open(&self) -> bool {
+        self.creating_file || self.confirming_delete.is_some() || self.confirming_bulk_delete || self.confirming_shutdown ||
+            self.save_menu || self.confirming_discard ||
+            self.moving_window || self.finding || self.search_query.is_some() || self.palette.window().is_some() ||
+            self.picker.window().is_some()
+    }
+
+    fn prompt_new_file(&mut self) {
+        self.creating_file = true;
+        self.tutorial.observe(TutorialEvent::PromptOpened);
+        self.new_filename = FileName::empty();
+        self.clear_prompt();
+    }
+
+    /// Asks how to save the active editor, or stops whatever else the
+    /// active window is doing and goes back to its file list.
+    fn close_active(&mut self) -> Effect {
+        if self.documents[self.active_window].window_status == WindowStatus::EditingFile {
+            self.save_menu = true;
+            self.clear_prompt();
+            return Effect::PromptOpened;
+        }
+        self.stop_program(self.active_window, "stopped");
+        self.close_editor(self.active_window);
+        Effect::Closed
+    }
+
+    fn discard_prompt(&self) -> &'static str {
+        if self.switching_to.is_some() { SWITCH_PROMPT } else { DISCARD_PROMPT }
+    }
+
+    fn handle_key(&mut self, key: DecodedKey) {
+        self.documents[self.active_window].error = None;
+        if key == DecodedKey::RawKey(KeyCode::ScrollLock) {
+            self.warm_restart();
+            return;
+        }
+        if self.creating_file {
+            self.file_creation_input(key);
+            return;
+        }
+        if self.confirming_delete.is_some() {
+            self.delete_confirmation_input(key);
+            return;
+        }
+        if self.confirming_bulk_delete {
+            self.confirming_bulk_delete = false;
+            self.clear_prompt();
+            if key == DecodedKey::Unicode('y') {
+                self.delete_marked();
+            } else {
+                self.status.notify(format_args!("marked files kept"));
+            }
+            return;
+        }
+        if self.confirming_shutdown {
+            self.confirming_shutdown = false;
+            self.clear_prompt();
+            if key == DecodedKey::Unicode('y') {
+                self.shut_down();
+            }
+            return;
+        }
+        if self.save_menu {
+            self.save_menu_input(key);
+            return;
+        }
+        if self.confirming_discard {
+            self.confirming_discard = false;
+            self.clear_prompt();
+            let switching_to: Option<FileName> = self.switching_to.take();
+            if key == DecodedKey::Unicode('y') {
+                match switching_to {
+                    Some(file_name) => self.switch_editor(self.active_window, file_name),
+                    None => self.close_editor(self.active_window)
+                }
+            }
+            return;
+        }
+        if self.moving_window {
+            self.move_window_input(key);
+            return;
+        }
+        if self.finding {
+            self.find_input(key);
+            return;
+        }
+        if self.search_query.is_some() {
+            self.search_input(key);
+            return;
+        }
+        if let Some(window) = self.palette.window() {
+            match self.palette.key(key, &self.documents[window].window_status) {
+                PaletteAction::None => {},
+                PaletteAction::Close => {
+                    self.palette.close();
+                    self.documents[window].redraw_contents(&mut self.canvas);
+                },
+                PaletteAction::Execute(command) => {
+                    self.palette.close();
+                    self.documents[window].redraw_contents(&mut self.canvas);
+                    let _ = self.execute(command);
+                }
+            }
+            return;
+        }
+        if let Some(window) = self.picker.window() {
+            let (num_files, files): (usize, [FileName; MAX_FILES_STORED]) = self.documents[window].list_files();
+            match self.picker.key(key, &files[0..num_files]) {
+                PickerAction::None => {},
+                PickerAction::Close => {
+                    self.picker.close();
+                    self.documents[window].redraw_contents(&mut self.canvas);
+                },
+                PickerAction::Open(file_name) => {
+                    self.picker.close();
+                    self.documents[window].redraw_contents(&mut self.canvas);
+                    if self.documents[window].modified {
+                        self.switching_to = Some(file_name);
+                        self.confirming_discard = true;
+                        self.clear_prompt();
+                    } else {
+                        self.switch_editor(window, file_name);
+                    }
+                }
+            }
+            return;
+        }
+        match key {
+            DecodedKey::RawKey(KeyCode::F1) => self.focus(0),
+            DecodedKey::RawKey(KeyCode::F2) => self.focus(1),
+            DecodedKey::RawKey(KeyCode::F3) => self.focus(2),
+            DecodedKey::RawKey(KeyCode::F4) => self.focus(3),
+            DecodedKey::RawKey(KeyCode::F5) if N >= 5 => self.focus(4),
+            DecodedKey::RawKey(KeyCode::F6) if N >= 6 => self.focus(5),
+            DecodedKey::RawKey(KeyCode::F5) => self.prompt_new_file(),
+            DecodedKey::RawKey(KeyCode::F6) => {
+                self.close_active();
+                return;
+            },
+            DecodedKey::RawKey(KeyCode::F8) => {
+                self.sandboxed[self.active_window] = !self.sandboxed[self.active_window];
+                if self.sandboxed[self.active_window] {
+                    self.status.notify(format_args!("F{}: sandbox on", self.active_window + 1));
+                } else {
+                    self.status.notify(format_args!("F{}: sandbox off", self.active_window + 1));
+                }
+            },
+            DecodedKey::RawKey(KeyCode::F9) => {
+                if CommandPalette::supports(&self.documents[self.active_window].window_status) {
+                    self.palette.open(self.active_window);
+                } else {
+                    self.report_error(format_args!("F{}: no commands while a program is running",
+                                                    self.active_window + 1));
+                }
+                return;
+            },
+            DecodedKey::RawKey(KeyCode::PauseBreak) => {
+                self.frozen = !self.frozen;
+                if self.frozen {
+                    self.status.notify(format_args!("frozen, Pause resumes"));
+                } else {
+                    self.status.notify(format_args!("resumed"));
+                }
+                return;
+            },
+            DecodedKey::RawKey(KeyCode::F12) => {
+                self.speaker.toggle();
+                if self.speaker.is_enabled() {
+                    self.status.notify(format_args!("sound on"));
+                } else {
+                    self.status.notify(format_args!("sound off"));
+                }
+                return;
+            },
+            DecodedKey::RawKey(KeyCode::F11) => {
+                if self.all_windows_idle() {
+                    self.demo.start();
+                } else {
+                    self.report_error(format_args!("close every window before starting the demo"));
+                }
+                return;
+            },
+            DecodedKey::RawKey(KeyCode::Insert) => {
+                if self.moving_window {
+                    self.invalid_key();
+                } else {
+                    self.toggle_zoom();
+                }
+                return;
+            },
+            DecodedKey::RawKey(KeyCode::F10) if N >= 5 && !self.tutorial.is_running() => {
+                self.report_error(format_args!("the tutorial uses F5 and F6, which pick windows here"));
+                return;
+            },
+            DecodedKey::RawKey(KeyCode::F10) => {
+                if self.tutorial.is_running() {
+                    self.tutorial.stop();
+                } else {
+                    self.tutorial.start();
+                }
+            },
+            // The keyboard layouts decode Delete as the DEL character.
+            DecodedKey::Unicode('\u{7f}') | DecodedKey::RawKey(KeyCode::Delete)
+                if self.documents[self.active_window].window_status == WindowStatus::DisplayingFiles => {
+                self.ask_delete();
+                return;
+            },
+            DecodedKey::RawKey(KeyCode::F7) => {
+                let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
+                if active_doc.window_status == WindowStatus::DisplayingFiles {
+                    active_doc.open_diagnostics(&mut self.canvas);
+                } else if active_doc.window_status == WindowStatus::Diagnostics {
+                    active_doc.next_diagnostics_page(&mut self.canvas);
+                }
+            },
+            DecodedKey::Unicode('\u{1b}') if self.documents[self.active_window].program_running => {
+                self.kill_program(self.active_window);
+                return;
+            },
+            DecodedKey::Unicode(char) => {
+                let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
+                if active_doc.window_status == WindowStatus::DisplayingFiles {
+                    if char == 'e' || char == 'v' || char == 'r' || char == 'p' || char == 'a' {
+                        let file_name: FileName = active_doc.selected_file();
+                        if file_name.is_empty() {
+                            return;
+                        }
+                        let done: Result<Effect, SwimError> = if char == 'e' || char == 'v' {
+                            self.open_selected(char == 'v')
+                        } else {
+                            self.run_selected(char)
+                        };
+                        if let Err(error) = done {
+                            self.report_file_error(error, file_name.as_str());
+                        }
+                        return;
+                    }
+                    if char == 'u' {
+                        self.restore_backup();
+                        return;
+                    }
+                    if char == 't' {
+                        self.transcripts = !self.transcripts;
+                        if self.transcripts {
+                            self.status.notify(format_args!("transcripts on"));
+                        } else {
+                            self.status.notify(format_args!("transcripts off"));
+                        }
+                        return;
+                    }
+                    if char == 'm' {
+                        output::set_mirroring(!output::is_mirroring());
+                        if output::is_mirroring() {
+                            self.status.notify(format_args!("serial mirror on"));
+                        } else {
+                            self.status.notify(format_args!("serial mirror off"));
+                        }
+                        return;
+                    }
+                    if char == 'c' {
+                        self.compare_selected();
+                        return;
+                    }
+                    if char == 'x' {
+                        self.confirming_shutdown = true;
+                        self.clear_prompt();
+                        return;
+                    }
+                    if char == 'n' {
+                        let doc: &mut SwimDocument = &mut self.documents[self.active_window];
+                        let file_name: FileName = doc.selected_file();
+                        if !file_name.is_empty() {
+                            let path: FileName = doc.directory.path();
+                            self.renaming = Some(file_name);
+                            self.new_filename = FileName::truncated(file_name.as_str().strip_prefix(path.as_str())
+                                                                    .unwrap_or(file_name.as_str()));
+                            self.creating_file = true;
+                            self.clear_prompt();
+                        }
+                        return;
+                    }
+                    if char == '\n' {
+                        let entry: FileName = active_doc.selected_entry();
+                        if DirectorySnapshot::is_directory(entry.as_str()) {
+                            active_doc.directory.enter(entry);
+                            active_doc.highlight(0);
+                            active_doc.clear_window(&mut self.canvas);
+                        } else {
+                            self.invalid_key();
+                        }
+                        return;
+                    }
+                    if char == ' ' {
+                        let file_name: FileName = active_doc.selected_file();
+                        if !file_name.is_empty() {
+                            active_doc.marks.toggle(file_name);
+                        }
+                        return;
+                    }
+                    if char == 'y' {
+                        if self.documents[self.active_window].marks.is_empty() {
+                            self.report_error(format_args!("F{}: mark files with Space first",
+                                                            self.active_window + 1));
+                        } else {
+                            self.copying_marked = true;
+                            self.new_filename = FileName::empty();
+                            self.creating_file = true;
+                            self.clear_prompt();
+                        }
+                        return;
+                    }
+                    if char == 'i' {
+                        let doc: &mut SwimDocument = &mut self.documents[self.active_window];
+                        doc.clear_window(&mut self.canvas);
+                        doc.window_status = WindowStatus::Notifications;
+                        return;
+                    }
+                    if char == 'w' && self.zoomed.is_some() {
+                        self.report_error(format_args!("F{}: unzoom before moving the window",
+                                                        self.active_window + 1));
+                        return;
+                    }
+                    if char == 'w' {
+                        self.moving_window = true;
+                        self.status.notify(format_args!("F{}: arrows move the window, Enter places it",
+                                                        self.active_window + 1));
+                        return;
+                    }
+                    if char == 'z' {
+                        self.toggle_compression();
+                        return;
+                    }
+                    if char == 's' {
+                        self.show_file_stats();
+                        return;
+                    }
+                    if char == '/' {
+                        self.search_query = Some(SearchQuery::new());
+                        self.clear_prompt();
+                        return;
+                    }
+                    if char == 'o' {
+                        self.run_line_tool(LineTool::Sort);
+                        return;
+                    }
+                    if char == 'd' {
+                        self.ask_delete();
+                        return;
+                    }
+                    if char == 'q' {
+                        self.run_line_tool(LineTool::Unique);
+                        return;
+                    }
+                    if !FILE_LIST_KEYS.contains(&char) {
+                        self.invalid_key();
+                    }
+                } else if (active_doc.window_status == WindowStatus::ExecutingFile ||
+                           active_doc.window_status == WindowStatus::Paused) && (char == '+' || char == '-') {
+                    self.change_priority(self.active_window, char == '+');
+                } else if (active_doc.window_status == WindowStatus::ExecutingFile ||
+                           active_doc.window_status == WindowStatus::Paused) && (char == '<' || char == '>') {
+                    self.change_quantum(char == '>');
+                } else if (active_doc.window_status == WindowStatus::ExecutingFile ||
+                           active_doc.window_status == WindowStatus::Paused) && (char == '[' || char == ']') {
+                    self.change_frames_per_refresh(char == ']');
+                } else if active_doc.window_status == WindowStatus::ExecutingFile && char == 'p' {
+                    active_doc.window_status = WindowStatus::Paused;
+                } else if active_doc.window_status == WindowStatus::Paused && char == 'p' {
+                    active_doc.window_status = WindowStatus::ExecutingFile;
+                } else if active_doc.window_status == WindowStatus::Diagnostics &&
+                          active_doc.diagnostics_page == DiagnosticsPage::AuditLog && char == 'j' {
+                    self.flush_journal();
+                } else if active_doc.window_status == WindowStatus::Diagnostics &&
+                          active_doc.diagnostics_page == DiagnosticsPage::Jobs && char.is_ascii_digit() {
+                    self.cancel_job(char as usize - '0' as usize);
+                } else if active_doc.window_status == WindowStatus::Diagnostics && char == 't' {
+                    self.self_test();
+                } else if active_doc.window_status == WindowStatus::ViewingFile && char == 'q' {
+                    active_doc.clear_window(&mut self.canvas);
+                    active_doc.window_status = WindowStatus::DisplayingFiles;
+                } else if active_doc.window_status == WindowStatus::ComparingFiles && char == 'q' {
+                    active_doc.clear_window(&mut self.canvas);
+                    active_doc.window_status = WindowStatus::DisplayingFiles;
+                } else if active_doc.window_status == WindowStatus::Notifications && char == 'q' {
+                    active_doc.clear_window(&mut self.canvas);
+                    active_doc.window_status = WindowStatus::DisplayingFiles;
+                    self.notifications.mark_read();
+                } else if active_doc.window_status == WindowStatus::SearchResults {
+                    if char == '\n' {
+                        if let Err((error, file_name)) = active_doc.open_search_hit(&mut self.canvas) {
+                            self.report_error(format_args!("F{}: {}: {}", self.active_window + 1, error.describe(),
+                                                           file_name));
+                        }
+                        return;
+                    } else if char == 'q' {
+                        active_doc.clear_window(&mut self.canvas);
+                        active_doc.window_status = WindowStatus::DisplayingFiles;
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.route_to_active(key);
+    }
+
+    /// Hands `key` to the active window once nothing above has used it.
+    /// Windows only get keys here, and only the focused one does. Characters
+    /// and Delete reach it only while it takes text, so a key meant for a
+    /// prompt or a mode that has just closed never lands in a buffer.
+    fn route_to_active(&mut self, key: DecodedKey) {
+        let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
+        let edits_text: bool = matches!(key, DecodedKey::Unicode(_) | DecodedKey::RawKey(KeyCode::Delete));
+        if edits_text && !active_doc.accepts_text() {
+            return;
+        }
+        self.canvas.clip_to(active_doc.region());
+        active_doc.key(&mut self.canvas, key);
+        self.canvas.unclip();
+        match active_doc.chord.take() {
+            Some(EditorChord::PickFile) => self.picker.open(self.active_window),
+            Some(EditorChord::Find) => {
+                self.finding = true;
+                self.clear_prompt();
+            },
+            Some(EditorChord::FindNext) if self.find_query.as_str().is_empty() => {
+                self.finding = true;
+                self.clear_prompt();
+            },
+            Some(EditorChord::FindNext) => self.find_in_editor(),
+            Some(EditorChord::Copy) => {
+                let row: usize = active_doc.current_row.get();
+                self.clipboard.copy(active_doc.line(row));
+                self.status.notify(format_args!("F{}: line copied", self.active_window + 1));
+            },
+            Some(EditorChord::Cut) => {
+                let row: usize = active_doc.current_row.get();
+                self.clipboard.copy(active_doc.line(row));
+                active_doc.remove_line(&mut self.canvas);
+            },
+            Some(EditorChord::Paste) => self.paste_line(),
+            None => {}
+        }
+    }
+
+    /// Puts the line on the clipboard into the active editor above the
+    /// cursor's line, whichever window it was copied from.
+    fn paste_line(&mut self) {
+        let doc: &mut SwimDocument = &mut self.documents[self.active_window];
+        match self.clipboard.line() {
+            None => self.report_error(format_args!("F{}: nothing copied to paste", self.active_window + 1)),
+            Some(line) if !doc.insert_line(&mut self.canvas, line) => {
+                self.report_error(format_args!("F{}: no room for another line", self.active_window + 1));
+            },
+            Some(_) => {}
+        }
+    }
+
+    /// Typing goes into the find prompt. Enter looks for it after the
+    /// editor's cursor and Escape gives up. The text is kept, so the prompt
+    /// opens with it next time.
+    fn find_input(&mut self, key: DecodedKey) {
+        match key {
+            DecodedKey::Unicode('\n') => {
+                self.finding = false;
+                self.clear_prompt();
+                self.find_in_editor();
+            },
+            DecodedKey::Unicode('\u{1b}') => {
+                self.finding = false;
+                self.clear_prompt();
+            },
+            DecodedKey::Unicode('\u{8}') => {
+                self.find_query.pop();
+                self.clear_prompt();
+            },
+            DecodedKey::Unicode(char) if is_drawable(char) => {
+                let pushed: bool = self.find_query.push(char);
+                if !pushed {
+                    self.invalid_key();
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Moves the active editor's cursor to the next place the find text
+    /// appears.
+    fn find_in_editor(&mut self) {
+        let query: SearchQuery = self.find_query;
+        let doc: &mut SwimDocument = &mut self.documents[self.active_window];
+        if doc.window_status != WindowStatus::EditingFile || query.as_str().is_empty() {
+            return;
+        }
+        if !doc.find_next(&mut self.canvas, query.as_str()) {
+            self.report_error(format_args!("F{}: {} not found", self.active_window + 1, query.as_str()));
+        }
+    }
+
+    /// Opens the highlighted file in the active window: data files, or any
+    /// file when `view` is set, in the viewer, and the rest in the editor.
+    fn open_selected(&mut self, view: bool) -> Result<Effect, SwimError> {
+        let started: u64 = read_time_stamp();
+        let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
+        let file_name: FileName = active_doc.selected_file();
+        if file_name.is_empty() {
+            return Err(SwimError::NoSuchFile);
+        }
+        if view || FileType::of(file_name.as_str()) == FileType::Data {
+            active_doc.open_viewer(&mut self.canvas, file_name.as_str())?;
+            return Ok(Effect::ViewerOpened);
+        }
+        let (row, col): (usize, usize) = active_doc.cursor_memory.recall(file_name.as_str()).unwrap_or((0, 0));
+        let opened: Result<(), SwimError> = active_doc.open_editor(&mut self.canvas, file_name, row, col);
+        self.scheduler_stats.record_open(started);
+        opened?;
+        self.tutorial.observe(TutorialEvent::EditorOpened);
+        Ok(Effect::EditorOpened)
+    }
+
+    /// Runs the highlighted program in the active window. `key` is 'r' to
+    /// run it, 'p' to also capture its output, or 'a' to check its output
+    /// against the expected output.
+    fn run_selected(&mut self, key: char) -> Result<Effect, SwimError> {
+        let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
+        let file_name: FileName = active_doc.selected_file();
+        if file_name.is_empty() {
+            return Err(SwimError::NoSuchFile);
+        }
+        if key != 'a' && FileType::of(file_name.as_str()) != FileType::Program {
+            return Err(SwimError::NotProgram);
+        }
+        let mut buffer: ScratchBuffer = SCRATCH.checkout()?;
+        let file_len: usize = active_doc.read_file(file_name.as_str(), &mut buffer)?;
+        let file: &str = match str::from_utf8(&buffer[0..file_len]) {
+            Ok(file) => file,
+            Err(_) => {
+                active_doc.error = Some(SwimError::NotText);
+                return Err(SwimError::NotText);
+            }
+        };
+        if token_count(file) > MAX_TOKENS {
+            active_doc.error = Some(SwimError::ProgramTooLong);
+            return Err(SwimError::ProgramTooLong);
+        }
+        let capture_to: Option<FileName> = if key == 'p' || key == 'a' {
+            Some(suffixed_name(file_name.as_str(), "out"))
+        } else {
+            None
+        };
+        self.asserting[self.active_window] = key == 'a';
+        self.verdicts[self.active_window] = None;
+        let transcript_to: Option<FileName> = if self.transcripts {
+            let slot: usize = self.next_transcript[self.active_window];
+            self.next_transcript[self.active_window] = slot % TRANSCRIPT_SLOTS + 1;
+            Some(transcript_name(self.active_window, slot))
+        } else {
+            None
+        };
+        active_doc.window_status = WindowStatus::ExecutingFile;
+        active_doc.clear_window(&mut self.canvas);
+        active_doc.output.start(file_name, capture_to, transcript_to);
+        active_doc.current_row = Line::FIRST;
+        active_doc.top_line = 0;
+        active_doc.cursor_position = Col::FIRST;
+        active_doc.num_letters = 0;
+        active_doc.next_letter = 0;
+        active_doc.program_running = true;
+        heap_stats::charge_to(self.active_window);
+        heap_stats::reset(self.active_window);
+        self.interpreters[self.active_window] = Some(Interpreter::new(file));
+        if active_doc.load_input_script(file_name.as_str()) {
+            self.status.notify(format_args!("F{}: input from {}", self.active_window + 1,
+                                            suffixed_name(file_name.as_str(), "in")));
+        }
+        self.tutorial.observe(TutorialEvent::ProgramStarted);
+        Ok(Effect::ProgramStarted)
+    }
+
+    /// Shows `error` on the status line as "F2: file not found: avg".
+    fn report_file_error(&mut self, error: SwimError, file_name: &str) {
+        self.report_error(format_args!("F{}: {}: {}", self.active_window + 1, error.describe(), file_name));
+    }
+
+    /// The first press marks the highlighted file. The second compares the
+    /// marked file with whichever file is highlighted then.
+    fn compare_selected(&mut self) {
+        let window: usize = self.active_window;
+        let doc: &mut SwimDocument = &mut self.documents[window];
+        let selected: FileName = doc.selected_file();
+        let marked: FileName = match doc.compare_mark.take() {
+            Some(marked) => marked,
+            None => {
+                doc.compare_mark = Some(selected);
+                self.status.notify(format_args!("F{}: compare {} with? press c", window + 1, selected));
+                return;
+            }
+        };
+        if let Err((error, file_name)) = doc.open_comparison(&mut self.canvas, marked, selected) {
+            self.report_error(format_args!("F{}: {}: {}", window + 1, error.describe(), file_name));
+        }
+    }
+
+    fn report_error(&mut self, args: fmt::Arguments) {
+        output::mirror_error(args);
+        self.status.notify(args);
+        self.speaker.beep(Beep::Error);
+    }
+
+    /// Tells the user that `count` keys came in faster than they were
+    /// handled and were lost, so nothing is silently missing from what they
+    /// typed.
+    pub fn keys_dropped(&mut self, count: usize) {
+        self.status.notify(format_args!("{} keys lost, typing too fast", count));
+        self.invalid_key();
+    }
+
+    /// Beeps and flashes the active window's border for a key that does
+    /// nothing in the current mode.
+    fn invalid_key(&mut self) {
+        self.speaker.beep(Beep::InvalidKey);
+        self.bell_frames = VISUAL_BELL_FRAMES;
+    }
+
+    /// Repaints the whole screen from the windows' state. Program output is
+    /// not kept anywhere, so windows showing it come back blank.
+    fn redraw_all(&mut self) {
+        self.canvas.clear();
+        for i in self.z_order {
+            if self.documents[i].hidden {
+                continue;
+            }
+            self.documents[i].draw_outline(&mut self.canvas, self.frame);
+            self.documents[i].redraw_contents(&mut self.canvas);
+        }
+        self.draw_program_ticks();
+    }
+
+    /// True when every window is showing its file list, so the demo can take
+    /// over without throwing away anyone's work.
+    fn all_windows_idle(&self) -> bool {
+        !self.prompt_open() && self.zoomed.is_none() &&
+            self.documents.iter().all(|doc| doc.window_status == WindowStatus::DisplayingFiles)
+    }
+
+    /// Makes `window` active. Managers with fewer windows ignore the keys
+    /// for the missing ones.
+    fn focus(&mut self, window: usize) {
+        if window >= N {
+            self.invalid_key();
+        } else if self.zoomed.is_some() && window != self.active_window {
+            self.toggle_zoom();
+            self.activate(window);
+            self.toggle_zoom();
+        } else {
+            self.activate(window);
+        }
+    }
+
+    /// Grows the active window to the full height of the window area,
+    /// hiding the others, or puts it back where it was. Editor lines and
+    /// program output are a fixed number of columns, so only the height
+    /// changes.
+    fn toggle_zoom(&mut self) {
+        let window: usize = self.active_window;
+        match self.zoomed.take() {
+            Some((col, row)) => {
+                self.documents[window].move_to(col, row);
+                self.documents[window].resize(self.screen.window_rows());
+                for doc in self.documents.iter_mut() {
+                    doc.hide(false);
+                }
+            },
+            None => {
+                let doc: &mut SwimDocument = &mut self.documents[window];
+                self.zoomed = Some((doc.start_col, doc.start_row));
+                let (col, row): (usize, usize) = self.screen.zoomed_origin();
+                doc.move_to(col, row);
+                doc.resize(self.screen.zoomed_rows());
+                for (i, doc) in self.documents.iter_mut().enumerate() {
+                    doc.hide(i != window);
+                }
+            }
+        }
+        self.redraw_all();
+    }
+
+    /// Switches the screen to `mode` and puts every window back in its
+    /// place in the layout, as tall as the mode leaves room for. A zoom is
+    /// undone rather than kept at the old height.
+    fn set_text_mode(&mut self, mode: TextMode) -> Result<(), SwimError> {
+        if !self.canvas.set_rows(mode.rows()) {
+            return Err(SwimError::ModeUnavailable);
+        }
+        self.screen = ScreenInfo::new(mode, N);
+        self.cursor.set_cell_lines(mode.cell_lines());
+        if let Some(mouse) = self.mouse.as_mut() {
+            mouse.set_rows(mode.rows());
+        }
+        self.zoomed = None;
+        for (window, doc) in self.documents.iter_mut().enumerate() {
+            let (col, row): (usize, usize) = self.screen.window_origin(window);
+            doc.move_to(col, row);
+            doc.resize(self.screen.window_rows());
+            doc.hide(false);
+        }
+        self.redraw_all();
+        self.status.notify(format_args!("{}x{} text mode", self.screen.cols, self.screen.rows));
+        Ok(())
+    }
+
+    /// Makes `window` active and puts it on top of the others.
+    fn activate(&mut self, window: usize) {
+        self.active_window = window;
+        if let Some(position) = self.z_order.iter().position(|&i| i == window) {
+            self.z_order[position..].rotate_left(1);
+        }
+    }
+
+    /// Steps aimed at a window this manager does not have end the demo.
+    fn run_demo_action(&mut self, action: DemoAction) {
+        match action {
+            DemoAction::Focus(window) if window >= N => self.demo.stop(),
+            DemoAction::Focus(window) => self.activate(window),
+            DemoAction::Select(filename) => self.documents[self.active_window].select_file(filename),
+            DemoAction::Key(key) => {
+                let _ = self.execute(Command::from(key));
+            },
+            DemoAction::Type(text) => {
+                for char in text.chars() {
+                    let _ = self.execute(Command::Key(char));
+                }
+            },
+            DemoAction::Close => {
+                let _ = self.execute(Command::Close);
+            }
+        }
+    }
+
+    /// Searches one more file, and shows the results once every file has
+    /// been searched. A window that has moved on to something else keeps it.
+    fn advance_search_job(&mut self) {
+        let job: &mut SearchJob = match &mut self.search_job {
+            Some(job) => job,
+            None => return
+        };
+        let window: usize = job.window();
+        if job.step(&mut self.documents[window]) == JobStep::Continue {
+            self.status.notify(format_args!("F{}: searching {}", window + 1, job.progress(self.frame)));
+            return;
+        }
+        if let Some(job) = self.search_job.take() {
+            let doc: &mut SwimDocument = &mut self.documents[window];
+            if doc.window_status == WindowStatus::DisplayingFiles {
+                doc.window_status = WindowStatus::SearchResults;
+            }
+            self.status.notify(format_args!("F{}: {} matches for {}", window + 1, doc.search_results.num_hits(),
+                                            job.query().as_str()));
+        }
+    }
+
+    fn advance_save_job(&mut self) {
+        if self.save_job.is_none() {
+            let save: PendingSave = match self.save_queue.pop() {
+                Some(save) => save,
+                None => return
+            };
+            let mut job: SaveJob = SaveJob::new(save, self.frame);
+            self.open_save_file(&mut job);
+            self.save_job = Some(job);
+        }
+        let finished: bool = match self.save_job.as_mut() {
+            Some(job) => {
+                let chunk_len: usize = job.next_chunk().len();
+                for (i, doc) in self.documents.iter_mut().enumerate() {
+                    if let Some(fd) = job.fds[i] {
+                        let mut file: FileHandle = doc.resume_file(fd);
+                        if file.write(job.next_chunk()).is_err() {
+                            job.failed = true;
+                        }
+                        file.detach();
+                    }
+                }
+                job.written += chunk_len;
+                if !job.is_done() {
+                    self.status.notify(format_args!("F{}: saving {} {}", job.save.window + 1,
+                                                    job.save.filename(), job.progress(self.frame)));
+                }
+                job.is_done() || job.failed
+            },
+            None => false
+        };
+        if finished {
+            if let Some(mut job) = self.save_job.take() {
+                self.close_save_file(&mut job);
+                if !job.failed {
+                    for doc in self.documents.iter_mut() {
+                        if doc.rotate_backups(job.save.filename(), job.save.window).is_err() {
+                            job.failed = true;
+                        }
+                    }
+                }
+                if !job.failed {
+                    for doc in self.documents.iter_mut() {
+                        if doc.switch_in_save(job.save.filename()).is_err() {
+                            job.failed = true;
+                        }
+                    }
+                }
+                for doc in self.documents.iter_mut() {
+                    doc.read_ahead.invalidate(job.save.filename());
+                }
+                if job.failed {
+                    self.unsave_editor(&job.save);
+                    self.report_error(format_args!("F{}: could not save {}", job.save.window + 1, job.save.filename()));
+                } else {
+                    let frame: usize = self.frame;
+                    for observer in self.observers() {
+                        observer.on_file_saved(frame, job.save.window, job.save.filename());
+                    }
+                    self.status.notify(format_args!("F{}: saved {}", job.save.window + 1, job.save.filename()));
+                    let doc: &SwimDocument = &self.documents[job.save.window];
+                    if job.save.closes_editor && doc.window_status == WindowStatus::EditingFile &&
+                       doc.current_editing_file.as_str() == job.save.filename() && !doc.modified {
+                        self.close_editor(job.save.window);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Marks the editor `save` came from as unsaved again, if it is still
+    /// editing that file, so a save that failed or was cancelled does not
+    /// lose its text.
+    fn unsave_editor(&mut self, save: &PendingSave) {
+        let doc: &mut SwimDocument = &mut self.documents[save.window];
+        if doc.window_status == WindowStatus::EditingFile && doc.current_editing_file.as_str() == save.filename() {
+            doc.modified = true;
+        }
+    }
+
+    fn open_save_file(&mut self, job: &mut SaveJob) {
+        for (i, doc) in self.documents.iter_mut().enumerate() {
+            match doc.open_file(SAVE_TEMP_FILENAME, OpenMode::Create, job.save.window) {
+                Ok(file) => job.fds[i] = Some(file.detach()),
+                Err(_) => job.failed = true
+            }
+        }
+    }
+
+    fn close_save_file(&mut self, job: &mut SaveJob) {
+        for (i, doc) in self.documents.iter_mut().enumerate() {
+            if let Some(fd) = job.fds[i].take() {
+                if doc.resume_file(fd).finish().is_err() {
+                    job.failed = true;
+                }
+            }
+        }
+    }
+
+    /// Everything running in the background, in the order the jobs page
+    /// lists it.
+    fn jobs(&self) -> impl Iterator<Item = JobEntry> + '_ {
+        self.save_job.iter().map(|_| JobEntry::ActiveSave)
+            .chain((0..self.save_queue.len()).map(JobEntry::QueuedSave))
+            .chain(self.search_job.iter().map(|_| JobEntry::Search))
+    }
+
+    fn draw_jobs(&mut self, start_col: usize, start_row: usize, rows: usize) {
+        self.canvas.plot_str("jobs, press number to cancel", start_col, start_row,
+                             ColorCode::new(Color::White, Color::Black));
+        for row in 1..rows {
+            for col in start_col..start_col + WINDOW_WIDTH {
+                self.canvas.plot(' ', col, start_row + row, ColorCode::new(Color::Black, Color::Black));
+            }
+            let job: JobEntry = match self.jobs().nth(row - 1) {
+                Some(job) => job,
+                None => continue
+            };
+            let mut buffer: [u8; WINDOW_WIDTH] = [0; WINDOW_WIDTH];
+            let mut line: ByteWriter = ByteWriter::new(&mut buffer);
+            let _ = write!(line, "{} ", row);
+            let _ = match job {
+                JobEntry::ActiveSave => match &self.save_job {
+                    Some(save) => write!(line, "save {} {}%", save.save.filename(), save.progress(self.frame).percent()),
+                    None => Ok(())
+                },
+                JobEntry::QueuedSave(index) => match self.save_queue.get(index) {
+                    Some(save) => write!(line, "save {} queued", save.filename()),
+                    None => Ok(())
+                },
+                JobEntry::Search => match &self.search_job {
+                    Some(search) => write!(line, "search {} {}%", search.query().as_str(),
+                                           search.progress(self.frame).percent()),
+                    None => Ok(())
+                }
+            };
+            let len: usize = line.len();
+            self.canvas.plot_str(str::from_utf8(&buffer[0..len]).unwrap_or(""), start_col, start_row + row,
+                                 ColorCode::new(Color::LightGray, Color::Black));
+        }
+    }
+
+    /// Stops the `number`th job on the jobs page. A save cancelled part way
+    /// leaves the real file as it was.
+    fn cancel_job(&mut self, number: usize) {
+        let listed: Option<JobEntry> = self.jobs().nth(number.wrapping_sub(1));
+        let job: JobEntry = match listed {
+            Some(job) => job,
+            None => {
+                self.invalid_key();
+                return;
+            }
+        };
+        match job {
+            JobEntry::ActiveSave => {
+                if let Some(mut save) = self.save_job.take() {
+                    self.close_save_file(&mut save);
+                    self.unsave_editor(&save.save);
+                    self.status.notify(format_args!("F{}: save of {} cancelled", save.save.window + 1,
+                                                    save.save.filename()));
+                }
+            },
+            JobEntry::QueuedSave(index) => {
+                if let Some(save) = self.save_queue.remove(index) {
+                    self.unsave_editor(&save);
+                    self.status.notify(format_args!("F{}: save of {} cancelled", save.window + 1, save.filename()));
+                }
+            },
+            JobEntry::Search => {
+                if let Some(search) = self.search_job.take() {
+                    self.status.notify(format_args!("F{}: search for {} cancelled", search.window() + 1,
+                                                    search.query().as_str()));
+                }
+            }
+        }
+    }
+
+    /// Queues a save that puts the most recent backup of the highlighted file
+    /// back in place. The version being replaced becomes the new backup.
+    fn restore_backup(&mut self) {
+        let window: usize = self.active_window;
+        let active_doc: &mut SwimDocument = &mut self.documents[window];
+        let file_name: FileName = active_doc.selected_file();
+        if file_name.is_empty() {
+            return;
+        }
+        let backup: FileName = backup_name(file_name.as_str(), 1);
+        let mut save: PendingSave = PendingSave::new(window);
+        save.filename = file_name;
+        let read: Result<Result<(), FileSystemError>, FileSystemError> =
+            active_doc.open_file(backup.as_str(), OpenMode::Read, window).map(|mut file| file.read(&mut save.contents));
+        match read {
+            Ok(Ok(())) => {},
+            Ok(Err(error)) => {
+                self.report_error(format_args!("F{}: {}: {}", window + 1, fs_error::describe(&error), backup));
+                return;
+            },
+            Err(_) => {
+                self.report_error(format_args!("F{}: {} has no backup", window + 1, file_name));
+                return;
+            }
+        }
+        save.contents_len = text_len(&save.contents);
+        if self.save_queue.push(save) {
+            self.status.notify(format_args!("F{}: restoring {} from {}", window + 1, file_name, backup));
+        } else {
+            self.report_error(format_args!("F{}: save queue full, {} not restored", window + 1, file_name));
+        }
+    }
+
+    /// Rewrites the highlighted file compressed if it is stored plain, and
+    /// plain if it is stored compressed.
+    fn toggle_compression(&mut self) {
+        let window: usize = self.active_window;
+        let active_doc: &mut SwimDocument = &mut self.documents[window];
+        let file_name: FileName = active_doc.selected_file();
+        if file_name.is_empty() {
+            return;
+        }
+        let mut stored: [u8; MAX_SAVE_BYTES] = [0; MAX_SAVE_BYTES];
+        let stored_len: usize = match active_doc.read_stored_file(file_name.as_str(), &mut stored) {
+            Ok(len) => len,
+            Err(error) => {
+                self.report_error(format_args!("F{}: {}: {}", window + 1, fs_error::describe(&error), file_name));
+                return;
+            }
+        };
+        let mut save: PendingSave = PendingSave::new(window);
+        save.filename = file_name;
+        let converted: Option<usize> = if stored_len == MAX_SAVE_BYTES {
+            None
+        } else if compression::is_compressed(&stored[0..stored_len]) {
+            compression::decompress(&stored[0..stored_len], &mut save.contents)
+        } else {
+            compression::compress(&stored[0..stored_len], &mut save.contents)
+        };
+        save.contents_len = match converted {
+            Some(len) => len,
+            None => {
+                self.report_error(format_args!("F{}: {} is too large to convert", window + 1, file_name));
+                return;
+            }
+        };
+        if !self.save_queue.push(save) {
+            self.report_error(format_args!("F{}: save queue full, {} not converted", window + 1, file_name));
+        } else if compression::is_compressed(save.contents()) {
+            self.status.notify(format_args!("F{}: compressing {}, {} -> {} bytes", window + 1, file_name,
+                                            stored_len, save.contents_len));
+        } else {
+            self.status.notify(format_args!("F{}: decompressing {}, {} -> {} bytes", window + 1, file_name,
+                                            stored_len, save.contents_len));
+        }
+    }
+
+    fn show_file_stats(&mut self) {
+        let window: usize = self.active_window;
+        let file_name: FileName = self.documents[window].selected_file();
+        if file_name.is_empty() {
+            return;
+        }
+        match self.documents[window].text_stats(file_name.as_str()) {
+            Some(stats) => self.status.notify(format_args!("F{}: {}: {} lines, {} words, {} chars, {} bytes",
+                                                           window + 1, file_name, stats.lines(), stats.words,
+                                                           stats.chars, stats.bytes)),
+            None => self.report_error(format_args!("F{}: could not read {}", window + 1, file_name))
+        }
+    }
+
+    /// Writes the sorted or deduplicated lines of the highlighted file to a
+    /// new file next to it, in every window's filesystem.
+    fn run_line_tool(&mut self, tool: LineTool) {
+        let window: usize = self.active_window;
+        let file_name: FileName = self.documents[window].selected_file();
+        if file_name.is_empty() {
+            return;
+        }
+        let (mut input, mut output): (ScratchBuffer, ScratchBuffer) = match (SCRATCH.checkout(), SCRATCH.checkout()) {
+            (Ok(input), Ok(output)) => (input, output),
+            (Err(error), _) | (_, Err(error)) => {
+                self.report_error(format_args!("F{}: {}: {}", window + 1, fs_error::describe(&error), file_name));
+                return;
+            }
+        };
+        let input_len: usize = match self.documents[window].read_file(file_name.as_str(), &mut input) {
+            Ok(len) => len,
+            Err(error) => {
+                self.report_file_error(error, file_name.as_str());
+                return;
+            }
+        };
+        let (num_lines, output_len): (usize, usize) = match line_tools::apply(tool, &input[0..input_len], &mut output) {
+            Some(result) => result,
+            None => {
+                self.report_error(format_args!("F{}: {} has too many lines", window + 1, file_name));
+                return;
+            }
+        };
+        let target: FileName = suffixed_name(file_name.as_str(), tool.suffix());
+        if !self.may_write(window, target.as_str()) {
+            self.report_error(format_args!("F{}: sandboxed, {} belongs to another window", window + 1, target));
+            return;
+        }
+        for doc in self.documents.iter_mut() {
+            if let Err(error) = doc.write_file(target.as_str(), &output[0..output_len], window) {
+                self.report_error(format_args!("F{}: {}: {}", window + 1, fs_error::describe(&error), target));
+                return;
+            }
+        }
+        self.file_owners.record(target.as_str(), window);
+        self.audit_log.record(self.frame, window, FileOperation::Create, target.as_str());
+        self.status.notify(format_args!("F{}: wrote {} lines to {}", window + 1, num_lines, target));
+    }
+
+    fn draw_diagnostics(&mut self, window: usize) {
+        let doc: &SwimDocument = &self.documents[window];
+        if doc.diagnostics_page == DiagnosticsPage::AuditLog {
+            self.audit_log.draw(&mut self.canvas, doc.start_col, doc.start_row, doc.rows);
+        } else if doc.diagnostics_page == DiagnosticsPage::Scheduler {
+            self.scheduler_stats.draw(&mut self.canvas, doc.start_col, doc.start_row, doc.rows, self.quantum,
+                                      self.frames_per_refresh);
+        } else if doc.diagnostics_page == DiagnosticsPage::Jobs {
+            self.draw_jobs(doc.start_col, doc.start_row, doc.rows);
+        } else if doc.diagnostics_page == DiagnosticsPage::Stack {
+            stack::draw_usage(&mut self.canvas, doc.start_col, doc.start_row, doc.rows);
+        } else if doc.diagnostics_page == DiagnosticsPage::Memory {
+            memory_map::draw_sizes(&mut self.canvas, doc.start_col, doc.start_row, doc.rows);
+        } else {
+            doc.draw_diagnostics(&mut self.canvas);
+        }
+    }
+
+    fn flush_journal(&mut self) {
+        let mut save: PendingSave = PendingSave::new(self.active_window);
+        save.set_filename(JOURNAL_FILENAME);
+        save.contents_len = self.audit_log.write_journal(&mut save.contents);
+        if !self.save_queue.push(save) {
+            self.report_error(format_args!("F{}: save queue full, {} not saved",
+                                            self.active_window + 1, JOURNAL_FILENAME));
+        }
+    }
+
+    /// Loads `file_name` into `window`'s editor in place of its file,
+    /// dropping any unsaved changes. Each file's cursor is remembered, as it
+    /// is when going through the file list.
+    fn switch_editor(&mut self, window: usize, file_name: FileName) {
+        let doc: &mut SwimDocument = &mut self.documents[window];
+        doc.cursor_memory.remember(doc.current_editing_file, doc.current_row.get(), doc.cursor_position.get());
+        let (row, col): (usize, usize) = doc.cursor_memory.recall(file_name.as_str()).unwrap_or((0, 0));
+        match doc.open_editor(&mut self.canvas, file_name, row, col) {
+            Ok(()) => self.status.notify(format_args!("F{}: editing {}", window + 1, file_name)),
+            Err(error) => self.report_error(format_args!("F{}: {}: {}", window + 1, error.describe(), file_name))
+        }
+    }
+
+    /// Returns `window` to its file list, dropping any unsaved changes.
+    fn close_editor(&mut self, window: usize) {
+        let doc: &mut SwimDocument = &mut self.documents[window];
+        doc.modified = false;
+        doc.clear_window(&mut self.canvas);
+        doc.window_status = WindowStatus::DisplayingFiles;
+    }
+
+    /// F6 in the editor: `s` saves and keeps editing, `a` and `c` ask for
+    /// a name to save as or to save a copy under, and F6 again or Enter
+    /// saves and closes once the save has gone through. Any other key
+    /// leaves the editor as it was.
+    fn save_menu_input(&mut self, key: DecodedKey) {
+        self.save_menu = false;
+        self.clear_prompt();
+        let window: usize = self.active_window;
+        match key {
+            DecodedKey::Unicode('s') => {
+                self.save_editor(window, false);
+            },
+            DecodedKey::Unicode('a') | DecodedKey::Unicode('c') => {
+                self.saving_to = Some(if key == DecodedKey::Unicode('a') { SaveMode::As } else { SaveMode::Copy });
+                self.new_filename = FileName::empty();
+                self.creating_file = true;
+            },
+            DecodedKey::RawKey(KeyCode::F6) | DecodedKey::Unicode('\n') => {
+                if self.save_editor(window, true) {
+                    return;
+                }
+                if self.documents[window].modified {
+                    self.confirming_discard = true;
+                    return;
+                }
+                self.close_editor(window);
+            },
+            _ => {}
+        }
+    }
+
+    /// Saves the active editor's text as `filename`, a file that must not
+    /// exist yet. Saving as carries on editing the new file; saving a copy
+    /// carries on editing the old one, which stays as it was.
+    fn save_editor_to(&mut self, filename: FileName, mode: SaveMode) -> Result<(), SwimError> {
+        let window: usize = self.active_window;
+        let (num_files, files): (usize, [FileName; MAX_FILES_STORED]) = self.documents[window].list_files();
+        if files[0..num_files].contains(&filename) {
+            return Err(SwimError::FileExists);
+        }
+        if self.file_owners.owner_of(filename.as_str()).is_none() {
+            self.file_owners.record(filename.as_str(), window);
+        }
+        if self.queue_editor_save(window, filename, false) && mode == SaveMode::As {
+            let doc: &mut SwimDocument = &mut self.documents[window];
+            doc.current_editing_file = filename;
+            doc.modified = false;
+        }
+        Ok(())
+    }
+
+    /// Queues the file open in `window`'s editor to be saved, if any, and
+    /// returns whether it was. The editor counts as saved once the save is
+    /// queued, and is marked unsaved again if the save fails.
+    fn save_editor(&mut self, window: usize, closes_editor: bool) -> bool {
+        let doc: &SwimDocument = &self.documents[window];
+        if doc.window_status != WindowStatus::EditingFile || doc.current_editing_file.is_empty() {
+            return false;
+        }
+        let filename: FileName = doc.current_editing_file;
+        if !self.queue_editor_save(window, filename, closes_editor) {
+            return false;
+        }
+        self.documents[window].modified = false;
+        true
+    }
+
+    /// Queues the text in `window`'s editor to be saved as `filename`.
+    /// Returns false, having said why, if it could not be.
+    fn queue_editor_save(&mut self, window: usize, filename: FileName, closes_editor: bool) -> bool {
+        let doc: &mut SwimDocument = &mut self.documents[window];
+        doc.cursor_memory.remember(filename, doc.current_row.get(), doc.cursor_position.get());
+        let mut save: PendingSave = PendingSave::new(window);
+        save.filename = filename;
+        save.contents_len = doc.serialize_into(&mut save.contents);
+        save.closes_editor = closes_editor;
+        if doc.stored_compressed(save.filename()) {
+            save.compress_contents();
+        }
+        if !self.may_write(window, save.filename()) {
+            self.report_error(format_args!("F{}: sandboxed, {} belongs to another window",
+                                            window + 1, save.filename()));
+            false
+        } else if !self.save_queue.push(save) {
+            self.report_error(format_args!("F{}: save queue full, {} not saved", window + 1, save.filename()));
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Puts every window back the way it was at boot and drops every
+    /// program, keeping the files and any saves still being written. Open
+    /// editors are saved first, and nothing is restarted if one cannot be.
+    /// A way out when the screen or a window gets into a state nothing else
+    /// fixes.
+    fn warm_restart(&mut self) {
+        if !self.save_all_editors() {
+            self.report_error(format_args!("windows not restarted, an editor could not be saved"));
+            return;
+        }
+        self.interpreters = [None; N];
+        self.asserting = [false; N];
+        self.verdicts = [None; N];
+        self.priorities = [DEFAULT_PRIORITY; N];
+        for window in 0..N {
+            heap_stats::reset(window);
+        }
+        self.next_tick = 0;
+        self.turn_ticks = 0;
+        self.creating_file = false;
+        self.renaming = None;
+        self.copying_marked = false;
+        self.confirming_delete = None;
+        self.confirming_bulk_delete = false;
+        self.confirming_shutdown = false;
+        self.save_menu = false;
+        self.saving_to = None;
+        self.confirming_discard = false;
+        self.switching_to = None;
+        self.moving_window = false;
+        self.zoomed = None;
+        self.search_query = None;
+        self.finding = false;
+        self.search_job = None;
+        self.frozen = false;
+        self.bell_frames = 0;
+        self.palette.close();
+        self.picker.close();
+        self.demo.stop();
+        self.tutorial.stop();
+        for doc in self.documents.iter_mut() {
+            doc.reset_view(&self.screen);
+        }
+        self.active_window = 0;
+        self.z_order = core::array::from_fn(|i| (i + 1) % N);
+        self.clear_prompt();
+        self.redraw_all();
+        self.status.notify(format_args!("windows restarted, files kept"));
+    }
+
+    /// Saves every open editor, waiting for each save to finish before
+    /// queueing the next so none finds the queue full. Returns whether
+    /// every editor was saved.
+    fn save_all_editors(&mut self) -> bool {
+        let mut saved: bool = true;
+        for window in 0..N {
+            self.save_editor(window, false);
+            self.finish_saves();
+            let doc: &SwimDocument = &self.documents[window];
+            if doc.window_status == WindowStatus::EditingFile && doc.modified {
+                saved = false;
+            }
+        }
+        saved
+    }
+
+    /// Runs the current save and every queued one to the end.
+    fn finish_saves(&mut self) {
+        while self.save_job.is_some() || self.save_queue.len() > 0 {
+            self.advance_save_job();
+        }
+    }
+
+    /// Saves every open editor, stops every program, writes out everything
+    /// still queued and the journal, then powers off. If an editor cannot
+    /// be saved, nothing is shut down.
+    fn shut_down(&mut self) {
+        if !self.save_all_editors() {
+            self.report_error(format_args!("not shut down, an editor could not be saved"));
+            return;
+        }
+        self.search_job = None;
+        for window in 0..N {
+            self.stop_program(window, "shut down");
+            self.finish_saves();
+        }
+        self.flush_journal();
+        self.finish_saves();
+        self.status.notify(format_args!("shut down, safe to switch off"));
+        self.status.draw(&mut self.canvas);
+        self.canvas.flush();
+        output::write_serial_line("swim: shut down");
+        power::power_off()
+    }
+
+    /// Queues the output of a program run with 'p' for its ".out" file.
+    fn save_captured_output(&mut self, window: usize, save: PendingSave) {
+        if !self.may_write(window, save.filename()) {
+            self.report_error(format_args!("F{}: sandboxed, {} belongs to another window",
+                                            window + 1, save.filename()));
+        } else if !self.save_queue.push(save) {
+            self.report_error(format_args!("F{}: save queue full, {} not saved", window + 1, save.filename()));
+        }
+    }
+
+    /// Drops the program running in `window`, if any, so it gives up its
+    /// share of the ticks, and returns the window to its file list. The
+    /// transcript ends with `status`.
+    fn stop_program(&mut self, window: usize, status: &str) {
+        self.interpreters[window] = None;
+        self.asserting[window] = false;
+        let doc: &mut SwimDocument = &mut self.documents[window];
+        let transcript: Option<PendingSave> = if doc.program_running {
+            doc.output.take_transcript(status)
+        } else {
+            None
+        };
+        doc.program_running = false;
+        if doc.window_status == WindowStatus::ExecutingFile || doc.window_status == WindowStatus::AwaitingInput ||
+           doc.window_status == WindowStatus::Paused {
+            doc.clear_window(&mut self.canvas);
+            doc.window_status = WindowStatus::DisplayingFiles;
+        }
+        if let Some(transcript) = transcript {
+            self.save_captured_output(window, transcript);
+        }
+    }
+
+    /// Compares what an assert run printed with the expected output in the
+    /// file it was captured for, "name.out". The verdict is shown in the task
+    /// manager and written to the serial port, where a grader can read it.
+    fn check_output(&mut self, window: usize, produced: &PendingSave) {
+        let mut expected: ScratchBuffer = match SCRATCH.checkout() {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                self.report_error(format_args!("F{}: {}: {}", window + 1, fs_error::describe(&error),
+                                               produced.filename()));
+                return;
+            }
+        };
+        let expected_len: usize = match self.documents[window].read_file(produced.filename(), &mut expected) {
+            Ok(len) => len,
+            Err(error) => {
+                self.report_error(format_args!("F{}: nothing to check against: {}: {}", window + 1,
+                                               error.describe(), produced.filename()));
+                return;
+            }
+        };
+        let mismatch: Option<usize> = transcript_diff::first_mismatch(&produced.contents[0..produced.contents_len],
+                                                                      &expected[0..expected_len]);
+        self.verdicts[window] = Some(mismatch.is_none());
+        let mut buffer: [u8; 2 * WINDOW_WIDTH] = [0; 2 * WINDOW_WIDTH];
+        let mut line: ByteWriter = ByteWriter::new(&mut buffer);
+        let _ = match mismatch {
+            None => write!(line, "assert {}: PASS", produced.filename()),
+            Some(line_num) => write!(line, "assert {}: FAIL at line {}", produced.filename(), line_num + 1)
+        };
+        let len: usize = line.len();
+        let verdict: &str = str::from_utf8(&buffer[0..len]).unwrap_or("");
+        output::write_serial_line(verdict);
+        self.status.notify(format_args!("F{}: {}", window + 1, verdict));
+        if mismatch.is_some() {
+            self.speaker.beep(Beep::Error);
+        }
+    }
+
+    /// A sandboxed window may only write files that it created itself.
+    fn may_write(&self, window: usize, filename: &str) -> bool {
+        !self.sandboxed[window] || self.file_owners.owner_of(filename) == Some(window)
+    }
+
+    /// Gives `window`'s program one more or one fewer tick per turn.
+    fn change_priority(&mut self, window: usize, raise: bool) {
+        let priority: usize = self.priorities[window];
+        let changed: usize = if raise { priority + 1 } else { priority.saturating_sub(1) };
+        if !(DEFAULT_PRIORITY..=MAX_PRIORITY).contains(&changed) {
+            self.invalid_key();
+            return;
+        }
+        self.priorities[window] = changed;
+        self.status.notify(format_args!("F{}: priority {}", window + 1, changed));
+    }
+
+    /// Lets every program run `QUANTUM_STEP` more or fewer ticks a frame.
+    fn change_quantum(&mut self, raise: bool) {
+        let changed: usize = if raise { self.quantum + QUANTUM_STEP } else { self.quantum.saturating_sub(QUANTUM_STEP) };
+        if !(MIN_QUANTUM..=MAX_QUANTUM).contains(&changed) {
+            self.invalid_key();
+            return;
+        }
+        self.quantum = changed;
+        self.status.notify(format_args!("{} ticks per frame", changed));
+    }
+
+    /// Draws the screen one frame less or more often, up to every
+    /// `MAX_FRAMES_PER_REFRESH` frames.
+    fn change_frames_per_refresh(&mut self, raise: bool) {
+        let changed: usize = if raise { self.frames_per_refresh + 1 } else { self.frames_per_refresh - 1 };
+        if !(1..=MAX_FRAMES_PER_REFRESH).contains(&changed) {
+            self.invalid_key();
+            return;
+        }
+        self.set_frames_per_refresh(changed);
+        self.status.notify(format_args!("screen drawn every {} frames", changed));
+    }
+
+    fn draw_program_ticks(&mut self) {
+        let col: usize = self.screen.task_manager_col();
+        for window in 0..self.ticks.len() {
+            self.canvas.plot_str(WINDOW_LABELS[window], col, window * 2, ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot_num(self.ticks[window] as isize, col, window * 2 + 1,
+                                 ColorCode::new(Color::White, Color::Black));
+            self.canvas.plot('x', col + 7, window * 2 + 1, ColorCode::new(Color::LightCyan, Color::Black));
+            self.canvas.plot_num(self.priorities[window] as isize, col + 8, window * 2 + 1,
+                                 ColorCode::new(Color::LightCyan, Color::Black));
+        }
+        for window in 0..self.sandboxed.len() {
+            let marker: &str = if self.sandboxed[window] { "sb" } else { "  " };
+            self.canvas.plot_str(marker, col + 3, window * 2, ColorCode::new(Color::Yellow, Color::Black));
+        }
+        for window in 0..self.verdicts.len() {
+            let (verdict, color): (&str, Color) = match self.verdicts[window] {
+                Some(true) => ("pass", Color::LightGreen),
+                Some(false) => ("fail", Color::LightRed),
+                None => ("    ", Color::Black)
+            };
+            self.canvas.plot_str(verdict, col + 6, window * 2, ColorCode::new(color, Color::Black));
+        }
+        // '<' marks the window keys go to. A blinking '?' marks a program
+        // waiting for input, so it is typed into the right window.
+        let blink_on: bool = (self.frame / INPUT_BLINK_FRAMES).is_multiple_of(2);
+        for window in 0..N {
+            let focus: char = if window == self.active_window { '<' } else { ' ' };
+            self.canvas.plot(focus, col + 2, window * 2, ColorCode::new(Color::White, Color::Black));
+            let waiting: bool = self.documents[window].window_status == WindowStatus::AwaitingInput;
+            let input: char = if waiting && blink_on { '?' } else { ' ' };
+            self.canvas.plot(input, col + 5, window * 2, ColorCode::new(Color::LightGreen, Color::Black));
+        }
+        let refresh_row: usize = self.ticks.len() * 2;
+        self.canvas.plot_str("ui 1/", col, refresh_row, ColorCode::new(Color::DarkGray, Color::Black));
+        self.canvas.plot_num(self.frames_per_refresh as isize, col + 5, refresh_row,
+                             ColorCode::new(Color::DarkGray, Color::Black));
+        if self.frozen {
+            self.canvas.plot_str("FROZEN", col, refresh_row + 1, ColorCode::new(Color::Black, Color::LightRed));
+        } else {
+            self.canvas.plot_str("      ", col, refresh_row + 1, ColorCode::new(Color::Black, Color::Black));
+        }
+        // With more windows than the column has rows for, the last ones'
+        // heap figures are left out.
+        for window in 0..N {
+            let row: usize = refresh_row + 2 + window * HEAP_ROWS;
+            if row + HEAP_ROWS > self.screen.rows {
+                break;
+            }
+            heap_stats::draw(&mut self.canvas, window, col, row);
+        }
+    }
+
+    fn clear_prompt(&mut self) {
+        for col in 0..WIN_REGION_WIDTH {
+            self.canvas.plot(' ', col, 0, ColorCode::new(Color::Black, Color::Black));
+        }
+    }
+
+    fn search_input(&mut self, key: DecodedKey) {
+        let query: &mut SearchQuery = match &mut self.search_query {
+            Some(query) => query,
+            None => return
+        };
+        match key {
+            DecodedKey::Unicode('\n') => {
+                if let Some(query) = self.search_query.take() {
+                    self.clear_prompt();
+                    if let Some(job) = &self.search_job {
+                        let (window, query): (usize, SearchQuery) = (job.window(), *job.query());
+                        self.report_error(format_args!("F{}: already searching for {}", window + 1, query.as_str()));
+                        return;
+                    }
+                    let window: usize = self.active_window;
+                    self.search_job = Some(SearchJob::new(window, query, &mut self.documents[window], self.frame));
+                }
+            },
+            DecodedKey::Unicode('\u{1b}') => {
+                self.search_query = None;
+                self.clear_prompt();
+            },
+            DecodedKey::Unicode('\u{8}') => {
+                query.pop();
+                self.clear_prompt();
+            },
+            DecodedKey::Unicode(char) => {
+                if is_drawable(char) && !query.push(char) {
+                    self.invalid_key();
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Arrow keys shift the active window a cell at a time. Moves that would
+    /// leave the window area or overlap another window are refused. Enter
+    /// or Escape leaves the window where it is.
+    fn move_window_input(&mut self, key: DecodedKey) {
+        let doc: &SwimDocument = &self.documents[self.active_window];
+        let (col, row): (usize, usize) = (doc.start_col, doc.start_row);
+        let wanted: (usize, usize) = match key {
+            DecodedKey::RawKey(KeyCode::ArrowLeft) => (col.saturating_sub(1), row),
+            DecodedKey::RawKey(KeyCode::ArrowRight) => (col + 1, row),
+            DecodedKey::RawKey(KeyCode::ArrowUp) => (col, row.saturating_sub(1)),
+            DecodedKey::RawKey(KeyCode::ArrowDown) => (col, row + 1),
+            DecodedKey::Unicode('\n') | DecodedKey::Unicode('\u{1b}') => {
+                self.moving_window = false;
+                self.status.notify(format_args!("F{}: window placed", self.active_window + 1));
+                return;
+            },
+            _ => return
+        };
+        let origin: (usize, usize) = self.screen.clamp_origin(wanted.0, wanted.1);
+        let blocked: bool = self.documents.iter().enumerate()
+            .any(|(i, other)| i != self.active_window &&
+                              self.screen.windows_overlap(origin, (other.start_col, other.start_row)));
+        if origin == (col, row) || blocked {
+            self.invalid_key();
+            return;
+        }
+        self.documents[self.active_window].move_to(origin.0, origin.1);
+        self.redraw_all();
+    }
+
+    /// 'y' deletes the file named in the prompt from every window, the
+    /// same way new files are created in every window. Any other key keeps
+    /// it.
+    fn delete_confirmation_input(&mut self, key: DecodedKey) {
+        let file_name: FileName = match self.confirming_delete.take() {
+            Some(file_name) => file_name,
+            None => return
+        };
+        self.clear_prompt();
+        if key != DecodedKey::Unicode('y') {
+            self.status.notify(format_args!("{} kept", file_name));
+            return;
+        }
+        match self.delete_everywhere(file_name) {
+            Ok(()) => self.status.notify(format_args!("F{}: deleted {}", self.active_window + 1, file_name)),
+            Err(error) => self.report_file_error(error, file_name.as_str())
+        }
+    }
+
+    /// Asks before deleting the highlighted file, or the marked ones if any
+    /// are.
+    fn ask_delete(&mut self) {
+        let active_doc: &mut SwimDocument = &mut self.documents[self.active_window];
+        let file_name: FileName = active_doc.selected_file();
+        if !active_doc.marks.is_empty() {
+            self.confirming_bulk_delete = true;
+            self.clear_prompt();
+        } else if !file_name.is_empty() {
+            self.confirming_delete = Some(file_name);
+            self.clear_prompt();
+        }
+    }
+
+    /// Deletes `file_name` from every window on behalf of the active
+    /// window. A file open in an editor or running is refused. If a
+    /// window's filesystem fails, the others still delete their copy and
+    /// the first failure is returned.
+    fn delete_everywhere(&mut self, file_name: FileName) -> Result<(), SwimError> {
+        let window: usize = self.active_window;
+        if !self.may_write(window, file_name.as_str()) {
+            return Err(SwimError::Sandboxed);
+        }
+        if self.file_in_use(file_name) {
+            return Err(SwimError::FileInUse);
+        }
+        let mut failure: Option<FileSystemError> = None;
+        for doc in self.documents.iter_mut() {
+            if let Err(error) = doc.delete_file(file_name.as_str()) {
+                failure = failure.or(Some(error));
+            }
+        }
+        if let Some(error) = failure {
+            return Err(error.into());
+        }
+        self.audit_log.record(self.frame, window, FileOperation::Delete, file_name.as_str());
+        Ok(())
+    }
+
+    /// Whether a window is editing `file_name` or running it.
+    fn file_in_use(&self, file_name: FileName) -> bool {
+        self.documents.iter().any(|doc| {
+            (doc.window_status == WindowStatus::EditingFile && doc.current_editing_file == file_name) ||
+            (doc.program_running && doc.output.program() == file_name)
+        })
+    }
+
+    /// Deletes every file marked in the active window. Files that cannot be
+    /// deleted stay marked.
+    fn delete_marked(&mut self) {
+        let marks: FileMarks = self.documents[self.active_window].marks;
+        let mut deleted: usize = 0;
+        for file_name in marks.iter() {
+            match self.delete_everywhere(*file_name) {
+                Ok(()) => deleted += 1,
+                Err(error) => self.report_file_error(error, file_name.as_str())
+            }
+        }
+        if deleted == marks.len() {
+            self.status.notify(format_args!("F{}: deleted {} marked files", self.active_window + 1, deleted));
+        }
+    }
+
+    /// The names of the files marked in the active window, separated by
+    /// commas, as far as the top line could show them.
+    fn marked_names(&self) -> ([u8; WIN_REGION_WIDTH], usize) {
+        let mut buffer: [u8; WIN_REGION_WIDTH] = [0; WIN_REGION_WIDTH];
+        let mut names: ByteWriter = ByteWriter::new(&mut buffer);
+        for (i, file_name) in self.documents[self.active_window].marks.iter().enumerate() {
+            let separator: &str = if i == 0 { "" } else { ", " };
+            let _ = write!(names, "{}{}", separator, file_name);
+        }
+        let len: usize = names.len();
+        (buffer, len)
+    }
+
+    /// Copies every file marked in the active window into the directory it
+    /// is browsing, named with `prefix` in front, owned by the active
+    /// window. Nothing is copied if any of the new names is too long or
+    /// taken in any window. A file whose copy fails part way is removed
+    /// from the windows it reached and stays marked.
+    fn copy_marked(&mut self, prefix: FileName) -> Result<(), SwimError> {
+        let window: usize = self.active_window;
+        let marks: FileMarks = self.documents[window].marks;
+        let path: FileName = self.documents[window].directory.path();
+        let copy_name = |from: &FileName| -> Result<FileName, SwimError> {
+            let base: &str = from.as_str().rsplit(PATH_SEPARATOR).next().unwrap_or("");
+            prefixed(path, prefix).and_then(|start| prefixed(start, FileName::truncated(base)))
+                .ok_or(SwimError::InvalidFilename)
+        };
+        for doc in self.documents.iter_mut() {
+            let (num_files, files): (usize, [FileName; MAX_FILES_STORED]) = doc.list_files();
+            for from in marks.iter() {
+                if files[0..num_files].contains(&copy_name(from)?) {
+                    return Err(SwimError::FileExists);
+                }
+            }
+        }
+        let mut copied: usize = 0;
+        for from in marks.iter() {
+            let to: FileName = copy_name(from)?;
+            if let Err(error) = self.copy_everywhere(*from, to, window) {
+                self.report_file_error(error.into(), from.as_str());
+                continue;
+            }
+            self.file_owners.record(to.as_str(), window);
+            self.audit_log.record(self.frame, window, FileOperation::Create, to.as_str());
+            self.documents[window].marks.remove(from.as_str());
+            copied += 1;
+        }
+        if copied == marks.len() {
+            self.status.notify(format_args!("F{}: copied {} files to {}...", window + 1, copied, prefix));
+        }
+        Ok(())
+    }
+
+    /// Copies `from` to `to` in every window's filesystem, or in none of
+    /// them: if one fails, the copies already made are deleted again.
+    fn copy_everywhere(&mut self, from: FileName, to: FileName, owner: usize) -> Result<(), FileSystemError> {
+        for i in 0..N {
+            if let Err(error) = self.documents[i].copy_file(from.as_str(), to.as_str(), owner) {
+                for doc in self.documents[0..=i].iter_mut() {
+                    let _ = doc.delete_file(to.as_str());
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates an empty `filename` in every window's filesystem, owned by
+    /// the active window.
+    fn create_file(&mut self, filename: FileName) -> Result<(), FileSystemError> {
+        let owner: usize = self.active_window;
+        for doc in self.documents.iter_mut() {
+            doc.open_file(filename.as_str(), OpenMode::Create, owner).and_then(FileHandle::finish)?;
+        }
+        self.file_owners.record(filename.as_str(), owner);
+        self.audit_log.record(self.frame, owner, FileOperation::Create, filename.as_str());
+        self.tutorial.observe(TutorialEvent::FileCreated);
+        Ok(())
+    }
+
+    /// Gives `from` the name `to` in every window's filesystem. The
+    /// contents are not copied, only the name they go by changes. `to`
+    /// keeps the owner `from` had.
+    fn rename_file(&mut self, from: FileName, to: FileName) -> Result<(), SwimError> {
+        let window: usize = self.active_window;
+        if to == from {
+            return Ok(());
+        }
+        let (num_files, files): (usize, [FileName; MAX_FILES_STORED]) = self.documents[window].list_files();
+        if files[0..num_files].contains(&to) {
+            return Err(SwimError::FileExists);
+        }
+        if !self.may_write(window, from.as_str()) {
+            return Err(SwimError::Sandboxed);
+        }
+        if self.file_in_use(from) {
+            return Err(SwimError::FileInUse);
+        }
+        let owner: usize = self.file_owners.owner_of(from.as_str()).unwrap_or(window);
+        for doc in self.documents.iter_mut() {
+            doc.rename_file(from.as_str(), to.as_str())?;
+        }
+        self.file_owners.record(to.as_str(), owner);
+        self.audit_log.record(self.frame, window, FileOperation::Create, to.as_str());
+        self.audit_log.record(self.frame, window, FileOperation::Delete, from.as_str());
+        self.documents[window].select_file(to.as_str());
+        self.status.notify(format_args!("F{}: renamed {} to {}", window + 1, from, to));
+        Ok(())
+    }
+
+    fn file_creation_input(&mut self, key: DecodedKey) {
+        match key {
+            DecodedKey::Unicode('\n') => {
+                if !self.new_filename.is_empty() {
+                    // Names are typed relative to the directory being browsed,
+                    // or when saving, the one the file being edited is in.
+                    let doc: &SwimDocument = &self.documents[self.active_window];
+                    let path: FileName = if self.saving_to.is_some() {
+                        FileName::truncated(DirectorySnapshot::directory_of(doc.current_editing_file.as_str()))
+                    } else {
+                        doc.directory.path()
+                    };
+                    let filename: Option<FileName> = prefixed(path, self.new_filename)
+                        .filter(|filename| DirectorySnapshot::is_valid_path(filename.as_str()));
+                    let done: Result<(), SwimError> = match (self.renaming, self.saving_to, filename) {
+                        _ if self.copying_marked => self.copy_marked(self.new_filename),
+                        (_, _, None) => Err(SwimError::InvalidFilename),
+                        (_, Some(mode), Some(filename)) => self.save_editor_to(filename, mode),
+                        (Some(old_filename), None, Some(filename)) => self.rename_file(old_filename, filename),
+                        (None, None, Some(filename)) => self.create_file(filename).map_err(SwimError::from)
+                    };
+                    if let Err(error) = done {
+                        self.canvas.plot_str(error.describe(), 12 + MAX_FILENAME_BYTES, 0,
+                                             ColorCode::new(Color::White, Color::Black));
+                        self.speaker.beep(Beep::Error);
+                        return;
+                    }
+                    self.creating_file = false;
+                    self.renaming = None;
+                    self.copying_marked = false;
+                    self.saving_to = None;
+                    for col in 0..WIN_REGION_WIDTH {
+                        self.canvas.plot(' ', col, 0, ColorCode::new(Color::Black, Color::Black));
+                    }
+                }
+            },
+            DecodedKey::Unicode('\u{1b}') => {
+                self.creating_file = false;
+                self.renaming = None;
+                self.copying_marked = false;
+                self.saving_to = None;
+                self.clear_prompt();
+            },
+            DecodedKey::Unicode('\u{8}') => {
+                if !self.new_filename.is_empty() {
+                    for i in 0..=self.new_filename.char_count() {
+                        self.canvas.plot(' ', 10 + i, 0, ColorCode::new(Color::Black, Color::Black));
+                    }
+                    self.new_filename.pop();
+                    self.canvas.plot_str(self.new_filename.as_str(), 10, 0, ColorCode::new(Color::White, Color::Black));
+                    self.canvas.plot(' ', 10 + self.new_filename.char_count(), 0,
+                                     ColorCode::new(Color::Black, Color::Black));
+                }
+            },
+            DecodedKey::Unicode(char) => {
+                if is_drawable(char) && self.new_filename.len() + char.len_utf8() < MAX_FILENAME_BYTES &&
+                   self.new_filename.push(char) {
+                    self.canvas.plot(char, 10 + self.new_filename.char_count() - 1, 0,
+                                     ColorCode::new(Color::White, Color::Black));
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+impl SwimDocument {
+    fn new(window: usize, screen: &ScreenInfo) -> Self {
+        let (start_col, start_row): (usize, usize) = screen.window_origin(window);
+        let mut swim_doc: SwimDocument = Self {
+            window,
+            letters: [['\0'; WINDOW_WIDTH]; MAX_EDITOR_LINES],
+            num_letters: 0,
+            next_letter: 0,
+            start_col,
+            start_row,
+            rows: screen.window_rows(),
+            hidden: false,
+            current_row: Line::FIRST,
+            top_line: 0,
+            cursor_position: Col::FIRST,
+            active: false,
+            highlighted: false,
+            flashing: false,
+            file_system: BlockFileSystem::new(WindowDisk::memory()),
+            open_files: OpenFileTable::new(),
+            window_status: WindowStatus::DisplayingFiles,
+            active_file: 0,
+            selected_name: FileName::empty(),
+            program_running: false,
+            output: ProgramOutput::new(window, start_col, start_row, screen.window_rows()),
+            input_script: InputScript::new(),
+            array_string: ArrayString::default(),
+            current_editing_file: FileName::empty(),
+            modified: false,
+            chord: None,
+            found: None,
+            cursor_memory: CursorMemory::new(),
+            bookmarks: Bookmarks::new(),
+            line_guide_col: DEFAULT_LINE_GUIDE_COL,
+            input_row: 0,
+            read_ahead: FileCache::new(),
+            diagnostics_page: DiagnosticsPage::BlockMap,
+            block_map: BlockMap::new(),
+            directory: DirectorySnapshot::new(),
+            names: NameTable::new(),
+            search_results: SearchResults::new(),
+            error: None,
+            compare_mark: None,
+            marks: FileMarks::new(),
+            compared: (FileName::empty(), FileName::empty())
+        };
+        default_files::install(&mut swim_doc);
+        swim_doc
+    }
+
+    fn display_files(&mut self, screen: &mut dyn Screen) {
+        let files: (usize, [FileName; MAX_FILES_STORED]) = self.list_entries();
+        let path: FileName = self.directory.path();
+        let mut col: usize = self.start_col;
+        let mut row: usize = self.start_row - 1;
+        for file_num in 0..files.0 {
+            let name: &str = files.1[file_num].as_str();
+            let text: &str = name.strip_prefix(path.as_str()).unwrap_or(name);
+            if file_num % 3 == 0 {
+                col = self.start_col;
+                row += 1;
+            } else {
+                col += FILE_GRID_COL_WIDTH;
+            }
+            if file_num == self.active_file {
+                let background: Color = if self.marks.contains(name) { Color::Yellow } else { Color::White };
+                plot_abbreviated(screen, text, FILE_GRID_NAME_WIDTH, col, row,
+                                 ColorCode::new(Color::Black, background));
+            } else if self.marks.contains(name) {
+                plot_abbreviated(screen, text, FILE_GRID_NAME_WIDTH, col, row,
+                                 ColorCode::new(Color::Yellow, Color::Black));
+            } else if DirectorySnapshot::is_directory(name) {
+                plot_abbreviated(screen, text, FILE_GRID_NAME_WIDTH, col, row,
+                                 ColorCode::new(Color::LightBlue, Color::Black));
+            } else {
+                let color: Color = match FileType::of(text) {
+                    FileType::Program => Color::White,
+                    FileType::Text => Color::LightCyan,
+                    FileType::Data => Color::LightGray
+                };
+                plot_abbreviated(screen, text, FILE_GRID_NAME_WIDTH, col, row, ColorCode::new(color, Color::Black));
+            }
+        }
+        let selected: FileName = self.selected_file();
+        if !selected.is_empty() {
+            self.refresh_read_ahead(selected.as_str());
+        }
+    }
+
+    /// The highlighted file, if its name is too long for the file grid.
+    fn abbreviated_selection(&mut self) -> Option<FileName> {
+        if self.window_status != WindowStatus::DisplayingFiles {
+            return None;
+        }
+        let selected: FileName = self.selected_entry();
+        if selected.char_count() <= FILE_GRID_NAME_WIDTH {
+            return None;
+        }
+        Some(selected)
+    }
+
+    fn open_diagnostics(&mut self, screen: &mut dyn Screen) {
+        self.clear_window(screen);
+        self.diagnostics_page = DiagnosticsPage::BlockMap;
+        self.window_status = WindowStatus::Diagnostics;
+        self.measure_files();
+    }
+
+    fn next_diagnostics_page(&mut self, screen: &mut dyn Screen) {
+        self.clear_window(screen);
+        match self.diagnostics_page.next() {
+            Some(page) => self.diagnostics_page = page,
+            None => self.window_status = WindowStatus::DisplayingFiles
+        }
+    }
+
+    fn draw_diagnostics(&self, screen: &mut dyn Screen) {
+        match self.diagnostics_page {
+            DiagnosticsPage::BlockMap => self.block_map.draw(screen, self.start_col, self.start_row, self.rows,
+                                                             self.active_file),
+            DiagnosticsPage::OpenFiles => self.open_files.draw(screen, self.start_col, self.start_row, self.rows),
+            DiagnosticsPage::AuditLog | DiagnosticsPage::Scheduler | DiagnosticsPage::Jobs |
+            DiagnosticsPage::Stack | DiagnosticsPage::Memory => {}
+        }
+    }
+
+    fn measure_files(&mut self) {
+        self.block_map = BlockMap::new();
+        if let Ok((num_entries, entries)) = self.stored_entries() {
+            self.block_map.num_files = num_entries;
+            for entry_num in 0..num_entries {
+                // Free entries were emptied when their files were deleted.
+                let length: usize = match self.names.name_of(entries[entry_num]) {
+                    Some(name) => self.file_length(name.as_str()),
+                    None => 0
+                };
+                self.block_map.file_blocks[entry_num] = length.div_ceil(BLOCK_SIZE);
+            }
+        }
+    }
+
+    fn file_length(&mut self, filename: &str) -> usize {
+        let mut length: usize = 0;
+        if let Ok(mut file) = self.open_file(filename, OpenMode::Read, self.window) {
+            loop {
+                let mut block: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+                if file.read(&mut block).is_err() {
+                    break;
+                }
+                let block_len: usize = text_len(&block);
+                length += block_len;
+                if block_len < BLOCK_SIZE {
+                    break;
+                }
+            }
+            let _ = file.finish();
+        }
+        length
+    }
+
+    /// Counts the contents of `filename` a block at a time. Compressed files
+    /// have to be unpacked whole first.
+    fn text_stats(&mut self, filename: &str) -> Option<TextStats> {
+        let mut stats: TextStats = TextStats::new();
+        if self.stored_compressed(filename) {
+            let mut buffer: ScratchBuffer = SCRATCH.checkout().ok()?;
+            let len: usize = self.read_file(filename, &mut buffer).ok()?;
+            for chunk in buffer[0..len].chunks(BLOCK_SIZE) {
+                stats.feed(chunk);
+            }
+            return Some(stats);
+        }
+        let mut file: FileHandle = self.open_file(filename, OpenMode::Read, self.window).ok()?;
+        loop {
+            let mut block: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+            if file.read(&mut block).is_err() {
+                break;
+            }
+            let block_len: usize = text_len(&block);
+            stats.feed(&block[0..block_len]);
+            if block_len < BLOCK_SIZE {
+                break;
+            }
+        }
+        let _ = file.finish();
+        Some(stats)
+    }
+
+    fn open_search_hit(&mut self, screen: &mut dyn Screen) -> Result<(), (SwimError, FileName)> {
+        let hit: SearchHit = match self.search_results.selected() {
+            Some(hit) => hit,
+            None => return Ok(())
+        };
+        let opened: Result<(), SwimError> = if FileType::of(hit.filename.as_str()) == FileType::Data {
+            self.open_viewer(screen, hit.filename.as_str())
+        } else {
+            self.open_editor(screen, hit.filename, hit.line, 0)
+        };
+        opened.map_err(|error| (error, hit.filename))
+    }
+
+    /// Loads `file_name` into the editor with the cursor at `cursor_col` of
+    /// `line`, clamped to the end of that line and to the last line the
+    /// editor holds, and scrolls that line into view. A file the editor
+    /// cannot hold all of is refused, since saving it would drop the rest.
+    fn open_editor(&mut self, screen: &mut dyn Screen, file_name: FileName, line: usize,
+                   cursor_col: usize) -> Result<(), SwimError> {
+        let mut buffer: ScratchBuffer = SCRATCH.checkout()?;
+        let file_len: usize = self.read_file(file_name.as_str(), &mut buffer)?;
+        let file_content: &str = str::from_utf8(&buffer[0..file_len]).map_err(|_| SwimError::NotText)?;
+        if !fits_editor(file_content) {
+            return Err(SwimError::TooBigToEdit);
+        }
+        self.current_editing_file = file_name;
+        self.modified = false;
+        self.found = None;
+        self.bookmarks = Bookmarks::new();
+        self.window_status = WindowStatus::EditingFile;
+        self.clear_window(screen);
+        self.letters = [['\0'; WINDOW_WIDTH]; MAX_EDITOR_LINES];
+        let mut row: usize = 0;
+        let mut col: usize = 0;
+        for char in file_content.chars() {
+            if char == '\n' {
+                row += 1;
+                col = 0;
+                if row >= MAX_EDITOR_LINES {
+                    break;
+                }
+            } else if is_drawable(char) && col < WINDOW_WIDTH {
+                self.letters[row][col] = char;
+                col += 1;
+            }
+        }
+        self.current_row = Line::new(line);
+        self.top_line = 0;
+        self.scroll_to_cursor();
+        self.num_letters = self.get_line_length(self.current_row.get());
+        self.cursor_position = Col::new(min(cursor_col, self.num_letters));
+        self.next_letter = self.num_letters;
+        self.draw_all_lines(screen);
+        Ok(())
+    }
+
+    /// Lists the directory without hidden files, falling back to the last
+    /// listing that could be read.
+    fn list_files(&mut self) -> (usize, [FileName; MAX_FILES_STORED]) {
+        let listing = self.stored_entries();
+        self.directory.refresh(listing, &self.names);
+        self.follow_selection();
+        self.directory.files()
+    }
+
+    /// What the file grid shows: the files and directories in the
+    /// directory being browsed.
+    fn list_entries(&mut self) -> (usize, [FileName; MAX_FILES_STORED]) {
+        self.list_files();
+        self.directory.entries()
+    }
+
+    /// Moves the window so its text starts at (`start_col`, `start_row`).
+    fn move_to(&mut self, start_col: usize, start_row: usize) {
+        self.start_col = start_col;
+        self.start_row = start_row;
+        self.output.move_to(start_col, start_row);
+    }
+
+    /// Puts the window back the way it was at boot. Its files, the names
+    /// they go by and the files a save has open are kept; everything
+    /// read from them is thrown away and read again.
+    fn reset_view(&mut self, screen: &ScreenInfo) {
+        let (start_col, start_row): (usize, usize) = screen.window_origin(self.window);
+        self.move_to(start_col, start_row);
+        self.letters = [['\0'; WINDOW_WIDTH]; MAX_EDITOR_LINES];
+        self.num_letters = 0;
+        self.next_letter = 0;
+        self.current_row = Line::FIRST;
+        self.top_line = 0;
+        self.cursor_position = Col::FIRST;
+        self.resize(screen.window_rows());
+        self.hide(false);
+        self.window_status = WindowStatus::DisplayingFiles;
+        self.active_file = 0;
+        self.selected_name = FileName::empty();
+        self.program_running = false;
+        self.output = ProgramOutput::new(self.window, start_col, start_row, self.rows);
+        self.input_script.clear();
+        self.array_string.clear();
+        self.current_editing_file = FileName::empty();
+        self.bookmarks = Bookmarks::new();
+        self.chord = None;
+        self.found = None;
+        self.input_row = 0;
+        self.read_ahead = FileCache::new();
+        self.diagnostics_page = DiagnosticsPage::BlockMap;
+        self.block_map = BlockMap::new();
+        self.directory = DirectorySnapshot::new();
+        self.search_results.clear();
+        self.error = None;
+        self.compare_mark = None;
+    }
+
+    /// Gives the window `rows` rows inside its border, scrolling the editor
+    /// if its cursor would end up below the last one.
+    fn resize(&mut self, rows: usize) {
+        self.rows = rows;
+        self.output.resize(rows);
+        self.top_line = min(self.top_line, MAX_EDITOR_LINES - rows);
+        self.scroll_to_cursor();
+    }
+
+    fn hide(&mut self, hidden: bool) {
+        self.hidden = hidden;
+        self.output.hide(hidden);
+    }
+
+    /// Empties `filename` and frees its directory entry for the next new
+    /// file.
+    fn delete_file(&mut self, filename: &str) -> Result<(), FileSystemError> {
+        self.open_file(filename, OpenMode::Read, self.window).and_then(FileHandle::finish)?;
+        self.read_ahead.invalidate(filename);
+        self.open_file(filename, OpenMode::Create, self.window).and_then(FileHandle::finish)?;
+        self.names.remove(filename);
+        self.store_names()?;
+        self.marks.remove(filename);
+        self.list_files();
+        Ok(())
+    }
+
+    /// The file being edited or run, or else the highlighted one.
+    fn shown_file(&self) -> FileName {
+        match self.window_status {
+            WindowStatus::EditingFile => self.current_editing_file,
+            WindowStatus::ExecutingFile | WindowStatus::AwaitingInput | WindowStatus::Paused |
+            WindowStatus::DisplayingOutput => self.output.program(),
+            _ => self.selected_name
+        }
+    }
+
+    /// Gives the file `from` the name `to`. Its contents stay in the
+    /// directory entry they were in.
+    fn rename_file(&mut self, from: &str, to: &str) -> Result<(), FileSystemError> {
+        self.open_file(from, OpenMode::Read, self.window).and_then(FileHandle::finish)?;
+        self.names.rename(from, to);
+        self.store_names()?;
+        self.marks.remove(from);
+        self.read_ahead.invalidate(from);
+        self.read_ahead.invalidate(to);
+        self.list_files();
+        Ok(())
+    }
+
+    /// Highlights `filename`, browsing the directory it is in.
+    fn select_file(&mut self, filename: &str) {
+        self.directory.enter_directory_of(filename);
+        let (num_files, files) = self.list_entries();
+        if let Some(file_num) = files[0..num_files].iter().position(|file| file.as_str() == filename) {
+            self.highlight(file_num);
+        }
+    }
+
+    /// Highlights the entry at `index` in the file grid.
+    fn highlight(&mut self, index: usize) {
+        self.active_file = index;
+        self.selected_name = self.directory.entry(index).unwrap_or(FileName::empty());
     }
 
-    fn file_creation_input(&mut self, key: DecodedKey) {
-        match key {
-            DecodedKey::Unicode('\n') => {
-                if self.new_filename_length > 0 {
-                    let mut filename_bytes: [u8; 10] = [0u8; MAX_FILENAME_BYTES];
-                    for i in 0..self.new_filename_length {
-                        filename_bytes[i] = self.new_filename[i] as u8;
-                    }
-                    let filename: &str = str::from_utf8(&filename_bytes[0..self.new_filename_length]).unwrap();
-                    for doc in self.documents.iter_mut() {
-                        let fd: usize;
-                        match doc.file_system.open_create(filename) {
-                            Ok(value) => fd = value,
-                            Err(_) => {
-                                plot_str("Too many files!", 20, 0, ColorCode::new(Color::White, Color::Black));
-                                return;
-                            }
-                        }
-                        doc.file_system.close(fd).unwrap();
-                    }
-                    self.creating_file = false;
-                    for col in 0..WIN_REGION_WIDTH {
-                        plot(' ', col, 0, ColorCode::new(Color::Black, Color::Black));
-                    }
-                }
-            },
-            DecodedKey::Unicode('\u{8}') => {
-                if self.new_filename_length > 0 {
-                    for i in 0..=self.new_filename_length {
-                        plot(' ', 10 + i, 0, ColorCode::new(Color::Black, Color::Black));
-                    }
-                    self.new_filename_length -= 1;
-                    self.new_filename[self.new_filename_length] = '\0';
-                    for i in 0..self.new_filename_length {
-                        plot(self.new_filename[i], 10 + i, 0, ColorCode::new(Color::White, Color::Black));
-                    }
-                    plot(' ', 10 + self.new_filename_length, 0, ColorCode::new(Color::White, Color::White));
-                }
-            },
-            DecodedKey::Unicode(char) => {
-                if is_drawable(char) && self.new_filename_length < MAX_FILENAME_BYTES - 1 {
-                    self.new_filename[self.new_filename_length] = char;
-                    self.new_filename_length += 1;
-                    plot(char, 10 + self.new_filename_length - 1, 0, ColorCode::new(Color::White, Color::Black));
-                }
+    /// Finds the highlighted name in a fresh listing. If it has gone, the
+    /// highlight stays where it was, or on the last entry if the list got
+    /// shorter, and takes on the name there.
+    fn follow_selection(&mut self) {
+        let (num_entries, entries): (usize, [FileName; MAX_FILES_STORED]) = self.directory.entries();
+        match entries[0..num_entries].iter().position(|entry| *entry == self.selected_name) {
+            Some(index) => self.active_file = index,
+            None => self.highlight(min(self.active_file, num_entries.saturating_sub(1)))
+        }
+    }
+
+    /// The highlighted file, or an empty name if the listing has shrunk or
+    /// a directory is highlighted.
+    fn selected_file(&mut self) -> FileName {
+        let entry: FileName = self.selected_entry();
+        if DirectorySnapshot::is_directory(entry.as_str()) {
+            FileName::empty()
+        } else {
+            entry
+        }
+    }
+
+    /// The highlighted file or directory, or an empty name if there is
+    /// none. This is the name the file grid shows highlighted, whatever
+    /// index it has reached since. The directory is only read if nothing has
+    /// been highlighted yet.
+    fn selected_entry(&mut self) -> FileName {
+        if self.selected_name.is_empty() {
+            self.list_files();
+        }
+        self.selected_name
+    }
+
+    /// Shows `filename` read-only, as text or, for data files, as hex.
+    fn open_viewer(&mut self, screen: &mut dyn Screen, filename: &str) -> Result<(), SwimError> {
+        let mut buffer: ScratchBuffer = SCRATCH.checkout()?;
+        let len: usize = self.read_file(filename, &mut buffer)?;
+        self.current_editing_file = FileName::truncated(filename);
+        self.clear_window(screen);
+        self.window_status = WindowStatus::ViewingFile;
+        if FileType::of(filename) == FileType::Data {
+            self.draw_hex(screen, &buffer[0..len]);
+        } else {
+            self.draw_text(screen, &buffer[0..len], FileType::of(filename) == FileType::Text);
+        }
+        Ok(())
+    }
+
+    /// Feeds the lines of "name.in", if there is one, to the program `name`
+    /// in place of typed input. Returns whether there was a script.
+    fn load_input_script(&mut self, file_name: &str) -> bool {
+        self.input_script.clear();
+        let script_name: FileName = suffixed_name(file_name, "in");
+        let mut buffer: ScratchBuffer = match SCRATCH.checkout() {
+            Ok(buffer) => buffer,
+            Err(_) => return false
+        };
+        match self.read_file(script_name.as_str(), &mut buffer) {
+            Ok(len) => {
+                self.input_script.load(&buffer[0..len]);
+                true
             },
-            _ => {}
+            Err(_) => false
         }
     }
-}
 
-impl InterpreterOutput for SwimDocument {
-    fn print(&mut self, chars: &[u8]) {
-        let output: &str = str::from_utf8(chars).unwrap().trim();
-        if self.output_line >= WINDOW_HEIGHT {
-            for row in 0..WINDOW_HEIGHT-1 {
-                self.clear_line(self.start_row + row);
+    /// Shows how `new` differs from `old`, such as two transcripts of the
+    /// same program run under different settings.
+    fn open_comparison(&mut self, screen: &mut dyn Screen, old: FileName, new: FileName)
+                       -> Result<(), (SwimError, FileName)> {
+        let mut old_buffer: ScratchBuffer = SCRATCH.checkout().map_err(|error| (error.into(), old))?;
+        let old_len: usize = self.read_file(old.as_str(), &mut old_buffer).map_err(|error| (error, old))?;
+        let mut new_buffer: ScratchBuffer = SCRATCH.checkout().map_err(|error| (error.into(), new))?;
+        let new_len: usize = self.read_file(new.as_str(), &mut new_buffer).map_err(|error| (error, new))?;
+        self.compared = (old, new);
+        self.clear_window(screen);
+        self.window_status = WindowStatus::ComparingFiles;
+        transcript_diff::draw(screen, &old_buffer[0..old_len], &new_buffer[0..new_len], self.start_col, self.start_row,
+                              self.rows);
+        Ok(())
+    }
+
+    /// Draws `bytes` as lines of text. Saved output, such as transcripts,
+    /// shows its `headings` the way they looked when the program ran.
+    fn draw_text(&self, screen: &mut dyn Screen, bytes: &[u8], headings: bool) {
+        for (row, line) in bytes.split(|&byte| byte == b'\n').take(self.rows).enumerate() {
+            let color: ColorCode = if headings {
+                output::line_color(line)
+            } else {
+                ColorCode::new(Color::White, Color::Black)
+            };
+            for (col, &byte) in line.iter().take(WINDOW_WIDTH).enumerate() {
+                let char: char = if is_drawable(byte as char) { byte as char } else { '.' };
+                screen.plot(char, self.start_col + col, self.start_row + row, color);
             }
-            self.output_line = WINDOW_HEIGHT - 1;
         }
-        self.clear_line(self.start_row + self.output_line);
-        plot_str(output, self.start_col, self.start_row + self.output_line, 
-                 ColorCode::new(Color::White, Color::Black));
-        self.output_line += 1;
     }
-}
 
-impl SwimDocument {
-    fn new(start_col: usize, start_row: usize) -> Self {
-        let mut swim_doc: SwimDocument = Self {
-            letters: [['\0'; WINDOW_WIDTH]; WINDOW_HEIGHT],
-            num_letters: 0,
-            next_letter: 0,
-            start_col,
-            start_row,
-            current_row: 0,
-            cursor_position: 0,
-            active: false,
-            file_system: FileSystem::new(RamDisk::new()),
-            window_status: WindowStatus::DisplayingFiles,
-            active_file: 0,
-            program_running: false,
-            output_line: 0,
-            array_string: ArrayString::default(),
-            current_editing_file: [0; MAX_FILENAME_BYTES],
-            current_editing_file_len: 0,
-            input_row: 0
+    fn draw_hex(&self, screen: &mut dyn Screen, bytes: &[u8]) {
+        for (row, chunk) in bytes.chunks(HEX_BYTES_PER_ROW).take(self.rows).enumerate() {
+            for (i, &byte) in chunk.iter().enumerate() {
+                let col: usize = self.start_col + i * 3;
+                screen.plot(hex_digit(byte >> 4), col, self.start_row + row,
+                            ColorCode::new(Color::White, Color::Black));
+                screen.plot(hex_digit(byte & 0xf), col + 1, self.start_row + row,
+                            ColorCode::new(Color::White, Color::Black));
+                let char: char = if is_drawable(byte as char) { byte as char } else { '.' };
+                screen.plot(char, self.start_col + HEX_BYTES_PER_ROW * 3 + 1 + i, self.start_row + row,
+                            ColorCode::new(Color::LightGray, Color::Black));
+            }
+        }
+    }
+
+    fn refresh_read_ahead(&mut self, filename: &str) {
+        let entry: FileName = match self.names.entry_of(filename) {
+            Some(entry) if !self.read_ahead.has_tried(filename) => entry,
+            _ => return
         };
-        swim_doc.create_default_files();
-        swim_doc
+        if let Ok(mut file) = FileHandle::open(&mut self.file_system, &mut self.open_files,
+                                               filename, entry.as_str(), OpenMode::Read, self.window) {
+            let read_ok: bool = file.read(self.read_ahead.start_fill(filename)).is_ok();
+            let mut probe: [u8; 1] = [0];
+            let fits: bool = file.read(&mut probe).is_ok() && probe[0] == 0;
+            let _ = file.finish();
+            if read_ok && fits {
+                self.read_ahead.finish_fill();
+            }
+        }
     }
 
-    fn create_default_files(&mut self) {
-        let hello: usize = self.file_system.open_create("hello").unwrap();
-        self.file_system.write(hello, r#"print("Hello, world!")"#.as_bytes()).unwrap();
-        self.file_system.close(hello).unwrap();
-        let nums: usize = self.file_system.open_create("nums").unwrap();
-        self.file_system.write(nums, r#"print(1)
-print(257)"#.as_bytes()).unwrap();
-        self.file_system.close(nums).unwrap();
-        let average: usize = self.file_system.open_create("average").unwrap();
-        self.file_system.write(average, r#"sum := 0
-count := 0
-averaging := true
-while averaging {
-    num := input("Enter a number:")
-    if (num == "quit") {
-        averaging := false
-    } else {
-        sum := (sum + num)
-        count := (count + 1)
+    /// Reads `filename` into `buffer`, decompressing it if it is stored
+    /// compressed, and returns the length of its text. A compressed file
+    /// that does not unpack is `Corrupt`, never empty, so saving over it
+    /// cannot happen by accident.
+    fn read_file(&mut self, filename: &str, buffer: &mut [u8]) -> Result<usize, SwimError> {
+        let len: usize = self.read_stored_file(filename, buffer)?;
+        if !compression::is_compressed(&buffer[0..len]) {
+            return Ok(len);
+        }
+        let mut packed: ScratchBuffer = SCRATCH.checkout()?;
+        let packed_len: usize = min(len, MAX_FILE_BYTES);
+        packed[0..packed_len].copy_from_slice(&buffer[0..packed_len]);
+        compression::decompress(&packed[0..packed_len], buffer).ok_or(SwimError::Corrupt)
     }
-}
-print((sum / count))"#.as_bytes()).unwrap();
-        self.file_system.close(average).unwrap();
-        let pi: usize = self.file_system.open_create("pi").unwrap();
-        self.file_system.write(pi, r#"sum := 0
-i := 0
-neg := false
-terms := input("Num terms:")
-while (i < terms) {
-    term := (1.0 / ((2.0 * i) + 1.0))
-    if neg {
-        term := -term
-    }
-    sum := (sum + term)
-    neg := not neg
-    i := (i + 1)
-}
-print((4 * sum))"#.as_bytes()).unwrap();
-        self.file_system.close(pi).unwrap();
+
+    /// Reads the bytes of `filename` exactly as they are stored.
+    fn read_stored_file(&mut self, filename: &str, buffer: &mut [u8]) -> Result<usize, FileSystemError> {
+        if let Some(contents) = self.read_ahead.contents_of(filename) {
+            let len: usize = min(contents.len(), buffer.len());
+            buffer[0..len].copy_from_slice(&contents[0..len]);
+            return Ok(len);
+        }
+        let mut file: FileHandle = self.open_file(filename, OpenMode::Read, self.window)?;
+        file.read(buffer)?;
+        file.finish()?;
+        Ok(text_len(buffer))
     }
 
-    fn display_files(&mut self) {
-        let files: (usize, [[u8; 10]; MAX_FILES_STORED]) = self.file_system.list_directory().unwrap();
-        let mut col: usize = self.start_col;
-        let mut row: usize = self.start_row - 1;
-        for file_num in 0..files.0 {
-            let text: &str = str::from_utf8(&files.1[file_num]).unwrap().trim_matches(char::from(0));
-            if file_num % 3 == 0 {
-                col = self.start_col;
-                row += 1;
-            } else {
-                col += 10;
-            }
-            if file_num == self.active_file {
-                plot_str(text, col, row, ColorCode::new(Color::Black, Color::White));
-            } else {
-                plot_str(text, col, row, ColorCode::new(Color::White, Color::Black));
+    fn stored_compressed(&mut self, filename: &str) -> bool {
+        let mut first_byte: [u8; 1] = [0];
+        if let Ok(mut file) = self.open_file(filename, OpenMode::Read, self.window) {
+            let _ = file.read(&mut first_byte);
+        }
+        compression::is_compressed(&first_byte)
+    }
+
+    /// Puts the finished `SAVE_TEMP_FILENAME` in place as `filename` by
+    /// switching the directory entries the two go by. `filename`'s old
+    /// entry becomes the swap file, so nothing is rewritten and `filename`
+    /// is never seen half saved.
+    fn switch_in_save(&mut self, filename: &str) -> Result<(), FileSystemError> {
+        let exists: bool = self.open_file(filename, OpenMode::Read, self.window).and_then(FileHandle::finish).is_ok();
+        self.names.switch(filename, SAVE_TEMP_FILENAME, exists).ok_or(FileSystemError::FileNotFound)?;
+        self.store_names()?;
+        self.read_ahead.invalidate(SAVE_TEMP_FILENAME);
+        self.list_files();
+        Ok(())
+    }
+
+    /// Shifts each backup of `filename` one generation older, dropping the
+    /// oldest, then copies `filename` itself into the first backup.
+    fn rotate_backups(&mut self, filename: &str, owner: usize) -> Result<(), FileSystemError> {
+        for generation in (1..MAX_BACKUPS).rev() {
+            let newer: FileName = backup_name(filename, generation);
+            let older: FileName = backup_name(filename, generation + 1);
+            self.copy_file(newer.as_str(), older.as_str(), owner)?;
+        }
+        self.copy_file(filename, backup_name(filename, 1).as_str(), owner)
+    }
+
+    /// Copies `from` over `to`. Does nothing if `from` cannot be opened.
+    fn copy_file(&mut self, from: &str, to: &str, owner: usize) -> Result<(), FileSystemError> {
+        let mut buffer: ScratchBuffer = SCRATCH.checkout()?;
+        match self.open_file(from, OpenMode::Read, owner) {
+            Ok(mut source) => {
+                source.read(&mut buffer)?;
+                source.finish()?;
+            },
+            Err(_) => return Ok(())
+        }
+        self.write_file(to, &buffer[0..text_len(&buffer)], owner)
+    }
+
+    /// Replaces the contents of `filename`, creating it if need be.
+    fn write_file(&mut self, filename: &str, contents: &[u8], owner: usize) -> Result<(), FileSystemError> {
+        let mut file: FileHandle = self.open_file(filename, OpenMode::Create, owner)?;
+        file.write(contents)?;
+        file.finish()?;
+        self.read_ahead.invalidate(filename);
+        Ok(())
+    }
+
+    /// Opens `filename` in whichever directory entry keeps it. A new file
+    /// takes over the entry of a deleted one if there is one.
+    fn open_file(&mut self, filename: &str, mode: OpenMode, owner: usize) -> Result<FileHandle<'_>, FileSystemError> {
+        let entry: FileName = match mode {
+            OpenMode::Read => self.names.entry_of(filename).ok_or(FileSystemError::FileNotFound)?,
+            OpenMode::Create => {
+                let (num_entries, entries): (usize, [FileName; MAX_FILES_STORED]) = self.stored_entries()?;
+                let entry: FileName = self.names.entry_for_create(filename, &entries[0..num_entries]);
+                self.store_names()?;
+                entry
             }
+        };
+        FileHandle::open(&mut self.file_system, &mut self.open_files, filename, entry.as_str(), mode, owner)
+    }
+
+    /// The directory entries in the filesystem, by the names they are
+    /// stored under rather than the ones their files go by.
+    fn stored_entries(&mut self) -> Result<(usize, [FileName; MAX_FILES_STORED]), FileSystemError> {
+        let (num_entries, entries) = self.file_system.list_directory()?;
+        Ok((num_entries, entries.map(|entry| FileName::from_entry(&entry))))
+    }
+
+    /// Writes the names files go by to `NAMES_FILENAME` if they changed, so
+    /// they last as long as the files do.
+    fn store_names(&mut self) -> Result<(), FileSystemError> {
+        if !self.names.take_changed() {
+            return Ok(());
+        }
+        let mut buffer: [u8; NAME_TABLE_BYTES] = [0; NAME_TABLE_BYTES];
+        let len: usize = self.names.write_to(&mut buffer);
+        let fd: usize = self.file_system.open_create(NAMES_FILENAME)?;
+        let written: Result<(), FileSystemError> = self.file_system.write(fd, &buffer[0..len]);
+        self.file_system.close(fd)?;
+        written
+    }
+
+    /// Reads back the names `store_names()` wrote, if it ever did.
+    fn load_names(&mut self) {
+        let mut buffer: [u8; NAME_TABLE_BYTES] = [0; NAME_TABLE_BYTES];
+        let len: usize = match self.file_system.open_read(NAMES_FILENAME) {
+            Ok(fd) => {
+                let read: Result<usize, FileSystemError> = self.file_system.read(fd, &mut buffer);
+                let _ = self.file_system.close(fd);
+                read.unwrap_or(0)
+            },
+            Err(_) => 0
+        };
+        self.names = NameTable::read_from(&buffer[0..len]);
+    }
+
+    /// Moves the window onto `file_system`, which already holds its files,
+    /// in place of the ones it booted with.
+    fn mount(&mut self, file_system: SwimFileSystem) {
+        self.file_system = file_system;
+        self.load_names();
+        self.read_ahead = FileCache::new();
+        self.list_files();
+    }
+
+    /// Copies every directory entry onto `file_system`, an empty one, then
+    /// moves the window onto it. The names files go by come along with
+    /// the entries they are kept in.
+    fn move_files_to(&mut self, mut file_system: SwimFileSystem) -> Result<(), FileSystemError> {
+        let (num_entries, entries): (usize, [FileName; MAX_FILES_STORED]) = self.stored_entries()?;
+        let mut buffer: ScratchBuffer = SCRATCH.checkout()?;
+        for entry in entries.iter().take(num_entries) {
+            let fd: usize = self.file_system.open_read(entry.as_str())?;
+            let read: Result<usize, FileSystemError> = self.file_system.read(fd, &mut buffer);
+            self.file_system.close(fd)?;
+            let len: usize = read?;
+            let fd: usize = file_system.open_create(entry.as_str())?;
+            let written: Result<(), FileSystemError> = file_system.write(fd, &buffer[0..len]);
+            file_system.close(fd)?;
+            written?;
         }
+        self.file_system = file_system;
+        Ok(())
+    }
+
+    fn resume_file(&mut self, fd: usize) -> FileHandle<'_> {
+        FileHandle::resume(&mut self.file_system, &mut self.open_files, fd)
     }
 
     fn letter_columns(&self) -> impl Iterator<Item = usize> + '_ {
         0..self.num_letters
     }
 
-    fn tick(&mut self, interpreter: &mut Option<Interpreter<MAX_TOKENS, MAX_LITERAL_CHARS, STACK_DEPTH, MAX_LOCAL_VARS, WINDOW_WIDTH, GenerationalHeap<HEAP_SIZE, MAX_HEAP_BLOCKS, 2>>>) {
+    /// Ends the run early, showing `error` on the window's border.
+    fn end_with_error(&mut self, error: SwimError) {
+        self.error = Some(error);
+        self.window_status = WindowStatus::DisplayingOutput;
+        self.program_running = false;
+    }
+
+    fn tick(&mut self, screen: &mut dyn Screen, interpreter: &mut Option<SwimInterpreter>) {
         if self.window_status == WindowStatus::ExecutingFile {
             match interpreter {
                 Some(ref mut ip) => {
                     if let Ok(input_str) = self.array_string.as_str() {
                         if !input_str.is_empty() {
-                            ip.provide_input(input_str).unwrap();
+                            let accepted: bool = ip.provide_input(input_str).is_ok();
+                            self.output.record_input(input_str);
                             self.array_string.clear();
-                            self.clear_line(self.start_row);
+                            self.clear_line(screen, self.start_row);
+                            if !accepted {
+                                self.end_with_error(SwimError::InputRejected);
+                                *interpreter = None;
+                                return;
+                            }
                         }
                     }
-                    match ip.tick(self) {
+                    self.output.count_tick();
+                    let status: simple_interp::TickStatus = ip.tick(&mut self.output.printer(screen));
+                    if heap_stats::take_faults(self.window) > 0 {
+                        self.end_with_error(SwimError::HeapFault);
+                        *interpreter = None;
+                        return;
+                    }
+                    match status {
                         simple_interp::TickStatus::Continuing => {},
+                        // TickStatus has no error case, so a program stopped by
+                        // a runtime error, such as dividing by zero, finishes
+                        // like any other. What the interpreter printed is all
+                        // the window can show of it.
                         simple_interp::TickStatus::Finished => {
                             self.window_status = WindowStatus::DisplayingOutput;
                             self.program_running = false;
                             *interpreter = None;
                         },
                         simple_interp::TickStatus::AwaitInput => {
+                            if let Some(line) = self.input_script.next_line() {
+                                let accepted: bool = ip.provide_input(line).is_ok();
+                                self.output.record_input(line);
+                                if !accepted {
+                                    self.end_with_error(SwimError::InputRejected);
+                                    *interpreter = None;
+                                }
+                                return;
+                            }
                             self.window_status = WindowStatus::AwaitingInput;
-                            self.clear_line(self.start_row + 1);
-                            self.current_row = 0;
-                            self.cursor_position = 0;
+                            self.clear_line(screen, self.start_row + 1);
+                            self.current_row = Line::FIRST;
+                            self.cursor_position = Col::FIRST;
                             self.num_letters = 0;
                             self.next_letter = 0;
                         }
@@ -542,272 +3846,704 @@ print((4 * sum))"#.as_bytes()).unwrap();
                 None => {}
             }
         }
-        if self.window_status == WindowStatus::AwaitingInput {
-            self.clear_current(1);
-            self.draw_current(1);
-            self.output_line = 0;
+        if self.window_status == WindowStatus::AwaitingInput && !self.hidden {
+            self.clear_current(screen, 1);
+            self.draw_current(screen, 1);
+            self.output.restart_scrollback();
+        }
+    }
+
+    /// Repaints whatever the window was showing after something covered it.
+    /// Modes drawn every frame only need the window cleared, and those that
+    /// paint every cell themselves are left alone.
+    fn redraw_contents(&mut self, screen: &mut dyn Screen) {
+        match self.window_status {
+            WindowStatus::EditingFile => {
+                self.clear_window(screen);
+                self.draw_all_lines(screen);
+            },
+            WindowStatus::ViewingFile => {
+                let filename: FileName = self.current_editing_file;
+                let _ = self.open_viewer(screen, filename.as_str());
+            },
+            WindowStatus::ComparingFiles => {
+                let (old, new): (FileName, FileName) = self.compared;
+                let _ = self.open_comparison(screen, old, new);
+            },
+            WindowStatus::SearchResults | WindowStatus::Notifications => {},
+            _ => self.clear_window(screen)
         }
     }
 
-    fn clear_window(&self) {
-        for row in self.start_row..self.start_row + WINDOW_HEIGHT {
+    fn clear_window(&self, screen: &mut dyn Screen) {
+        for row in self.start_row..self.start_row + self.rows {
             for col in self.start_col..self.start_col + WINDOW_WIDTH {
-                plot(' ', col, row, ColorCode::new(Color::Black, Color::Black));
+                screen.plot(' ', col, row, ColorCode::new(Color::Black, Color::Black));
             }
         }
     }
 
-    fn clear_current(&self, offset: usize) {
+    fn clear_current(&self, screen: &mut dyn Screen, offset: usize) {
         let row: usize = self.get_actual_row() + offset;
         for col in self.letter_columns() {
             let actual_col: usize = self.start_col + col;
-            plot(' ', actual_col, row, ColorCode::new(Color::Black, Color::Black));
+            screen.plot(' ', actual_col, row, ColorCode::new(Color::Black, Color::Black));
         }
-        plot(' ', self.start_col + self.cursor_position, row, ColorCode::new(Color::Black, Color::Black));
+        screen.plot(' ', self.start_col + self.cursor_position.get(), row, ColorCode::new(Color::Black, Color::Black));
     }
 
-    fn clear_line(&self, row: usize) {
+    fn clear_line(&self, screen: &mut dyn Screen, row: usize) {
+        if self.hidden {
+            return;
+        }
         for col in self.start_col..self.start_col + WINDOW_WIDTH {
-            plot(' ', col, row, ColorCode::new(Color::Black, Color::Black));
+            screen.plot(' ', col, row, ColorCode::new(Color::Black, Color::Black));
         }
     }
 
-    fn draw_current(&mut self, offset: usize) {
+    fn draw_current(&mut self, screen: &mut dyn Screen, offset: usize) {
         let row: usize = self.get_actual_row() + offset;
         let buffer_row: usize = if self.window_status == WindowStatus::AwaitingInput {
             self.input_row
         } else {
-            self.current_row
+            self.current_row.get()
         };
         for (i, _) in self.letter_columns().enumerate() {
             let actual_col: usize = self.start_col + i;
-            plot(
+            screen.plot(
                 self.letters[buffer_row][i],
                 actual_col,
                 row,
-                ColorCode::new(Color::White, Color::Black),
+                self.letter_color(buffer_row, i),
             );
         }
-        plot(' ', self.start_col + self.cursor_position, row, ColorCode::new(Color::White, Color::White));
     }
 
-    fn draw_outline(&self) {
+    fn draw_outline(&self, screen: &mut dyn Screen, frame: usize) {
         let color: ColorCode;
-        if self.active {
+        if self.flashing {
+            color = ColorCode::new(Color::Black, Color::LightRed);
+        } else if self.highlighted {
+            color = ColorCode::new(Color::Black, Color::Yellow);
+        } else if self.active {
             color = ColorCode::new(Color::Black, Color::White);
         } else {
             color = ColorCode::new(Color::White, Color::Black);
         }
         for col in self.start_col - 1..=self.start_col + WINDOW_WIDTH {
-            plot('*', col, self.start_row - 1, color);
-            plot('*', col, self.start_row + WINDOW_HEIGHT, color);
-        }
-        for row in self.start_row - 1..=self.start_row + WINDOW_HEIGHT {
-            plot('*', self.start_col - 1, row, color);
-            plot('*', self.start_col + WINDOW_WIDTH, row, color);
-        }
-        let window_label: &str = match (self.start_col, self.start_row) {
-            (1, 2) => "F1",
-            (36, 2) => "F2",
-            (1, 14) => "F3",
-            (36, 14) => "F4",
-            _ => "",
-        };
-        plot_str(window_label, self.start_col, self.start_row - 1, ColorCode::new(Color::White, Color::Black));
-        if self.window_status == WindowStatus::EditingFile && self.current_editing_file_len > 0 {
+            screen.plot('*', col, self.start_row - 1, color);
+            screen.plot('*', col, self.start_row + self.rows, color);
+        }
+        for row in self.start_row - 1..=self.start_row + self.rows {
+            screen.plot('*', self.start_col - 1, row, color);
+            screen.plot('*', self.start_col + WINDOW_WIDTH, row, color);
+        }
+        let window_label: &str = WINDOW_LABELS[self.window];
+        screen.plot_str(window_label, self.start_col, self.start_row - 1, ColorCode::new(Color::White, Color::Black));
+        if let Some(error) = self.error {
+            plot_abbreviated(screen, error.describe(), WINDOW_WIDTH, self.start_col, self.start_row + self.rows,
+                             ColorCode::new(Color::LightRed, Color::Black));
+        }
+        if (self.window_status == WindowStatus::EditingFile || self.window_status == WindowStatus::ViewingFile) &&
+           !self.current_editing_file.is_empty() {
+            let mut buffer: [u8; MAX_FILENAME_BYTES + 1] = [0; MAX_FILENAME_BYTES + 1];
+            let mut title: ByteWriter = ByteWriter::new(&mut buffer);
+            let unsaved: &str = if self.modified && self.window_status == WindowStatus::EditingFile { "*" } else { "" };
+            let _ = write!(title, "{}{}", self.current_editing_file, unsaved);
+            let len: usize = title.len();
             let label_offset = window_label.len();
-            if let Ok(filename) = str::from_utf8(&self.current_editing_file[0..self.current_editing_file_len]) {
-                plot_str(filename, self.start_col + label_offset + 1, self.start_row - 1, 
-                        ColorCode::new(Color::White, Color::Black));
+            plot_scrolling(screen, str::from_utf8(&buffer[0..len]).unwrap_or(""), WINDOW_WIDTH - label_offset - 1,
+                           self.start_col + label_offset + 1, self.start_row - 1,
+                           ColorCode::new(Color::White, Color::Black), frame);
+        }
+        let path: FileName = self.directory.path();
+        if self.window_status == WindowStatus::DisplayingFiles && !path.is_empty() {
+            let label_offset: usize = window_label.len();
+            plot_scrolling(screen, path.as_str(), WINDOW_WIDTH - label_offset - 1, self.start_col + label_offset + 1,
+                           self.start_row - 1, ColorCode::new(Color::White, Color::Black), frame);
+        }
+        if self.window_status == WindowStatus::Paused {
+            screen.plot_str("paused", self.start_col + window_label.len() + 1, self.start_row - 1,
+                            ColorCode::new(Color::Yellow, Color::Black));
+        }
+        if self.window_status == WindowStatus::ComparingFiles {
+            let mut buffer: [u8; 2 * MAX_FILENAME_BYTES + 4] = [0; 2 * MAX_FILENAME_BYTES + 4];
+            let mut title: ByteWriter = ByteWriter::new(&mut buffer);
+            let _ = write!(title, "{} vs {}", self.compared.0, self.compared.1);
+            let len: usize = title.len();
+            let label_offset: usize = window_label.len();
+            plot_scrolling(screen, str::from_utf8(&buffer[0..len]).unwrap_or(""), WINDOW_WIDTH - label_offset - 1,
+                           self.start_col + label_offset + 1, self.start_row - 1,
+                           ColorCode::new(Color::White, Color::Black), frame);
+        }
+        if self.window_status == WindowStatus::EditingFile {
+            self.draw_line_guide(screen, color);
+            self.draw_scroll_position(screen, color);
+            for (number, line) in self.bookmarks.markers() {
+                if (self.top_line..self.top_line + self.rows).contains(&line) {
+                    screen.plot(number, self.start_col - 1, self.start_row + line - self.top_line, color);
+                }
+            }
+        }
+    }
+
+    /// Marks the right border with '^' when there are lines above the
+    /// window and 'v' when there are lines below it, and shows on the bottom
+    /// border how far down the file the window is. Files that fit show
+    /// neither.
+    fn draw_scroll_position(&self, screen: &mut dyn Screen, border_color: ColorCode) {
+        let used_lines: usize = self.used_lines();
+        if used_lines <= self.rows {
+            return;
+        }
+        let right: usize = self.start_col + WINDOW_WIDTH;
+        if self.top_line > 0 {
+            screen.plot('^', right, self.start_row, border_color);
+        }
+        if self.top_line + self.rows < used_lines {
+            screen.plot('v', right, self.start_row + self.rows - 1, border_color);
+        }
+        if self.error.is_none() {
+            let percent: usize = min(self.top_line * 100 / (used_lines - self.rows), 100);
+            let mut buffer: [u8; SCROLL_PERCENT_WIDTH] = [0; SCROLL_PERCENT_WIDTH];
+            let mut label: ByteWriter = ByteWriter::new(&mut buffer);
+            let _ = write!(label, " {}% ", percent);
+            let len: usize = label.len();
+            screen.plot_str(str::from_utf8(&buffer[0..len]).unwrap_or(""), right - len, self.start_row + self.rows,
+                            border_color);
+        }
+    }
+
+    /// Marks the guide column on every row that leaves it empty, or the
+    /// right border if the guide is at the window's edge.
+    fn draw_line_guide(&self, screen: &mut dyn Screen, border_color: ColorCode) {
+        let col: usize = self.start_col + self.line_guide_col;
+        for row in 0..self.rows {
+            if self.line_guide_col == WINDOW_WIDTH {
+                screen.plot('|', col, self.start_row + row, border_color);
+            } else if self.letters[self.top_line + row][self.line_guide_col] == '\0' {
+                screen.plot(':', col, self.start_row + row, ColorCode::new(Color::DarkGray, Color::Black));
             }
         }
     }
 
+    /// The window and its border, which is all it should ever draw on.
+    fn region(&self) -> Region {
+        Region {
+            col: self.start_col - 1,
+            row: self.start_row - 1,
+            width: WINDOW_WIDTH + 2,
+            height: self.rows + 2
+        }
+    }
+
+    fn visible_rows(&self) -> Range<usize> {
+        let rows: usize = if self.hidden { 0 } else { self.rows };
+        self.start_row..self.start_row + rows
+    }
+
     fn get_actual_row(&self) -> usize {
-        self.start_row + (self.current_row % WINDOW_HEIGHT)
+        self.start_row + self.current_row.get() - self.top_line
     }
 
-    fn start_new_line(&mut self, offset: usize) {
-        let row: usize = self.get_actual_row() + offset;
-        plot(' ', self.start_col + self.cursor_position, row, ColorCode::new(Color::Black, Color::Black));
-        self.current_row = (self.current_row + 1) % (WINDOW_HEIGHT - offset);
-        self.cursor_position = 0;
-        self.num_letters = 0;
-        self.next_letter = 0;
+    /// Scrolls just far enough to bring the cursor's line into the window.
+    /// Returns whether the window scrolled.
+    fn scroll_to_cursor(&mut self) -> bool {
+        let line: usize = self.current_row.get();
+        let top_line: usize = self.top_line;
+        if line < self.top_line {
+            self.top_line = line;
+        } else if line >= self.top_line + self.rows {
+            self.top_line = line + 1 - self.rows;
+        }
+        self.top_line != top_line
+    }
+
+    /// Moves the text right of the cursor onto a new line below, pushing
+    /// the lines under it down. Does nothing once the last line of the
+    /// buffer is in use, since that line would be pushed off the end.
+    fn split_line(&mut self, screen: &mut dyn Screen) {
+        let row: usize = self.current_row.get();
+        if self.used_lines() == MAX_EDITOR_LINES || self.current_row.is_last() {
+            return;
+        }
+        let col: usize = self.cursor_position.get();
+        self.letters.copy_within(row + 1..MAX_EDITOR_LINES - 1, row + 2);
+        let line: [char; WINDOW_WIDTH] = self.letters[row];
+        self.letters[row + 1] = ['\0'; WINDOW_WIDTH];
+        self.letters[row + 1][0..WINDOW_WIDTH - col].copy_from_slice(&line[col..]);
+        self.letters[row][col..].fill('\0');
+        self.bookmarks.line_inserted(row + 1);
+        self.current_row = self.current_row.next();
+        self.cursor_position = Col::FIRST;
+        self.num_letters = self.get_line_length(row + 1);
+        self.next_letter = self.num_letters;
+        self.mark_modified();
+        self.scroll_to_cursor();
+        self.draw_all_lines(screen);
+    }
+
+    /// Appends the cursor's line to the one above it, pulling the lines
+    /// under it up. Does nothing if the two don't fit on one line.
+    fn join_line(&mut self, screen: &mut dyn Screen) {
+        let row: usize = self.current_row.get();
+        if row == 0 {
+            return;
+        }
+        let above_len: usize = self.get_line_length(row - 1);
+        let len: usize = self.get_line_length(row);
+        if above_len + len > WINDOW_WIDTH {
+            return;
+        }
+        let line: [char; WINDOW_WIDTH] = self.letters[row];
+        self.letters[row - 1][above_len..above_len + len].copy_from_slice(&line[0..len]);
+        self.letters.copy_within(row + 1..MAX_EDITOR_LINES, row);
+        self.letters[MAX_EDITOR_LINES - 1] = ['\0'; WINDOW_WIDTH];
+        self.bookmarks.line_removed(row);
+        self.current_row = self.current_row.previous();
+        self.cursor_position = Col::new(above_len);
+        self.num_letters = above_len + len;
+        self.next_letter = self.num_letters;
+        self.mark_modified();
+        self.scroll_to_cursor();
+        self.draw_all_lines(screen);
+    }
+
+    /// Takes the cursor's line out of the buffer, pulling the lines under
+    /// it up.
+    fn remove_line(&mut self, screen: &mut dyn Screen) {
+        let row: usize = self.current_row.get();
+        self.letters.copy_within(row + 1..MAX_EDITOR_LINES, row);
+        self.letters[MAX_EDITOR_LINES - 1] = ['\0'; WINDOW_WIDTH];
+        self.bookmarks.line_removed(row);
+        self.cursor_position = Col::FIRST;
+        self.mark_modified();
+        self.jump_to_row(screen, min(row, self.used_lines().saturating_sub(1)));
+    }
+
+    /// Puts `line` into the buffer at the cursor's line, pushing that line
+    /// and the ones under it down. Returns false if the buffer is full.
+    fn insert_line(&mut self, screen: &mut dyn Screen, line: &[char]) -> bool {
+        let row: usize = self.current_row.get();
+        if self.used_lines() == MAX_EDITOR_LINES {
+            return false;
+        }
+        self.letters.copy_within(row..MAX_EDITOR_LINES - 1, row + 1);
+        self.letters[row] = ['\0'; WINDOW_WIDTH];
+        self.letters[row][0..line.len()].copy_from_slice(line);
+        self.bookmarks.line_inserted(row);
+        self.cursor_position = Col::FIRST;
+        self.mark_modified();
+        self.jump_to_row(screen, row);
+        true
+    }
+
+    /// How many lines of the buffer are in use: up to the last one with
+    /// text, counting blank lines before it.
+    fn used_lines(&self) -> usize {
+        (0..MAX_EDITOR_LINES).rev().find(|row| !self.is_line_empty(*row)).map_or(0, |row| row + 1)
     }
 
     fn get_line_length(&self, row: usize) -> usize {
-        let mut length: usize = 0;
-        for i in 0..WINDOW_WIDTH {
-            if self.letters[row][i] == '\0' {
-                break;
+        self.chars_in_line(row).count()
+    }
+
+    /// The text on `row` of the editor buffer.
+    fn line(&self, row: usize) -> &[char] {
+        &self.letters[row][0..self.get_line_length(row)]
+    }
+
+    /// The characters typed on `row` of the editor buffer.
+    fn chars_in_line(&self, row: usize) -> impl Iterator<Item = char> + '_ {
+        self.letters[row].iter().copied().take_while(|char| *char != '\0')
+    }
+
+    /// The rows of the editor buffer in use, top to bottom, with their row
+    /// numbers.
+    fn lines(&self) -> impl Iterator<Item = (usize, &[char])> + '_ {
+        (0..self.used_lines()).map(|row| (row, self.line(row)))
+    }
+
+    /// Writes the buffer into `buffer` the way it is saved, one line per
+    /// row in use. Returns how many bytes were written.
+    fn serialize_into(&self, buffer: &mut [u8]) -> usize {
+        let mut len: usize = 0;
+        for (i, (_, line)) in self.lines().enumerate() {
+            if i > 0 && len < buffer.len() {
+                buffer[len] = b'\n';
+                len += 1;
+            }
+            for char in line {
+                if len == buffer.len() {
+                    return len;
+                }
+                buffer[len] = *char as u8;
+                len += 1;
             }
-            length += 1;
         }
-        length
+        len
     }
     
     fn is_line_empty(&self, row: usize) -> bool {
         self.letters[row][0] == '\0'
     }
 
-    fn draw_all_lines(&self) {
-        for row in 0..WINDOW_HEIGHT {
-            if !self.is_line_empty(row) {
-                for col in 0..self.get_line_length(row) {
-                    plot(
-                        self.letters[row][col],
-                        self.start_col + col,
-                        self.start_row + row,
-                        ColorCode::new(Color::White, Color::Black),
-                    );
-                }
+    /// Draws the lines that fit in the window, from `top_line` down.
+    fn draw_all_lines(&self, screen: &mut dyn Screen) {
+        for row in 0..self.rows {
+            self.clear_line(screen, self.start_row + row);
+            for (col, char) in self.chars_in_line(self.top_line + row).enumerate() {
+                screen.plot(char, self.start_col + col, self.start_row + row,
+                            self.letter_color(self.top_line + row, col));
+            }
+        }
+    }
+
+    /// Text found by the find prompt stands out from the rest.
+    fn letter_color(&self, row: usize, col: usize) -> ColorCode {
+        match self.found {
+            Some((found_row, found_col, len)) if row == found_row && (found_col..found_col + len).contains(&col) => {
+                ColorCode::new(Color::Black, Color::Yellow)
+            },
+            _ => ColorCode::new(Color::White, Color::Black)
+        }
+    }
+
+    /// Moves the cursor to the next place `query` appears after it, ignoring
+    /// case and going round to the top after the last line, and highlights
+    /// it. Returns false if it appears nowhere.
+    fn find_next(&mut self, screen: &mut dyn Screen, query: &str) -> bool {
+        let used_lines: usize = self.used_lines();
+        if used_lines == 0 {
+            return false;
+        }
+        let wanted: &[u8] = query.as_bytes();
+        let start_row: usize = min(self.current_row.get(), used_lines - 1);
+        for step in 0..=used_lines {
+            let row: usize = (start_row + step) % used_lines;
+            let first_col: usize = if step == 0 { self.cursor_position.get() + 1 } else { 0 };
+            let line_len: usize = self.get_line_length(row);
+            let found: Option<usize> = (first_col..(line_len + 1).saturating_sub(wanted.len())).find(|&col| {
+                wanted.iter().enumerate().all(|(i, byte)| self.letters[row][col + i].eq_ignore_ascii_case(&(*byte as char)))
+            });
+            if let Some(col) = found {
+                self.found = Some((row, col, wanted.len()));
+                self.cursor_position = Col::new(col);
+                self.jump_to_row(screen, row);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The line `count` lines below the cursor, or the last line of the
+    /// file if that comes first.
+    fn line_below(&self, count: usize) -> usize {
+        let mut line: usize = self.current_row.get();
+        for _ in 0..count {
+            if line + 1 >= self.used_lines() {
+                break;
             }
+            line += 1;
         }
-        plot(' ', 
-            self.start_col + self.cursor_position,
-            self.start_row + self.current_row, 
-            ColorCode::new(Color::White, Color::White)
-        );
+        line
     }
 
-    fn key(&mut self, key: DecodedKey) {
+    /// Whether typing into the window changes its text: the editor, or a
+    /// program's input line.
+    fn accepts_text(&self) -> bool {
+        self.window_status == WindowStatus::EditingFile || self.window_status == WindowStatus::AwaitingInput
+    }
+
+    /// Only ever given keys by `SwimDocManager::route_to_active()`, so the
+    /// window is always the focused one.
+    fn key(&mut self, screen: &mut dyn Screen, key: DecodedKey) {
+        if self.found.take().is_some() {
+            self.draw_all_lines(screen);
+        }
+        if let DecodedKey::Unicode(char) = key {
+            if self.window_status == WindowStatus::EditingFile {
+                match self.bookmarks.key(char, self.current_row.get()) {
+                    BookmarkKey::Typed => {},
+                    BookmarkKey::Consumed => return,
+                    BookmarkKey::Jump(row) => {
+                        self.jump_to_row(screen, row);
+                        return;
+                    },
+                    BookmarkKey::Chord(key) => {
+                        self.chord = EditorChord::of(key);
+                        return;
+                    }
+                }
+            }
+        }
         match key {
             DecodedKey::RawKey(KeyCode::ArrowUp) => {
-                if !self.active {
-                    return;
-                }
-                if self.window_status == WindowStatus::EditingFile {
-                    if self.current_row > 0 {
-                        plot(' ', 
-                            self.start_col + self.cursor_position,
-                            self.start_row + self.current_row, 
-                            ColorCode::new(Color::Black, Color::Black)
-                        );
-                        self.current_row -= 1;
-                        let line_length: usize = self.get_line_length(self.current_row);
-                        self.cursor_position = core::cmp::min(self.cursor_position, line_length);
-                        self.num_letters = line_length;
-                        self.next_letter = line_length;
-                        self.draw_all_lines();
+                if self.window_status == WindowStatus::SearchResults {
+                    self.search_results.select_previous();
+                } else if self.window_status == WindowStatus::EditingFile {
+                    if !self.current_row.is_first() {
+                        self.jump_to_row(screen, self.current_row.get() - 1);
                     }
                 }
             },
             DecodedKey::RawKey(KeyCode::ArrowDown) => {
-                if !self.active {
-                    return;
+                if self.window_status == WindowStatus::SearchResults {
+                    self.search_results.select_next();
+                } else if self.window_status == WindowStatus::EditingFile {
+                    if self.current_row.get() + 1 < self.used_lines() {
+                        self.jump_to_row(screen, self.current_row.get() + 1);
+                    }
                 }
+            },
+            DecodedKey::RawKey(KeyCode::PageUp) => {
                 if self.window_status == WindowStatus::EditingFile {
-                    if self.current_row < WINDOW_HEIGHT - 1 && !self.is_line_empty(self.current_row + 1) {
-                        plot(' ', 
-                            self.start_col + self.cursor_position,
-                            self.start_row + self.current_row, 
-                            ColorCode::new(Color::Black, Color::Black)
-                        );
-                        self.current_row += 1;
-                        let line_length: usize = self.get_line_length(self.current_row);
-                        self.cursor_position = core::cmp::min(self.cursor_position, line_length);
-                        self.num_letters = line_length;
-                        self.next_letter = line_length;
-                        self.draw_all_lines();
-                    }
+                    self.jump_to_row(screen, self.current_row.get().saturating_sub(self.rows));
                 }
             },
-            DecodedKey::RawKey(KeyCode::ArrowLeft) => {
-                if !self.active {
-                    return;
+            DecodedKey::RawKey(KeyCode::PageDown) => {
+                if self.window_status == WindowStatus::EditingFile {
+                    self.jump_to_row(screen, self.line_below(self.rows));
                 }
+            },
+            DecodedKey::RawKey(KeyCode::ArrowLeft) => {
                 if self.window_status == WindowStatus::DisplayingFiles {
                     if self.active_file > 0 {
-                        self.active_file -= 1;
+                        self.highlight(self.active_file - 1);
                     }
                 } else if self.window_status == WindowStatus::EditingFile {
-                    if self.cursor_position > 0 {
-                        self.clear_line(self.get_actual_row());
-                        self.cursor_position -= 1;
-                        self.draw_current(0);
+                    if !self.cursor_position.is_first() {
+                        self.clear_line(screen, self.get_actual_row());
+                        self.cursor_position = self.cursor_position.previous();
+                        self.draw_current(screen, 0);
                     }
                 }
             },
             DecodedKey::RawKey(KeyCode::ArrowRight) => {
-                if !self.active {
-                    return;
-                }
                 if self.window_status == WindowStatus::DisplayingFiles {
-                    let num_files: usize = self.file_system.list_directory().unwrap().0;
-                    if self.active_file < num_files - 1 {
-                        self.active_file += 1;
+                    let num_files: usize = self.list_entries().0;
+                    if self.active_file + 1 < num_files {
+                        self.highlight(self.active_file + 1);
                     }
                 } else if self.window_status == WindowStatus::EditingFile {
-                    if self.cursor_position < self.num_letters {
-                        self.cursor_position += 1;
-                        self.draw_current(0);
+                    if self.cursor_position.get() < self.num_letters {
+                        self.cursor_position = self.cursor_position.next();
+                        self.draw_current(screen, 0);
                     }
                 }
             },
             DecodedKey::Unicode('\u{8}') => {
-                if self.window_status == WindowStatus::AwaitingInput || 
-                   self.window_status == WindowStatus::EditingFile {
-                    self.handle_unicode('\u{8}');
+                if self.accepts_text() {
+                    self.handle_unicode(screen, '\u{8}');
+                }
+            },
+            DecodedKey::Unicode('\u{7f}') | DecodedKey::RawKey(KeyCode::Delete) => {
+                if self.accepts_text() {
+                    self.delete_forward(screen);
                 }
             },
             DecodedKey::Unicode(char) => {
-                if self.window_status == WindowStatus::AwaitingInput ||
-                   self.window_status == WindowStatus::EditingFile {
-                    self.handle_unicode(char);
+                if self.accepts_text() {
+                    self.handle_unicode(screen, char);
                 }
             },
             DecodedKey::RawKey(_) => {},
         }
     }
 
-    fn handle_unicode(&mut self, key: char) {
+    /// Moves the editor's cursor to the screen cell at (`col`, `row`), or as
+    /// near as the text allows. Clicks on the border do nothing.
+    fn click(&mut self, screen: &mut dyn Screen, col: usize, row: usize) {
+        if self.window_status != WindowStatus::EditingFile || col < self.start_col || row < self.start_row ||
+           col >= self.start_col + WINDOW_WIDTH || row >= self.start_row + self.rows {
+            return;
+        }
+        let line: usize = min(self.top_line + row - self.start_row, self.used_lines().saturating_sub(1));
+        self.cursor_position = Col::new(col - self.start_col);
+        self.jump_to_row(screen, line);
+    }
+
+    fn jump_to_row(&mut self, screen: &mut dyn Screen, row: usize) {
+        self.current_row = Line::new(row);
+        self.scroll_to_cursor();
+        let line_length: usize = self.get_line_length(self.current_row.get());
+        self.cursor_position = Col::new(min(self.cursor_position.get(), line_length));
+        self.num_letters = line_length;
+        self.next_letter = line_length;
+        self.draw_all_lines(screen);
+    }
+
+    /// Removes the character under the cursor, pulling the rest of the
+    /// line left.
+    fn delete_forward(&mut self, screen: &mut dyn Screen) {
+        let col: usize = self.cursor_position.get();
+        if col >= self.num_letters {
+            return;
+        }
+        let (row_to_use, offset): (usize, usize) = if self.window_status == WindowStatus::AwaitingInput {
+            (self.input_row, 1)
+        } else {
+            (self.current_row.get(), 0)
+        };
+        self.letters[row_to_use].copy_within(col + 1..self.num_letters, col);
+        self.letters[row_to_use][self.num_letters - 1] = '\0';
+        self.num_letters -= 1;
+        self.next_letter = self.num_letters;
+        self.mark_modified();
+        self.clear_line(screen, self.get_actual_row() + offset);
+        self.draw_current(screen, offset);
+    }
+
+    fn mark_modified(&mut self) {
+        if self.window_status == WindowStatus::EditingFile {
+            self.modified = true;
+        }
+    }
+
+    fn handle_unicode(&mut self, screen: &mut dyn Screen, key: char) {
         if key == '\n' {
             if self.window_status == WindowStatus::AwaitingInput {
                 let mut input_string: ArrayString<33> = ArrayString::default();
                 for i in 0..self.num_letters {
                     input_string.push_char(self.letters[self.input_row][i]);
                 }
-                self.cursor_position = 0;
+                self.cursor_position = Col::FIRST;
                 self.num_letters = 0;
                 self.next_letter = 0;
                 self.window_status = WindowStatus::ExecutingFile;
                 self.program_running = true;
                 self.array_string = input_string;
             } else {
-                self.start_new_line(0);
+                self.split_line(screen);
             }
         } else if key == '\u{8}' {
-            if self.cursor_position > 0 {
+            if self.cursor_position.is_first() && self.window_status == WindowStatus::EditingFile {
+                self.join_line(screen);
+            } else if !self.cursor_position.is_first() {
                 let row_to_use: usize = if self.window_status == WindowStatus::AwaitingInput {
                     self.input_row
                 } else {
-                    self.current_row
+                    self.current_row.get()
                 };
-                for i in self.cursor_position-1..self.num_letters-1 {
+                for i in self.cursor_position.get()-1..self.num_letters-1 {
                     self.letters[row_to_use][i] = self.letters[row_to_use][i+1];
                 }
                 self.letters[row_to_use][self.num_letters-1] = '\0';
                 self.num_letters -= 1;
                 self.next_letter = self.num_letters;
-                self.cursor_position -= 1;
-                self.clear_line(self.get_actual_row() + 
+                self.cursor_position = self.cursor_position.previous();
+                self.mark_modified();
+                self.clear_line(screen, self.get_actual_row() + 
                     (if self.window_status == WindowStatus::AwaitingInput { 1 } else { 0 }));
-                self.draw_current(if self.window_status == WindowStatus::AwaitingInput { 1 } else { 0 });
+                self.draw_current(screen, if self.window_status == WindowStatus::AwaitingInput { 1 } else { 0 });
             }
         } else if is_drawable(key) {
             let row_to_use: usize = if self.window_status == WindowStatus::AwaitingInput {
                 self.input_row
             } else {
-                self.current_row
+                self.current_row.get()
             };
-            self.letters[row_to_use][self.cursor_position] = key;
-            self.next_letter = min(add1::<WINDOW_WIDTH>(self.next_letter), WINDOW_WIDTH - 1);
+            // Typing inserts. A full line drops its last character to make
+            // room.
+            let col: usize = self.cursor_position.get();
+            let end: usize = min(self.num_letters, WINDOW_WIDTH - 1);
+            if col < end {
+                self.letters[row_to_use].copy_within(col..end, col + 1);
+            }
+            self.letters[row_to_use][col] = key;
+            self.next_letter = min(self.next_letter + 1, WINDOW_WIDTH - 1);
             self.num_letters = min(self.num_letters + 1, WINDOW_WIDTH);
-            self.cursor_position = min(add1::<WINDOW_WIDTH>(self.cursor_position), WINDOW_WIDTH - 1);
-            self.clear_line(self.get_actual_row() + 
+            self.cursor_position = self.cursor_position.next();
+            self.mark_modified();
+            self.clear_line(screen, self.get_actual_row() + 
                 (if self.window_status == WindowStatus::AwaitingInput { 1 } else { 0 }));
-                self.draw_current(if self.window_status == WindowStatus::AwaitingInput { 1 } else { 0 });
+                self.draw_current(screen, if self.window_status == WindowStatus::AwaitingInput { 1 } else { 0 });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pluggable_interrupt_os::vga_buffer::BUFFER_HEIGHT;
+
+    /// A manager is too big for the default test thread's stack.
+    fn on_big_stack(test: fn()) {
+        std::thread::Builder::new().stack_size(64 << 20).spawn(test).unwrap().join().unwrap();
+    }
+
+    /// The text `window` shows inside its border on `manager`'s screen.
+    fn window_text<const N: usize>(manager: &SwimDocManager<N, MemoryScreen>, window: usize) -> String {
+        let doc: &SwimDocument = &manager.documents[window];
+        let text: TextGrid = manager.screen().text();
+        let mut shown: String = String::new();
+        for row in doc.visible_rows() {
+            shown.extend(&text[row][doc.start_col..doc.start_col + WINDOW_WIDTH]);
+            shown.push('\n');
         }
+        shown
+    }
+
+    #[test]
+    fn created_file_is_listed() {
+        on_big_stack(|| {
+            let mut manager: SwimDocManager<NUM_WINDOWS, MemoryScreen> = SwimDocManager::default();
+            manager.update();
+            assert!(window_text(&manager, 0).contains("hello"));
+            assert!(!window_text(&manager, 0).contains("notes"));
+            manager.key(DecodedKey::RawKey(KeyCode::F5));
+            for char in "notes".chars() {
+                manager.key(DecodedKey::Unicode(char));
+            }
+            manager.key(DecodedKey::Unicode('\n'));
+            manager.update();
+            assert!(window_text(&manager, 0).contains("notes"));
+        });
+    }
+
+    #[test]
+    fn six_windows_take_f5_and_f6() {
+        on_big_stack(|| {
+            let mut manager: SwimDocManager<6, MemoryScreen> = SwimDocManager::default();
+            manager.update();
+            manager.key(DecodedKey::RawKey(KeyCode::F6));
+            assert_eq!(manager.active_window, 5);
+            manager.key(DecodedKey::RawKey(KeyCode::F5));
+            assert_eq!(manager.active_window, 4);
+            assert!(!manager.prompt_open());
+            assert!(manager.execute(Command::NewFile).ok() == Some(Effect::PromptOpened));
+            for char in "notes".chars() {
+                manager.key(DecodedKey::Unicode(char));
+            }
+            manager.key(DecodedKey::Unicode('\n'));
+            manager.update();
+            assert!(window_text(&manager, 5).contains("notes"));
+            assert_eq!(manager.documents[5].rows, manager.screen.window_rows());
+        });
+    }
+
+    #[test]
+    fn taller_text_mode_lays_windows_out_again() {
+        on_big_stack(|| {
+            let mut manager: SwimDocManager<4, MemoryScreen> = SwimDocManager::default();
+            assert!(manager.execute(Command::CreateFile("notes")).ok() == Some(Effect::FileCreated));
+            assert!(manager.execute(Command::SetTextMode(TextMode::Text80x50)).ok() == Some(Effect::TextModeSet));
+            manager.update();
+            assert_eq!(manager.documents[3].rows, 22);
+            assert!(manager.documents[3].start_row >= BUFFER_HEIGHT);
+            assert!(window_text(&manager, 3).contains("notes"));
+            assert!(manager.execute(Command::SetTextMode(TextMode::Text80x25)).ok() == Some(Effect::TextModeSet));
+            manager.update();
+            assert_eq!(manager.documents[3].rows, 10);
+            assert!(window_text(&manager, 3).contains("notes"));
+            assert!(manager.screen().text()[BUFFER_HEIGHT..].iter().all(|row| row.iter().all(|&char| char == ' ')));
+        });
+    }
+
+    #[test]
+    fn tokens_without_spaces_are_counted() {
+        assert_eq!(token_count("x:=(x+1)"), 8);
+        assert_eq!(token_count("print(\"a b\")"), 4);
+        assert_eq!(token_count("print((2 + 3))"), 8);
     }
 }
@@ -0,0 +1,85 @@
+use core::ops::Range;
+
+const MAX_LINES: usize = 256;
+const NO_LINE: Range<usize> = 0..0;
+
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum LineTool {
+    Sort,
+    Unique
+}
+
+impl LineTool {
+    /// Appended to the source filename to name the result.
+    pub(crate) fn suffix(self) -> &'static str {
+        match self {
+            LineTool::Sort => "sort",
+            LineTool::Unique => "uniq"
+        }
+    }
+}
+
+/// Applies `tool` to the lines of `input` and writes them, each ending in a
+/// newline, to `output`. Returns the number of lines and bytes written, or
+/// `None` if `input` has more than `MAX_LINES` lines.
+pub(crate) fn apply(tool: LineTool, input: &[u8], output: &mut [u8]) -> Option<(usize, usize)> {
+    let mut lines: [Range<usize>; MAX_LINES] = [NO_LINE; MAX_LINES];
+    let mut num_lines: usize = 0;
+    let mut start: usize = 0;
+    for (i, &byte) in input.iter().enumerate() {
+        if byte == b'\n' || i + 1 == input.len() {
+            if num_lines == MAX_LINES {
+                return None;
+            }
+            let end: usize = if byte == b'\n' { i } else { i + 1 };
+            lines[num_lines] = start..end;
+            num_lines += 1;
+            start = i + 1;
+        }
+    }
+    let lines: &mut [Range<usize>] = &mut lines[0..num_lines];
+    let kept: usize = match tool {
+        LineTool::Sort => {
+            sort(lines, input);
+            num_lines
+        },
+        LineTool::Unique => remove_duplicates(lines, input)
+    };
+    let mut written: usize = 0;
+    for line in lines[0..kept].iter() {
+        let line: &[u8] = &input[line.clone()];
+        if written + line.len() + 1 > output.len() {
+            break;
+        }
+        output[written..written + line.len()].copy_from_slice(line);
+        output[written + line.len()] = b'\n';
+        written += line.len() + 1;
+    }
+    Some((kept, written))
+}
+
+/// Insertion sort of the line ranges by the bytes they cover. Stable, and
+/// needs no memory beyond the ranges themselves.
+fn sort(lines: &mut [Range<usize>], input: &[u8]) {
+    for i in 1..lines.len() {
+        let mut j: usize = i;
+        while j > 0 && input[lines[j - 1].clone()] > input[lines[j].clone()] {
+            lines.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+/// Moves the first copy of each distinct line to the front, keeping their
+/// order, and returns how many there are.
+fn remove_duplicates(lines: &mut [Range<usize>], input: &[u8]) -> usize {
+    let mut kept: usize = 0;
+    for i in 0..lines.len() {
+        let line: &[u8] = &input[lines[i].clone()];
+        if !lines[0..kept].iter().any(|earlier| &input[earlier.clone()] == line) {
+            lines.swap(kept, i);
+            kept += 1;
+        }
+    }
+    kept
+}
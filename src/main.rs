@@ -1,16 +1,19 @@
 #![no_std]
 #![no_main]
 
+use bootloader::BootInfo;
 use crossbeam::atomic::AtomicCell;
 use pc_keyboard::DecodedKey;
 use pluggable_interrupt_os::{vga_buffer::clear_screen, HandlerTable};
-use csci320_project7::SwimDocManager;
+use csci320_project7::{map_physical_memory, paint_stack, KeyQueue, SwimDocManager};
 
-static LAST_KEY: AtomicCell<Option<DecodedKey>> = AtomicCell::new(None);
+static KEYS: KeyQueue<16> = KeyQueue::new();
 static TICKED: AtomicCell<bool> = AtomicCell::new(false);
 
 #[no_mangle]
-pub extern "C" fn _start() -> ! {
+pub extern "C" fn _start(boot_info: &'static BootInfo) -> ! {
+    paint_stack();
+    map_physical_memory(boot_info.physical_memory_offset);
     HandlerTable::new()
         .keyboard(key)
         .timer(tick)
@@ -21,20 +24,27 @@ pub extern "C" fn _start() -> ! {
 
 fn cpu_loop() -> ! {
     let mut doc_manager: SwimDocManager = SwimDocManager::default();
+    doc_manager.attach_disk();
+    doc_manager.attach_mouse();
+    doc_manager.self_test();
     loop {
         if let Ok(_) = TICKED.compare_exchange(true, false) {
-            doc_manager.update();
-        }
-        if let Ok(k) = LAST_KEY.fetch_update(|k| if k.is_some() {Some(None)} else {None}) {
-            if let Some(k) = k {
+            // The CPU loop is the only consumer.
+            while let Some(k) = unsafe { KEYS.pop() } {
                 doc_manager.key(k);
             }
+            let dropped: usize = KEYS.take_dropped();
+            if dropped > 0 {
+                doc_manager.keys_dropped(dropped);
+            }
+            doc_manager.update();
         }
     }
 }
 
 fn key(key: DecodedKey) {
-    LAST_KEY.store(Some(key));
+    // The keyboard interrupt is the only producer, and it does not nest.
+    unsafe { KEYS.push(key) };
 }
 
 fn tick() {
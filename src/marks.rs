@@ -0,0 +1,54 @@
+use crate::file_name::FileName;
+use crate::MAX_FILES_STORED;
+
+/// Files marked with Space in a window's file list, for the commands that
+/// work on several files at once. Kept in the order they were marked.
+#[derive(Copy, Clone)]
+pub(crate) struct FileMarks {
+    names: [FileName; MAX_FILES_STORED],
+    count: usize
+}
+
+impl FileMarks {
+    pub(crate) fn new() -> Self {
+        Self {
+            names: [FileName::empty(); MAX_FILES_STORED],
+            count: 0
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.count
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub(crate) fn contains(&self, filename: &str) -> bool {
+        self.iter().any(|name| name.as_str() == filename)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &FileName> {
+        self.names[0..self.count].iter()
+    }
+
+    /// Marks `filename`, or unmarks it if it was marked.
+    pub(crate) fn toggle(&mut self, filename: FileName) {
+        if self.contains(filename.as_str()) {
+            self.remove(filename.as_str());
+        } else if self.count < self.names.len() {
+            self.names[self.count] = filename;
+            self.count += 1;
+        }
+    }
+
+    /// Unmarks `filename`, for a file that has gone.
+    pub(crate) fn remove(&mut self, filename: &str) {
+        let found: Option<usize> = self.iter().position(|name| name.as_str() == filename);
+        if let Some(i) = found {
+            self.names.copy_within(i + 1..self.count, i);
+            self.count -= 1;
+        }
+    }
+}
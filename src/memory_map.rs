@@ -0,0 +1,46 @@
+use core::mem::size_of;
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::output::ProgramOutput;
+use crate::render::Screen;
+use crate::save_queue::SaveQueue;
+use crate::scratch::ScratchArena;
+use crate::{SwimDocManager, SwimDocument, SwimFileSystem, SwimHeap, SwimInterpreter, MAX_EDITOR_LINES,
+            NUM_WINDOWS, WINDOW_WIDTH};
+
+/// Where the sizes start, past the longest label.
+const VALUE_COL: usize = 14;
+
+/// Bytes taken by the largest structures, worked out by the compiler for
+/// the profile being built. Indented entries are part of the one above.
+/// Everything except the scratch arena lives inside the manager.
+const SIZES: [(&str, usize); 9] = [
+    ("manager", size_of::<SwimDocManager>()),
+    ("window", size_of::<SwimDocument>()),
+    (" editor", size_of::<[[char; WINDOW_WIDTH]; MAX_EDITOR_LINES]>()),
+    (" filesystem", size_of::<SwimFileSystem>()),
+    (" scrollback", size_of::<ProgramOutput>()),
+    ("interpreter", size_of::<Option<SwimInterpreter>>()),
+    (" heap", size_of::<SwimHeap>()),
+    ("save queue", size_of::<SaveQueue>()),
+    ("scratch", size_of::<ScratchArena>())
+];
+
+/// Diagnostics page listing what the static structures cost, to keep an
+/// eye on the memory budget as features are added. Windows and
+/// interpreters come `NUM_WINDOWS` to a manager.
+pub(crate) fn draw_sizes(screen: &mut dyn Screen, start_col: usize, start_row: usize, rows: usize) {
+    let title_color: ColorCode = ColorCode::new(Color::White, Color::Black);
+    for row in 0..rows {
+        for col in start_col..start_col + WINDOW_WIDTH {
+            screen.plot(' ', col, start_row + row, ColorCode::new(Color::Black, Color::Black));
+        }
+    }
+    screen.plot_str("bytes, windows x", start_col, start_row, title_color);
+    screen.plot_num(NUM_WINDOWS as isize, start_col + 16, start_row, title_color);
+    let color: ColorCode = ColorCode::new(Color::LightGray, Color::Black);
+    for (row, (label, bytes)) in SIZES.iter().take(rows - 1).enumerate() {
+        screen.plot_str(label, start_col, start_row + row + 1, color);
+        screen.plot_num(*bytes as isize, start_col + VALUE_COL, start_row + row + 1, color);
+    }
+}
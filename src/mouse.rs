@@ -0,0 +1,170 @@
+use pluggable_interrupt_os::vga_buffer::BUFFER_WIDTH;
+use x86_64::instructions::interrupts;
+
+use crate::port::Port;
+
+const DATA_PORT: u16 = 0x60;
+const STATUS_PORT: u16 = 0x64;
+const COMMAND_PORT: u16 = 0x64;
+
+const STATUS_OUTPUT_FULL: u8 = 0x01;
+const STATUS_INPUT_FULL: u8 = 0x02;
+const STATUS_FROM_MOUSE: u8 = 0x20;
+
+const READ_CONFIG: u8 = 0x20;
+const WRITE_CONFIG: u8 = 0x60;
+const ENABLE_MOUSE_PORT: u8 = 0xa8;
+const SEND_TO_MOUSE: u8 = 0xd4;
+/// Set in the controller's configuration byte to raise IRQ 12 for mouse
+/// bytes. Nothing handles IRQ 12, so it is kept clear.
+const CONFIG_MOUSE_IRQ: u8 = 0x02;
+
+const SET_DEFAULTS: u8 = 0xf6;
+const ENABLE_REPORTING: u8 = 0xf4;
+const ACKNOWLEDGED: u8 = 0xfa;
+
+/// Set in the first byte of every packet, to find the start of one again
+/// after a byte is lost.
+const PACKET_ALWAYS_SET: u8 = 0x08;
+const PACKET_LEFT_BUTTON: u8 = 0x01;
+const PACKET_X_NEGATIVE: u8 = 0x10;
+const PACKET_Y_NEGATIVE: u8 = 0x20;
+const PACKET_OVERFLOW: u8 = 0xc0;
+
+/// Status reads before the controller is given up on.
+const MAX_POLLS: usize = 100_000;
+/// Mouse movement counts per character cell. Cells are twice as tall as
+/// they are wide.
+const COUNTS_PER_COL: isize = 8;
+const COUNTS_PER_ROW: isize = 16;
+
+/// A PS/2 mouse on the keyboard controller's second port, read by polling
+/// once a frame. The interrupt handlers only know about the keyboard and
+/// the timer, so the mouse's IRQ is left off.
+pub(crate) struct Mouse {
+    packet: [u8; 3],
+    received: usize,
+    /// Where the pointer is, in movement counts from the top left.
+    x: isize,
+    y: isize,
+    /// Rows the pointer can move over.
+    rows: usize,
+    left_down: bool
+}
+
+impl Mouse {
+    /// The mouse, if the controller has one and it agrees to report, with
+    /// its pointer in the middle of a screen `rows` tall.
+    pub(crate) fn attach(rows: usize) -> Option<Self> {
+        interrupts::without_interrupts(|| {
+            write_command(ENABLE_MOUSE_PORT)?;
+            write_command(READ_CONFIG)?;
+            let config: u8 = read_data()?;
+            write_command(WRITE_CONFIG)?;
+            write_data(config & !CONFIG_MOUSE_IRQ)?;
+            send_to_mouse(SET_DEFAULTS)?;
+            send_to_mouse(ENABLE_REPORTING)
+        })?;
+        Some(Self {
+            packet: [0; 3],
+            received: 0,
+            x: (BUFFER_WIDTH as isize / 2) * COUNTS_PER_COL,
+            y: (rows as isize / 2) * COUNTS_PER_ROW,
+            rows,
+            left_down: false
+        })
+    }
+
+    /// Keeps the pointer on a screen that is now `rows` tall.
+    pub(crate) fn set_rows(&mut self, rows: usize) {
+        self.rows = rows;
+        self.y = self.y.min(rows as isize * COUNTS_PER_ROW - 1);
+    }
+
+    /// The cell under the pointer, as (col, row).
+    pub(crate) fn cell(&self) -> (usize, usize) {
+        ((self.x / COUNTS_PER_COL) as usize, (self.y / COUNTS_PER_ROW) as usize)
+    }
+
+    /// Reads whatever the mouse has sent since the last call. Returns the
+    /// cell clicked, if the left button went down.
+    pub(crate) fn poll(&mut self) -> Option<(usize, usize)> {
+        let mut clicked: Option<(usize, usize)> = None;
+        while let Some(byte) = interrupts::without_interrupts(read_mouse_byte) {
+            if self.received == 0 && byte & PACKET_ALWAYS_SET == 0 {
+                continue;
+            }
+            self.packet[self.received] = byte;
+            self.received += 1;
+            if self.received == self.packet.len() {
+                self.received = 0;
+                if self.apply_packet() {
+                    clicked = Some(self.cell());
+                }
+            }
+        }
+        clicked
+    }
+
+    /// Moves the pointer and notes the button. Returns whether the left
+    /// button went down.
+    fn apply_packet(&mut self) -> bool {
+        let [flags, dx, dy]: [u8; 3] = self.packet;
+        if flags & PACKET_OVERFLOW == 0 {
+            let dx: isize = if flags & PACKET_X_NEGATIVE != 0 { dx as isize - 256 } else { dx as isize };
+            let dy: isize = if flags & PACKET_Y_NEGATIVE != 0 { dy as isize - 256 } else { dy as isize };
+            // The mouse counts up the screen, rows count down it.
+            self.x = (self.x + dx).clamp(0, BUFFER_WIDTH as isize * COUNTS_PER_COL - 1);
+            self.y = (self.y - dy).clamp(0, self.rows as isize * COUNTS_PER_ROW - 1);
+        }
+        let was_down: bool = self.left_down;
+        self.left_down = flags & PACKET_LEFT_BUTTON != 0;
+        self.left_down && !was_down
+    }
+}
+
+fn read_status() -> u8 {
+    let mut port: Port<u8> = Port::new(STATUS_PORT);
+    unsafe { port.read() }
+}
+
+fn wait_for(ready: impl Fn(u8) -> bool) -> Option<()> {
+    (0..MAX_POLLS).any(|_| ready(read_status())).then_some(())
+}
+
+fn write_command(command: u8) -> Option<()> {
+    wait_for(|status| status & STATUS_INPUT_FULL == 0)?;
+    let mut port: Port<u8> = Port::new(COMMAND_PORT);
+    unsafe { port.write(command) }
+    Some(())
+}
+
+fn write_data(value: u8) -> Option<()> {
+    wait_for(|status| status & STATUS_INPUT_FULL == 0)?;
+    let mut port: Port<u8> = Port::new(DATA_PORT);
+    unsafe { port.write(value) }
+    Some(())
+}
+
+fn read_data() -> Option<u8> {
+    wait_for(|status| status & STATUS_OUTPUT_FULL != 0)?;
+    let mut port: Port<u8> = Port::new(DATA_PORT);
+    Some(unsafe { port.read() })
+}
+
+fn send_to_mouse(command: u8) -> Option<()> {
+    write_command(SEND_TO_MOUSE)?;
+    write_data(command)?;
+    (read_data()? == ACKNOWLEDGED).then_some(())
+}
+
+/// The next byte from the mouse, leaving keyboard bytes for the keyboard
+/// handler.
+fn read_mouse_byte() -> Option<u8> {
+    let status: u8 = read_status();
+    if status & STATUS_OUTPUT_FULL == 0 || status & STATUS_FROM_MOUSE == 0 {
+        return None;
+    }
+    let mut port: Port<u8> = Port::new(DATA_PORT);
+    Some(unsafe { port.read() })
+}
@@ -0,0 +1,354 @@
+use core::cmp::min;
+
+use crate::file_name::FileName;
+use crate::{suffixed_name, MAX_FILENAME_BYTES, MAX_FILES_STORED};
+
+/// Spare directory entries are named this with a number on the end. It
+/// starts with the hidden prefix, so one is never listed under its own
+/// name.
+const SPARE_ENTRY: &str = "~";
+/// The hidden file the table is kept in, so it lasts as long as the files.
+pub(crate) const NAMES_FILENAME: &str = "~names";
+/// The most `write_to()` writes: the number of aliases and of free
+/// entries, then two names for each alias and one for each free entry.
+pub(crate) const NAME_TABLE_BYTES: usize = 2 + 3 * MAX_FILES_STORED * MAX_FILENAME_BYTES;
+
+/// The names files go by, over the directory entries the filesystem keeps
+/// them in. The filesystem can neither remove nor rename a file, so:
+/// - a deleted file's entry is emptied and set aside, and the next new
+///   file takes it over instead of using up another one;
+/// - a renamed file stays in its entry, which goes by the new name.
+///
+/// A file is kept in the entry named after it until one of these happens,
+/// so most files need nothing recorded here.
+pub(crate) struct NameTable {
+    /// (name, entry) for each file kept in an entry named otherwise.
+    aliases: [(FileName, FileName); MAX_FILES_STORED],
+    num_aliases: usize,
+    /// The entries of deleted files, free for new ones.
+    free: [FileName; MAX_FILES_STORED],
+    num_free: usize,
+    /// Set by every change, until it is taken to write the table out.
+    changed: bool
+}
+
+impl NameTable {
+    pub(crate) fn new() -> Self {
+        Self {
+            aliases: [(FileName::empty(), FileName::empty()); MAX_FILES_STORED],
+            num_aliases: 0,
+            free: [FileName::empty(); MAX_FILES_STORED],
+            num_free: 0,
+            changed: false
+        }
+    }
+
+    /// The table `write_to()` wrote to `bytes`.
+    pub(crate) fn read_from(bytes: &[u8]) -> Self {
+        let mut table: NameTable = Self::new();
+        let num_aliases: usize = min(bytes.first().copied().unwrap_or(0) as usize, MAX_FILES_STORED);
+        let num_free: usize = min(bytes.get(1).copied().unwrap_or(0) as usize, MAX_FILES_STORED);
+        let mut names = bytes.get(2..).unwrap_or(&[]).chunks_exact(MAX_FILENAME_BYTES).map(FileName::from_entry);
+        for _ in 0..num_aliases {
+            if let (Some(alias), Some(entry)) = (names.next(), names.next()) {
+                table.aliases[table.num_aliases] = (alias, entry);
+                table.num_aliases += 1;
+            }
+        }
+        for entry in names.take(num_free) {
+            table.free[table.num_free] = entry;
+            table.num_free += 1;
+        }
+        table
+    }
+
+    /// Writes the table to `buffer` for `read_from()`, returning how many
+    /// bytes it took.
+    pub(crate) fn write_to(&self, buffer: &mut [u8; NAME_TABLE_BYTES]) -> usize {
+        buffer[0] = self.num_aliases as u8;
+        buffer[1] = self.num_free as u8;
+        let mut len: usize = 2;
+        let names = self.aliases[0..self.num_aliases].iter()
+            .flat_map(|(alias, entry)| [*alias, *entry])
+            .chain(self.free[0..self.num_free].iter().copied());
+        for name in names {
+            buffer[len..len + MAX_FILENAME_BYTES].fill(0);
+            buffer[len..len + name.len()].copy_from_slice(name.as_str().as_bytes());
+            len += MAX_FILENAME_BYTES;
+        }
+        len
+    }
+
+    /// Whether the table has changed since this was last asked.
+    pub(crate) fn take_changed(&mut self) -> bool {
+        core::mem::take(&mut self.changed)
+    }
+
+    fn alias(&self, name: &str) -> Option<usize> {
+        self.aliases[0..self.num_aliases].iter().position(|(alias, _)| alias.as_str() == name)
+    }
+
+    fn is_free(&self, entry: &str) -> bool {
+        self.free[0..self.num_free].iter().any(|free| free.as_str() == entry)
+    }
+
+    /// Whether `entry` is free or keeps a file named otherwise.
+    fn is_taken(&self, entry: &str) -> bool {
+        self.is_free(entry) || self.aliases[0..self.num_aliases].iter().any(|(_, kept_in)| kept_in.as_str() == entry)
+    }
+
+    /// The entry `name` is kept in, or None if it cannot be.
+    pub(crate) fn entry_of(&self, name: &str) -> Option<FileName> {
+        match self.alias(name) {
+            Some(i) => Some(self.aliases[i].1),
+            None if self.is_taken(name) => None,
+            None => Some(FileName::truncated(name))
+        }
+    }
+
+    /// The name the file in `entry` goes by, or None if `entry` is free.
+    pub(crate) fn name_of(&self, entry: FileName) -> Option<FileName> {
+        if self.is_free(entry.as_str()) {
+            return None;
+        }
+        let alias: Option<FileName> = self.aliases[0..self.num_aliases].iter()
+            .find(|(_, kept_in)| *kept_in == entry)
+            .map(|(alias, _)| *alias);
+        Some(alias.unwrap_or(entry))
+    }
+
+    /// The entry to create or overwrite `name` in. A new file takes over a
+    /// free entry if there is one. `entries` are the ones in the directory.
+    pub(crate) fn entry_for_create(&mut self, name: &str, entries: &[FileName]) -> FileName {
+        if let Some(i) = self.alias(name) {
+            return self.aliases[i].1;
+        }
+        let own: FileName = FileName::truncated(name);
+        if self.is_free(name) {
+            self.unfree(name);
+            self.changed = true;
+            return own;
+        }
+        if !self.is_taken(name) && (entries.contains(&own) || self.num_free == 0) {
+            return own;
+        }
+        self.changed = true;
+        let entry: FileName = if self.num_free > 0 {
+            self.num_free -= 1;
+            self.free[self.num_free]
+        } else {
+            // There are more spare names than entries, so one is unused.
+            (0..=MAX_FILES_STORED).map(|n| suffixed_name(SPARE_ENTRY, n))
+                .find(|spare| !entries.contains(spare))
+                .unwrap_or(own)
+        };
+        self.set_alias(own, entry);
+        entry
+    }
+
+    /// Frees the entry `name` is kept in, returning it. The caller empties
+    /// it.
+    pub(crate) fn remove(&mut self, name: &str) -> Option<FileName> {
+        let entry: FileName = self.entry_of(name)?;
+        self.changed = true;
+        self.clear_alias(name);
+        if !self.is_free(entry.as_str()) && self.num_free < self.free.len() {
+            self.free[self.num_free] = entry;
+            self.num_free += 1;
+        }
+        Some(entry)
+    }
+
+    /// `to`, which must not name a file, goes by the entry `from` had.
+    pub(crate) fn rename(&mut self, from: &str, to: &str) -> Option<()> {
+        let entry: FileName = self.entry_of(from)?;
+        self.changed = true;
+        self.clear_alias(from);
+        self.set_alias(FileName::truncated(to), entry);
+        Some(())
+    }
+
+    /// `name` goes by the entry `with` is kept in, and `with` by the one
+    /// `name` had, if `name_exists`.
+    pub(crate) fn switch(&mut self, name: &str, with: &str, name_exists: bool) -> Option<()> {
+        let with_entry: FileName = self.entry_of(with)?;
+        let name_entry: Option<FileName> = if name_exists { self.entry_of(name) } else { None };
+        self.changed = true;
+        self.clear_alias(name);
+        self.clear_alias(with);
+        self.set_alias(FileName::truncated(name), with_entry);
+        if let Some(name_entry) = name_entry {
+            self.set_alias(FileName::truncated(with), name_entry);
+        }
+        Some(())
+    }
+
+    /// A file kept in the entry named after it needs no alias.
+    fn set_alias(&mut self, name: FileName, entry: FileName) {
+        self.clear_alias(name.as_str());
+        if name != entry && self.num_aliases < self.aliases.len() {
+            self.aliases[self.num_aliases] = (name, entry);
+            self.num_aliases += 1;
+        }
+    }
+
+    fn clear_alias(&mut self, name: &str) {
+        if let Some(i) = self.alias(name) {
+            self.aliases.copy_within(i + 1..self.num_aliases, i);
+            self.num_aliases -= 1;
+        }
+    }
+
+    fn unfree(&mut self, entry: &str) {
+        if let Some(i) = self.free[0..self.num_free].iter().position(|free| free.as_str() == entry) {
+            self.free.copy_within(i + 1..self.num_free, i);
+            self.num_free -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWAP: &str = crate::save_queue::SAVE_TEMP_FILENAME;
+
+    fn names(names: &[&str]) -> Vec<FileName> {
+        names.iter().map(|name| FileName::truncated(name)).collect()
+    }
+
+    fn entry_of(table: &NameTable, name: &str) -> Option<String> {
+        table.entry_of(name).map(|entry| entry.to_string())
+    }
+
+    fn name_of(table: &NameTable, entry: &str) -> Option<String> {
+        table.name_of(FileName::truncated(entry)).map(|name| name.to_string())
+    }
+
+    #[test]
+    fn create_takes_over_a_deleted_entry() {
+        let mut table: NameTable = NameTable::new();
+        let entries: Vec<FileName> = names(&["a", "b"]);
+        assert_eq!(table.remove("a").map(|entry| entry.to_string()), Some("a".to_string()));
+        assert_eq!(entry_of(&table, "a"), None);
+        assert_eq!(name_of(&table, "a"), None);
+        assert_eq!(table.entry_for_create("c", &entries).to_string(), "a");
+        assert_eq!(entry_of(&table, "c"), Some("a".to_string()));
+        assert_eq!(name_of(&table, "a"), Some("c".to_string()));
+        let spare: FileName = table.entry_for_create("a", &entries);
+        assert_ne!(spare.to_string(), "a");
+        assert_ne!(spare.to_string(), "b");
+        assert_eq!(entry_of(&table, "a"), Some(spare.to_string()));
+        assert_eq!(entry_of(&table, "b"), Some("b".to_string()));
+    }
+
+    #[test]
+    fn recreating_a_deleted_file_takes_back_its_entry() {
+        let mut table: NameTable = NameTable::new();
+        let entries: Vec<FileName> = names(&["a"]);
+        table.remove("a");
+        assert_eq!(table.entry_for_create("a", &entries).to_string(), "a");
+        assert_eq!(entry_of(&table, "a"), Some("a".to_string()));
+        assert_eq!(table.entry_for_create("b", &entries).to_string(), "b");
+    }
+
+    #[test]
+    fn renames_chain_through_one_entry() {
+        let mut table: NameTable = NameTable::new();
+        assert_eq!(table.rename("a", "b"), Some(()));
+        assert_eq!(table.rename("b", "c"), Some(()));
+        assert_eq!(entry_of(&table, "c"), Some("a".to_string()));
+        assert_eq!(entry_of(&table, "a"), None);
+        assert_eq!(name_of(&table, "a"), Some("c".to_string()));
+        assert_eq!(table.rename("c", "a"), Some(()));
+        assert_eq!(entry_of(&table, "a"), Some("a".to_string()));
+        assert_eq!(name_of(&table, "a"), Some("a".to_string()));
+        assert_eq!(table.num_aliases, 0);
+    }
+
+    #[test]
+    fn deleting_a_renamed_file_frees_its_entry() {
+        let mut table: NameTable = NameTable::new();
+        let entries: Vec<FileName> = names(&["a"]);
+        table.rename("a", "b");
+        assert_eq!(table.remove("b").map(|entry| entry.to_string()), Some("a".to_string()));
+        assert_eq!(entry_of(&table, "b"), Some("b".to_string()));
+        assert_eq!(table.entry_for_create("d", &entries).to_string(), "a");
+        assert_eq!(name_of(&table, "a"), Some("d".to_string()));
+    }
+
+    #[test]
+    fn saves_switch_entries_back_and_forth() {
+        let mut table: NameTable = NameTable::new();
+        let entries: Vec<FileName> = names(&["notes"]);
+        assert_eq!(table.entry_for_create(SWAP, &entries).to_string(), SWAP);
+        assert_eq!(table.switch("notes", SWAP, true), Some(()));
+        assert_eq!(entry_of(&table, "notes"), Some(SWAP.to_string()));
+        assert_eq!(entry_of(&table, SWAP), Some("notes".to_string()));
+
+        let entries: Vec<FileName> = names(&["notes", SWAP]);
+        assert_eq!(table.entry_for_create(SWAP, &entries).to_string(), "notes");
+        assert_eq!(table.switch("notes", SWAP, true), Some(()));
+        assert_eq!(entry_of(&table, "notes"), Some("notes".to_string()));
+        assert_eq!(entry_of(&table, SWAP), Some(SWAP.to_string()));
+        assert_eq!(table.num_aliases, 0);
+    }
+
+    #[test]
+    fn saving_a_new_file_leaves_no_swap_behind() {
+        let mut table: NameTable = NameTable::new();
+        let entries: Vec<FileName> = names(&["notes", SWAP]);
+        table.entry_for_create(SWAP, &entries);
+        assert_eq!(table.switch("fresh", SWAP, false), Some(()));
+        assert_eq!(entry_of(&table, "fresh"), Some(SWAP.to_string()));
+        assert_eq!(name_of(&table, SWAP), Some("fresh".to_string()));
+        assert_eq!(entry_of(&table, SWAP), None);
+        assert_eq!(entry_of(&table, "notes"), Some("notes".to_string()));
+    }
+
+    #[test]
+    fn switching_a_renamed_file_during_a_save() {
+        let mut table: NameTable = NameTable::new();
+        let entries: Vec<FileName> = names(&["old", SWAP]);
+        table.rename("old", "new");
+        table.entry_for_create(SWAP, &entries);
+        assert_eq!(table.switch("new", SWAP, true), Some(()));
+        assert_eq!(entry_of(&table, "new"), Some(SWAP.to_string()));
+        assert_eq!(entry_of(&table, SWAP), Some("old".to_string()));
+        assert_eq!(name_of(&table, "old"), Some(SWAP.to_string()));
+    }
+
+    #[test]
+    fn written_table_reads_back_the_same() {
+        let mut table: NameTable = NameTable::new();
+        let entries: Vec<FileName> = names(&["a", "b", "c"]);
+        table.rename("a", "renamed");
+        table.remove("b");
+        table.remove("c");
+        table.entry_for_create("new", &entries);
+        assert!(table.take_changed());
+        assert!(!table.take_changed());
+        let mut buffer: [u8; NAME_TABLE_BYTES] = [0xFF; NAME_TABLE_BYTES];
+        let len: usize = table.write_to(&mut buffer);
+        assert_eq!(len, 2 + 5 * MAX_FILENAME_BYTES);
+        let read: NameTable = NameTable::read_from(&buffer[0..len]);
+        for name in ["a", "b", "c", "renamed", "new"] {
+            assert_eq!(entry_of(&read, name), entry_of(&table, name), "{}", name);
+            assert_eq!(name_of(&read, name), name_of(&table, name), "{}", name);
+        }
+        assert!(!NameTable::read_from(&buffer[0..len]).take_changed());
+    }
+
+    #[test]
+    fn short_or_empty_bytes_read_as_what_is_there() {
+        assert_eq!(entry_of(&NameTable::read_from(&[]), "a"), Some("a".to_string()));
+        let mut table: NameTable = NameTable::new();
+        table.remove("a");
+        table.remove("b");
+        let mut buffer: [u8; NAME_TABLE_BYTES] = [0; NAME_TABLE_BYTES];
+        let len: usize = table.write_to(&mut buffer);
+        let read: NameTable = NameTable::read_from(&buffer[0..len - 1]);
+        assert_eq!(entry_of(&read, "a"), None);
+        assert_eq!(entry_of(&read, "b"), Some("b".to_string()));
+    }
+}
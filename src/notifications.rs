@@ -0,0 +1,97 @@
+use core::fmt::{self, Write};
+use core::str;
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::byte_writer::ByteWriter;
+use crate::file_name::FileName;
+use crate::observer::Observer;
+use crate::render::Screen;
+use crate::{plot_abbreviated, WINDOW_WIDTH};
+
+/// As many as the list shows at once. Older ones are dropped.
+const MAX_NOTIFICATIONS: usize = 9;
+
+/// A program that finished in a window nobody was looking at.
+#[derive(Copy, Clone)]
+pub(crate) struct Notification {
+    window: usize,
+    program: FileName,
+    status: &'static str,
+    lines: usize
+}
+
+impl Notification {
+    pub(crate) fn new(window: usize, program: FileName, status: &'static str, lines: usize) -> Self {
+        Self { window, program, status, lines }
+    }
+}
+
+impl fmt::Display for Notification {
+    /// "F3: pi finished, 4 lines of output"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural: &str = if self.lines == 1 { "" } else { "s" };
+        write!(f, "F{}: {} {}, {} line{} of output", self.window + 1, self.program, self.status, self.lines, plural)
+    }
+}
+
+/// The most recent notifications, newest first, for reviewing after the
+/// status line has moved on.
+pub(crate) struct Notifications {
+    entries: [Option<Notification>; MAX_NOTIFICATIONS],
+    unread: usize
+}
+
+impl Notifications {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: [None; MAX_NOTIFICATIONS],
+            unread: 0
+        }
+    }
+
+    pub(crate) fn push(&mut self, notification: Notification) {
+        self.entries.rotate_right(1);
+        self.entries[0] = Some(notification);
+        self.unread = (self.unread + 1).min(MAX_NOTIFICATIONS);
+    }
+
+    pub(crate) fn mark_read(&mut self) {
+        self.unread = 0;
+    }
+
+    /// Unread entries are drawn brighter than the ones already seen.
+    pub(crate) fn draw(&self, screen: &mut dyn Screen, start_col: usize, start_row: usize, rows: usize) {
+        let title_color: ColorCode = ColorCode::new(Color::White, Color::Black);
+        for col in 0..WINDOW_WIDTH {
+            screen.plot(' ', start_col + col, start_row, ColorCode::new(Color::Black, Color::Black));
+        }
+        screen.plot_num(self.unread as isize, start_col, start_row, title_color);
+        screen.plot_str(" unread", start_col + 1, start_row, title_color);
+        for row in 1..rows {
+            for col in 0..WINDOW_WIDTH {
+                screen.plot(' ', start_col + col, start_row + row, ColorCode::new(Color::Black, Color::Black));
+            }
+            if let Some(notification) = self.entries.get(row - 1).copied().flatten() {
+                let color: ColorCode = if row <= self.unread {
+                    ColorCode::new(Color::White, Color::Black)
+                } else {
+                    ColorCode::new(Color::DarkGray, Color::Black)
+                };
+                let mut buffer: [u8; 2 * WINDOW_WIDTH] = [0; 2 * WINDOW_WIDTH];
+                let mut text: ByteWriter = ByteWriter::new(&mut buffer);
+                let _ = write!(text, "{}", notification);
+                let len: usize = text.len();
+                plot_abbreviated(screen, str::from_utf8(&buffer[0..len]).unwrap_or(""), WINDOW_WIDTH, start_col,
+                                 start_row + row, color);
+            }
+        }
+    }
+}
+
+impl Observer for Notifications {
+    fn on_program_finished(&mut self, finished: &Notification, watched: bool) {
+        if !watched {
+            self.push(*finished);
+        }
+    }
+}
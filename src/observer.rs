@@ -0,0 +1,54 @@
+use core::fmt::Write;
+use core::str;
+
+use crate::byte_writer::ByteWriter;
+use crate::notifications::Notification;
+use crate::output;
+use crate::{WindowStatus, WINDOW_WIDTH};
+
+/// Something that reacts to changes in the windows, told about them by the
+/// manager so the places that make the changes need not know who cares.
+/// Every method does nothing unless overridden.
+pub(crate) trait Observer {
+    /// Called once a frame for each window that changed mode during it.
+    fn on_mode_change(&mut self, _window: usize, _from: WindowStatus, _to: WindowStatus) {}
+
+    /// Called once a queued save has been written in full.
+    fn on_file_saved(&mut self, _frame: usize, _window: usize, _filename: &str) {}
+
+    /// Called when a program runs to its end. `watched` is set when its
+    /// window was active and on screen at the time.
+    fn on_program_finished(&mut self, _finished: &Notification, _watched: bool) {}
+}
+
+/// Writes every change to the first serial port as a "swim: " line, for
+/// whoever is following along from outside the machine, while output is
+/// mirrored there.
+pub(crate) struct SerialEvents;
+
+impl SerialEvents {
+    fn write(args: core::fmt::Arguments) {
+        if !output::is_mirroring() {
+            return;
+        }
+        let mut buffer: [u8; 2 * WINDOW_WIDTH] = [0; 2 * WINDOW_WIDTH];
+        let mut line: ByteWriter = ByteWriter::new(&mut buffer);
+        let _ = write!(line, "swim: {}", args);
+        let len: usize = line.len();
+        output::write_serial_line(str::from_utf8(&buffer[0..len]).unwrap_or(""));
+    }
+}
+
+impl Observer for SerialEvents {
+    fn on_mode_change(&mut self, window: usize, _from: WindowStatus, to: WindowStatus) {
+        Self::write(format_args!("F{} {}", window + 1, to.name()));
+    }
+
+    fn on_file_saved(&mut self, _frame: usize, window: usize, filename: &str) {
+        Self::write(format_args!("F{} saved {}", window + 1, filename));
+    }
+
+    fn on_program_finished(&mut self, finished: &Notification, _watched: bool) {
+        Self::write(format_args!("{}", finished));
+    }
+}
@@ -0,0 +1,326 @@
+use core::cmp::min;
+use core::fmt::{self, Write};
+use core::str;
+use core::sync::atomic::{AtomicBool, Ordering};
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+use simple_interp::InterpreterOutput;
+
+use crate::byte_writer::ByteWriter;
+use crate::file_name::FileName;
+use crate::render::Screen;
+use crate::save_queue::PendingSave;
+use crate::sound::Tone;
+use crate::uart;
+use crate::{MAX_SAVE_BYTES, WINDOW_WIDTH};
+
+/// Output lines starting with this are headings.
+const HEADING_PREFIX: &[u8] = b"# ";
+
+/// The color for a line of program output. Headings stand out from the
+/// rest, so long output can be skimmed in a small window. Text mode has one
+/// font size, so emphasis is by color alone.
+pub(crate) fn line_color(line: &[u8]) -> ColorCode {
+    if line.starts_with(HEADING_PREFIX) {
+        ColorCode::new(Color::Yellow, Color::Black)
+    } else {
+        ColorCode::new(Color::White, Color::Black)
+    }
+}
+
+/// Somewhere a program's printed lines are kept or copied to, besides its
+/// window.
+pub(crate) trait OutputSink {
+    fn write_line(&mut self, line: &str);
+}
+
+/// The window the program runs in. Once the window is full, the oldest
+/// lines are cleared and printing continues on the bottom row. A hidden
+/// window prints nothing.
+pub(crate) struct WindowScrollback {
+    start_col: usize,
+    start_row: usize,
+    rows: usize,
+    next_line: usize,
+    hidden: bool
+}
+
+impl WindowScrollback {
+    fn new(start_col: usize, start_row: usize, rows: usize) -> Self {
+        Self {
+            start_col,
+            start_row,
+            rows,
+            next_line: 0,
+            hidden: false
+        }
+    }
+
+    fn clear_line(&self, screen: &mut dyn Screen, line: usize) {
+        for col in self.start_col..self.start_col + WINDOW_WIDTH {
+            screen.plot(' ', col, self.start_row + line, ColorCode::new(Color::Black, Color::Black));
+        }
+    }
+
+    fn write_line(&mut self, screen: &mut dyn Screen, line: &str) {
+        if self.hidden {
+            return;
+        }
+        if self.next_line >= self.rows {
+            for row in 0..self.rows - 1 {
+                self.clear_line(screen, row);
+            }
+            self.next_line = self.rows - 1;
+        }
+        self.clear_line(screen, self.next_line);
+        screen.plot_str(line, self.start_col, self.start_row + self.next_line, line_color(line.as_bytes()));
+        self.next_line += 1;
+    }
+}
+
+/// Collects output into a save for a file, one line per print. Output past
+/// the size of a save is dropped.
+pub(crate) struct FileCapture {
+    save: PendingSave
+}
+
+impl FileCapture {
+    fn new(window: usize, filename: FileName) -> Self {
+        let mut save: PendingSave = PendingSave::new(window);
+        save.filename = filename;
+        Self { save }
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if self.save.contents_len < MAX_SAVE_BYTES {
+                self.save.contents[self.save.contents_len] = byte;
+                self.save.contents_len += 1;
+            }
+        }
+    }
+
+    /// Adds `line` as the last line, dropping earlier output if that is the
+    /// only way to fit it.
+    fn write_last_line(&mut self, line: &str) {
+        let needed: usize = min(line.len() + 1, MAX_SAVE_BYTES);
+        self.save.contents_len = min(self.save.contents_len, MAX_SAVE_BYTES - needed);
+        self.write_line(line);
+    }
+}
+
+impl OutputSink for FileCapture {
+    fn write_line(&mut self, line: &str) {
+        self.push_bytes(line.as_bytes());
+        self.push_bytes(b"\n");
+    }
+}
+
+/// Whether program output and errors are copied to the serial port.
+static MIRRORING: AtomicBool = AtomicBool::new(true);
+
+pub(crate) fn set_mirroring(on: bool) {
+    MIRRORING.store(on, Ordering::Relaxed);
+}
+
+pub(crate) fn is_mirroring() -> bool {
+    MIRRORING.load(Ordering::Relaxed)
+}
+
+/// Copies an error shown on screen to the serial port, while output is
+/// mirrored there.
+pub(crate) fn mirror_error(args: fmt::Arguments) {
+    if !is_mirroring() {
+        return;
+    }
+    let mut buffer: [u8; 2 * WINDOW_WIDTH] = [0; 2 * WINDOW_WIDTH];
+    let mut line: ByteWriter = ByteWriter::new(&mut buffer);
+    let _ = write!(line, "swim: error: {}", args);
+    let len: usize = line.len();
+    write_serial_line(str::from_utf8(&buffer[0..len]).unwrap_or(""));
+}
+
+fn write_serial_byte(byte: u8) {
+    uart::COM1.write_byte(byte);
+}
+
+/// Writes `line` to the first serial port, followed by a newline.
+pub(crate) fn write_serial_line(line: &str) {
+    for byte in line.bytes().chain(Some(b'\n')) {
+        write_serial_byte(byte);
+    }
+}
+
+/// Copies output to the first serial port, tagged with its window, unless
+/// mirroring has been turned off.
+pub(crate) struct SerialMirror {
+    window: usize
+}
+
+impl OutputSink for SerialMirror {
+    fn write_line(&mut self, line: &str) {
+        if !is_mirroring() {
+            return;
+        }
+        for byte in [b'F', b'1' + self.window as u8, b'>', b' '] {
+            write_serial_byte(byte);
+        }
+        write_serial_line(line);
+    }
+}
+
+/// Everything a running program prints, handed to each sink in turn.
+/// `!tone` lines are held back as a tone request instead. A transcript also
+/// records the lines typed in reply to prompts and how the run ended.
+pub(crate) struct ProgramOutput {
+    window: usize,
+    program: FileName,
+    lines: usize,
+    scrollback: WindowScrollback,
+    capture: Option<FileCapture>,
+    transcript: Option<FileCapture>,
+    serial: SerialMirror,
+    requested_tone: Option<Tone>,
+    ticks: usize,
+    last_line: [u8; WINDOW_WIDTH],
+    last_line_len: usize
+}
+
+impl ProgramOutput {
+    pub(crate) fn new(window: usize, start_col: usize, start_row: usize, rows: usize) -> Self {
+        Self {
+            window,
+            program: FileName::empty(),
+            lines: 0,
+            scrollback: WindowScrollback::new(start_col, start_row, rows),
+            capture: None,
+            transcript: None,
+            serial: SerialMirror { window },
+            requested_tone: None,
+            ticks: 0,
+            last_line: [0; WINDOW_WIDTH],
+            last_line_len: 0
+        }
+    }
+
+    /// What a program prints during a tick, drawn in its window on
+    /// `screen`.
+    pub(crate) fn printer<'a>(&'a mut self, screen: &'a mut dyn Screen) -> Printer<'a> {
+        Printer { output: self, screen }
+    }
+
+    /// Starts a run of `program` from the top of the window, also writing
+    /// to `capture_to` and keeping a transcript in `transcript_to` if given.
+    pub(crate) fn start(&mut self, program: FileName, capture_to: Option<FileName>,
+                        transcript_to: Option<FileName>) {
+        self.program = program;
+        self.lines = 0;
+        self.scrollback.next_line = 0;
+        self.ticks = 0;
+        self.last_line_len = 0;
+        self.capture = capture_to.map(|filename| FileCapture::new(self.window, filename));
+        self.transcript = transcript_to.map(|filename| FileCapture::new(self.window, filename));
+    }
+
+    /// Prints from (`start_col`, `start_row`) after the window has moved.
+    pub(crate) fn move_to(&mut self, start_col: usize, start_row: usize) {
+        self.scrollback.start_col = start_col;
+        self.scrollback.start_row = start_row;
+    }
+
+    pub(crate) fn resize(&mut self, rows: usize) {
+        self.scrollback.rows = rows;
+        self.scrollback.next_line = min(self.scrollback.next_line, rows);
+    }
+
+    pub(crate) fn hide(&mut self, hidden: bool) {
+        self.scrollback.hidden = hidden;
+    }
+
+    pub(crate) fn program(&self) -> FileName {
+        self.program
+    }
+
+    /// Lines printed since the run started.
+    pub(crate) fn lines(&self) -> usize {
+        self.lines
+    }
+
+    pub(crate) fn count_tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    pub(crate) fn record_input(&mut self, input: &str) {
+        if let Some(transcript) = &mut self.transcript {
+            transcript.push_bytes(b"> ");
+            transcript.write_line(input);
+        }
+    }
+
+    /// Input is typed on the first rows, so output after it starts there too.
+    pub(crate) fn restart_scrollback(&mut self) {
+        self.scrollback.next_line = 0;
+    }
+
+    /// The most recent line the program printed, cut to the window width.
+    pub(crate) fn last_line(&self) -> &str {
+        str::from_utf8(&self.last_line[0..self.last_line_len]).unwrap_or("")
+    }
+
+    fn remember_line(&mut self, line: &str) {
+        let mut len: usize = min(line.len(), WINDOW_WIDTH);
+        while !line.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.last_line[0..len].copy_from_slice(&line.as_bytes()[0..len]);
+        self.last_line_len = len;
+    }
+
+    pub(crate) fn take_tone(&mut self) -> Option<Tone> {
+        self.requested_tone.take()
+    }
+
+    /// The captured output, ready to be queued, once the program is done.
+    pub(crate) fn take_capture(&mut self) -> Option<PendingSave> {
+        self.capture.take().map(|capture| capture.save)
+    }
+
+    /// The transcript, ending with `status` and the run's tick count. The
+    /// last line is kept even if the transcript is full.
+    pub(crate) fn take_transcript(&mut self, status: &str) -> Option<PendingSave> {
+        let mut transcript: FileCapture = self.transcript.take()?;
+        let mut buffer: [u8; WINDOW_WIDTH] = [0; WINDOW_WIDTH];
+        let mut line: ByteWriter = ByteWriter::new(&mut buffer);
+        let _ = write!(line, "-- {} after {} ticks", status, self.ticks);
+        let len: usize = line.len();
+        transcript.write_last_line(str::from_utf8(&buffer[0..len]).unwrap_or(""));
+        Some(transcript.save)
+    }
+}
+
+/// A program's output together with the screen its window is on, for as
+/// long as the interpreter is printing.
+pub(crate) struct Printer<'a> {
+    output: &'a mut ProgramOutput,
+    screen: &'a mut dyn Screen
+}
+
+impl InterpreterOutput for Printer<'_> {
+    fn print(&mut self, chars: &[u8]) {
+        let output: &mut ProgramOutput = &mut *self.output;
+        let line: &str = str::from_utf8(chars).unwrap_or("").trim();
+        if let Some(tone) = Tone::parse(line, output.window) {
+            output.requested_tone = Some(tone);
+            return;
+        }
+        output.scrollback.write_line(self.screen, line);
+        output.remember_line(line);
+        output.lines += 1;
+        if let Some(capture) = &mut output.capture {
+            capture.write_line(line);
+        }
+        if let Some(transcript) = &mut output.transcript {
+            transcript.write_line(line);
+        }
+        output.serial.write_line(line);
+    }
+}
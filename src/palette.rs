@@ -0,0 +1,220 @@
+use pc_keyboard::{DecodedKey, KeyCode};
+use pluggable_interrupt_os::vga_buffer::{is_drawable, Color, ColorCode};
+
+use crate::command::Command;
+use crate::render::Screen;
+use crate::text_mode::TextMode;
+use crate::{WindowStatus, WINDOW_LABELS, WINDOW_WIDTH};
+
+const QUERY_CHARS: usize = WINDOW_WIDTH - 3;
+const HINT_WIDTH: usize = 4;
+const ESCAPE: char = '\u{1b}';
+
+struct Entry {
+    name: &'static str,
+    hint: &'static str,
+    command: Command<'static>,
+    modes: &'static [WindowStatus]
+}
+
+const EVERY_MODE: &[WindowStatus] = &[WindowStatus::DisplayingFiles, WindowStatus::EditingFile,
+                                      WindowStatus::Diagnostics, WindowStatus::ViewingFile,
+                                      WindowStatus::SearchResults, WindowStatus::ComparingFiles,
+                                      WindowStatus::Notifications];
+
+static COMMANDS: [Entry; 47] = [
+    Entry { name: "edit file", hint: "e", command: Command::Key('e'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "run file", hint: "r", command: Command::Key('r'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "run to file", hint: "p", command: Command::Key('p'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "assert run", hint: "a", command: Command::Key('a'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "view file", hint: "v", command: Command::Key('v'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "new file", hint: "F5", command: Command::NewFile, modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "rename file", hint: "n", command: Command::Key('n'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "delete file", hint: "d", command: Command::Key('d'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "open directory", hint: "Ent", command: Command::Key('\n'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "mark file", hint: "Spc", command: Command::Key(' '), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "copy marked", hint: "y", command: Command::Key('y'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "restore backup", hint: "u", command: Command::Key('u'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "toggle compression", hint: "z", command: Command::Key('z'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "file stats", hint: "s", command: Command::Key('s'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "sort lines", hint: "o", command: Command::Key('o'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "unique lines", hint: "q", command: Command::Key('q'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "search files", hint: "/", command: Command::Key('/'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "toggle transcripts", hint: "t", command: Command::Key('t'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "toggle serial mirror", hint: "m", command: Command::Key('m'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "compare files", hint: "c", command: Command::Key('c'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "open match", hint: "Ent", command: Command::Key('\n'), modes: &[WindowStatus::SearchResults] },
+    Entry { name: "close results", hint: "q", command: Command::Key('q'), modes: &[WindowStatus::SearchResults] },
+    Entry { name: "close comparison", hint: "q", command: Command::Key('q'), modes: &[WindowStatus::ComparingFiles] },
+    Entry { name: "notifications", hint: "i", command: Command::Key('i'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "close notifications", hint: "q", command: Command::Key('q'), modes: &[WindowStatus::Notifications] },
+    Entry { name: "shut down", hint: "x", command: Command::Key('x'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "move window", hint: "w", command: Command::Key('w'), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "diagnostics", hint: "F7", command: Command::RawKey(KeyCode::F7), modes: &[WindowStatus::DisplayingFiles] },
+    Entry { name: "save menu", hint: "F6", command: Command::Close, modes: &[WindowStatus::EditingFile] },
+    Entry { name: "next page", hint: "F7", command: Command::RawKey(KeyCode::F7), modes: &[WindowStatus::Diagnostics] },
+    Entry { name: "flush journal", hint: "j", command: Command::Key('j'), modes: &[WindowStatus::Diagnostics] },
+    Entry { name: "self-test", hint: "t", command: Command::Key('t'), modes: &[WindowStatus::Diagnostics] },
+    Entry { name: "close", hint: "F6", command: Command::Close, modes: &[WindowStatus::Diagnostics] },
+    Entry { name: "close viewer", hint: "q", command: Command::Key('q'), modes: &[WindowStatus::ViewingFile] },
+    Entry { name: "freeze everything", hint: "Paus", command: Command::RawKey(KeyCode::PauseBreak), modes: EVERY_MODE },
+    Entry { name: "restart windows", hint: "ScrL", command: Command::RawKey(KeyCode::ScrollLock), modes: EVERY_MODE },
+    Entry { name: "zoom window", hint: "Ins", command: Command::RawKey(KeyCode::Insert), modes: EVERY_MODE },
+    Entry { name: "toggle sandbox", hint: "F8", command: Command::RawKey(KeyCode::F8), modes: EVERY_MODE },
+    Entry { name: "80x25 text", hint: "", command: Command::SetTextMode(TextMode::Text80x25), modes: EVERY_MODE },
+    Entry { name: "80x43 text", hint: "", command: Command::SetTextMode(TextMode::Text80x43), modes: EVERY_MODE },
+    Entry { name: "80x50 text", hint: "", command: Command::SetTextMode(TextMode::Text80x50), modes: EVERY_MODE },
+    Entry { name: "switch to F1", hint: "F1", command: Command::FocusWindow(0), modes: EVERY_MODE },
+    Entry { name: "switch to F2", hint: "F2", command: Command::FocusWindow(1), modes: EVERY_MODE },
+    Entry { name: "switch to F3", hint: "F3", command: Command::FocusWindow(2), modes: EVERY_MODE },
+    Entry { name: "switch to F4", hint: "F4", command: Command::FocusWindow(3), modes: EVERY_MODE },
+    Entry { name: "switch to F5", hint: "F5", command: Command::FocusWindow(4), modes: EVERY_MODE },
+    Entry { name: "switch to F6", hint: "F6", command: Command::FocusWindow(5), modes: EVERY_MODE }
+];
+
+pub(crate) enum PaletteAction {
+    None,
+    Close,
+    Execute(Command<'static>)
+}
+
+/// True if every character of `query` appears in `name` in order, ignoring
+/// case.
+pub(crate) fn fuzzy_matches(name: &str, query: &[char]) -> bool {
+    let mut remaining = query.iter().peekable();
+    for char in name.chars() {
+        if remaining.peek().is_some_and(|wanted| wanted.eq_ignore_ascii_case(&char)) {
+            remaining.next();
+        }
+    }
+    remaining.peek().is_none()
+}
+
+/// A filterable list of the commands available in one window's current
+/// mode. Choosing a command carries it out as if its key had been pressed.
+pub(crate) struct CommandPalette {
+    /// Windows the manager has, so only those can be switched to.
+    windows: usize,
+    window: Option<usize>,
+    query: [char; QUERY_CHARS],
+    query_len: usize,
+    selected: usize
+}
+
+impl CommandPalette {
+    pub(crate) fn new(windows: usize) -> Self {
+        Self {
+            windows,
+            window: None,
+            query: ['\0'; QUERY_CHARS],
+            query_len: 0,
+            selected: 0
+        }
+    }
+
+    /// The palette covers the window it opens in, so it is only offered in
+    /// modes whose contents can be redrawn afterwards.
+    pub(crate) fn supports(status: &WindowStatus) -> bool {
+        EVERY_MODE.contains(status)
+    }
+
+    pub(crate) fn window(&self) -> Option<usize> {
+        self.window
+    }
+
+    pub(crate) fn open(&mut self, window: usize) {
+        self.window = Some(window);
+        self.query_len = 0;
+        self.selected = 0;
+    }
+
+    pub(crate) fn close(&mut self) {
+        self.window = None;
+    }
+
+    /// Column of the insertion point in the query line, relative to the
+    /// window.
+    pub(crate) fn cursor_col(&self) -> usize {
+        2 + self.query_len
+    }
+
+    fn matches<'a>(&'a self, status: &'a WindowStatus) -> impl Iterator<Item = &'static Entry> + 'a {
+        COMMANDS.iter()
+            .filter(move |command| command.modes.contains(status))
+            .filter(move |command| !matches!(command.command, Command::FocusWindow(window) if window >= self.windows))
+            .filter(move |command| fuzzy_matches(command.name, &self.query[0..self.query_len]))
+    }
+
+    pub(crate) fn key(&mut self, key: DecodedKey, status: &WindowStatus) -> PaletteAction {
+        let num_matches: usize = self.matches(status).count();
+        match key {
+            DecodedKey::RawKey(KeyCode::F9) | DecodedKey::Unicode(ESCAPE) => return PaletteAction::Close,
+            DecodedKey::RawKey(KeyCode::ArrowUp) => self.selected = self.selected.saturating_sub(1),
+            DecodedKey::RawKey(KeyCode::ArrowDown) => {
+                if self.selected + 1 < num_matches {
+                    self.selected += 1;
+                }
+            },
+            DecodedKey::Unicode('\n') => {
+                return match self.matches(status).nth(self.selected) {
+                    Some(command) => PaletteAction::Execute(command.command),
+                    None => PaletteAction::None
+                };
+            },
+            DecodedKey::Unicode('\u{8}') => {
+                if self.query_len > 0 {
+                    self.query_len -= 1;
+                    self.selected = 0;
+                }
+            },
+            DecodedKey::Unicode(char) => {
+                if is_drawable(char) && self.query_len < QUERY_CHARS {
+                    self.query[self.query_len] = char;
+                    self.query_len += 1;
+                    self.selected = 0;
+                }
+            },
+            _ => {}
+        }
+        PaletteAction::None
+    }
+
+    /// The key `command` is bound to, unless it has been taken to switch
+    /// to a window.
+    fn hint(&self, command: &Entry) -> &'static str {
+        let switches: bool = matches!(command.command, Command::FocusWindow(_));
+        if !switches && WINDOW_LABELS[0..self.windows].contains(&command.hint) { "" } else { command.hint }
+    }
+
+    pub(crate) fn draw(&self, screen: &mut dyn Screen, status: &WindowStatus, start_col: usize, start_row: usize,
+                       rows: usize) {
+        let prompt_color: ColorCode = ColorCode::new(Color::Yellow, Color::Black);
+        screen.plot_str("> ", start_col, start_row, prompt_color);
+        for col in 0..QUERY_CHARS + 1 {
+            let (char, color): (char, ColorCode) = if col < self.query_len {
+                (self.query[col], prompt_color)
+            } else {
+                (' ', ColorCode::new(Color::Black, Color::Black))
+            };
+            screen.plot(char, start_col + 2 + col, start_row, color);
+        }
+        let visible_rows: usize = rows - 1;
+        let first: usize = self.selected.saturating_sub(visible_rows - 1);
+        let mut matches = self.matches(status).skip(first);
+        for row in 1..rows {
+            for col in 0..WINDOW_WIDTH {
+                screen.plot(' ', start_col + col, start_row + row, ColorCode::new(Color::Black, Color::Black));
+            }
+            if let Some(command) = matches.next() {
+                let color: ColorCode = if first + row - 1 == self.selected {
+                    ColorCode::new(Color::Black, Color::White)
+                } else {
+                    ColorCode::new(Color::White, Color::Black)
+                };
+                screen.plot_str(command.name, start_col, start_row + row, color);
+                screen.plot_str(self.hint(command), start_col + WINDOW_WIDTH - HINT_WIDTH, start_row + row,
+                                ColorCode::new(Color::DarkGray, Color::Black));
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use pc_keyboard::{DecodedKey, KeyCode};
+use pluggable_interrupt_os::vga_buffer::{is_drawable, Color, ColorCode};
+
+use crate::file_name::FileName;
+use crate::palette::fuzzy_matches;
+use crate::render::Screen;
+use crate::WINDOW_WIDTH;
+
+const PROMPT: &str = "Open: ";
+const QUERY_CHARS: usize = WINDOW_WIDTH - PROMPT.len() - 1;
+const ESCAPE: char = '\u{1b}';
+
+pub(crate) enum PickerAction {
+    None,
+    Close,
+    Open(FileName)
+}
+
+/// A list of files laid over an editor, narrowed as a name is typed, for
+/// switching the editor to another file without going back to the file
+/// list.
+pub(crate) struct FilePicker {
+    window: Option<usize>,
+    query: [char; QUERY_CHARS],
+    query_len: usize,
+    selected: usize
+}
+
+impl FilePicker {
+    pub(crate) fn new() -> Self {
+        Self {
+            window: None,
+            query: ['\0'; QUERY_CHARS],
+            query_len: 0,
+            selected: 0
+        }
+    }
+
+    pub(crate) fn window(&self) -> Option<usize> {
+        self.window
+    }
+
+    pub(crate) fn open(&mut self, window: usize) {
+        self.window = Some(window);
+        self.query_len = 0;
+        self.selected = 0;
+    }
+
+    pub(crate) fn close(&mut self) {
+        self.window = None;
+    }
+
+    /// Column of the insertion point in the query line, relative to the
+    /// window.
+    pub(crate) fn cursor_col(&self) -> usize {
+        PROMPT.len() + self.query_len
+    }
+
+    fn matches<'a>(&'a self, files: &'a [FileName]) -> impl Iterator<Item = &'a FileName> + 'a {
+        files.iter().filter(move |file| fuzzy_matches(file.as_str(), &self.query[0..self.query_len]))
+    }
+
+    /// `files` are the names on offer.
+    pub(crate) fn key(&mut self, key: DecodedKey, files: &[FileName]) -> PickerAction {
+        let num_matches: usize = self.matches(files).count();
+        match key {
+            DecodedKey::Unicode(ESCAPE) => return PickerAction::Close,
+            DecodedKey::RawKey(KeyCode::ArrowUp) => self.selected = self.selected.saturating_sub(1),
+            DecodedKey::RawKey(KeyCode::ArrowDown) if self.selected + 1 < num_matches => self.selected += 1,
+            DecodedKey::Unicode('\n') => {
+                return match self.matches(files).nth(self.selected) {
+                    Some(file) => PickerAction::Open(*file),
+                    None => PickerAction::None
+                };
+            },
+            DecodedKey::Unicode('\u{8}') if self.query_len > 0 => {
+                self.query_len -= 1;
+                self.selected = 0;
+            },
+            DecodedKey::Unicode(char) if is_drawable(char) && self.query_len < QUERY_CHARS => {
+                self.query[self.query_len] = char;
+                self.query_len += 1;
+                self.selected = 0;
+            },
+            _ => {}
+        }
+        PickerAction::None
+    }
+
+    pub(crate) fn draw(&self, screen: &mut dyn Screen, files: &[FileName], start_col: usize, start_row: usize,
+                       rows: usize) {
+        let prompt_color: ColorCode = ColorCode::new(Color::LightCyan, Color::Black);
+        screen.plot_str(PROMPT, start_col, start_row, prompt_color);
+        for col in 0..QUERY_CHARS + 1 {
+            let (char, color): (char, ColorCode) = if col < self.query_len {
+                (self.query[col], prompt_color)
+            } else {
+                (' ', ColorCode::new(Color::Black, Color::Black))
+            };
+            screen.plot(char, start_col + PROMPT.len() + col, start_row, color);
+        }
+        let visible_rows: usize = rows - 1;
+        let first: usize = self.selected.saturating_sub(visible_rows - 1);
+        let mut matches = self.matches(files).skip(first);
+        for row in 1..rows {
+            for col in 0..WINDOW_WIDTH {
+                screen.plot(' ', start_col + col, start_row + row, ColorCode::new(Color::Black, Color::Black));
+            }
+            if let Some(file) = matches.next() {
+                let color: ColorCode = if first + row - 1 == self.selected {
+                    ColorCode::new(Color::Black, Color::White)
+                } else {
+                    ColorCode::new(Color::White, Color::Black)
+                };
+                screen.plot_str(file.as_str(), start_col, start_row + row, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use core::fmt;
+
+const BAR_WIDTH: usize = 10;
+
+/// How far a job that spans several frames has got, formatted as a bar with
+/// the percentage done and an estimate of the frames still to go.
+#[derive(Copy, Clone)]
+pub(crate) struct Progress {
+    started: usize,
+    now: usize,
+    done: usize,
+    total: usize
+}
+
+impl Progress {
+    pub(crate) fn new(started: usize, now: usize, done: usize, total: usize) -> Self {
+        Self { started, now, done, total }
+    }
+
+    pub(crate) fn percent(&self) -> usize {
+        if self.total == 0 {
+            100
+        } else {
+            self.done * 100 / self.total
+        }
+    }
+
+    /// Assumes the rest goes as fast as what has been done so far.
+    pub(crate) fn frames_left(&self) -> Option<usize> {
+        if self.done == 0 {
+            return None;
+        }
+        let elapsed: usize = self.now.saturating_sub(self.started);
+        Some(elapsed * (self.total.saturating_sub(self.done)) / self.done)
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let filled: usize = self.percent() * BAR_WIDTH / 100;
+        f.write_str("[")?;
+        for cell in 0..BAR_WIDTH {
+            f.write_str(if cell < filled { "#" } else { "-" })?;
+        }
+        write!(f, "] {}%", self.percent())?;
+        match self.frames_left() {
+            Some(frames) => write!(f, " eta {}", frames),
+            None => f.write_str(" eta ?")
+        }
+    }
+}
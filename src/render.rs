@@ -0,0 +1,349 @@
+use core::fmt::{self, Write};
+use core::str;
+use pluggable_interrupt_os::vga_buffer::{self, Color, ColorCode, BUFFER_HEIGHT, BUFFER_WIDTH};
+
+use crate::byte_writer::ByteWriter;
+use crate::output;
+use crate::text_mode::{self, TextMode, MAX_TEXT_ROWS};
+
+/// What one cell should show. `None` is a blank cell, as `clear()` leaves
+/// it.
+type Cell = Option<(char, ColorCode)>;
+type Cells = [[Cell; BUFFER_WIDTH]; MAX_TEXT_ROWS];
+
+/// Part of the screen something owns and draws inside, such as a window
+/// and its border.
+#[derive(Copy, Clone)]
+pub(crate) struct Region {
+    pub(crate) col: usize,
+    pub(crate) row: usize,
+    pub(crate) width: usize,
+    pub(crate) height: usize
+}
+
+impl Region {
+    fn contains(&self, col: usize, row: usize) -> bool {
+        (self.col..self.col + self.width).contains(&col) && (self.row..self.row + self.height).contains(&row)
+    }
+}
+
+/// The characters on the screen, blank cells as spaces. Rows below the
+/// current text mode stay blank.
+pub type TextGrid = [[char; BUFFER_WIDTH]; MAX_TEXT_ROWS];
+
+/// Somewhere cells are drawn. The kernel draws to the VGA buffer;
+/// `MemoryScreen` keeps cells in a grid instead, so what the windows draw
+/// can be checked without the hardware.
+pub trait Screen {
+    /// Draws `char` at (`col`, `row`). Cells off the screen are ignored.
+    fn plot(&mut self, char: char, col: usize, row: usize, color: ColorCode);
+
+    fn plot_str(&mut self, text: &str, col: usize, row: usize, color: ColorCode) {
+        for (i, char) in text.chars().enumerate() {
+            self.plot(char, col + i, row, color);
+        }
+    }
+
+    fn plot_num(&mut self, num: isize, col: usize, row: usize, color: ColorCode) {
+        let mut buffer: [u8; 20] = [0; 20];
+        let mut digits: ByteWriter = ByteWriter::new(&mut buffer);
+        let _ = write!(digits, "{}", num);
+        let len: usize = digits.len();
+        self.plot_str(str::from_utf8(&buffer[0..len]).unwrap_or(""), col, row, color);
+    }
+
+    /// Switches to the text mode `rows` tall. Returns false, showing what
+    /// it did before, if this screen has no such mode.
+    fn set_rows(&mut self, rows: usize) -> bool {
+        rows == BUFFER_HEIGHT
+    }
+
+    /// Blanks every cell.
+    fn clear(&mut self) {
+        for row in 0..MAX_TEXT_ROWS {
+            for col in 0..BUFFER_WIDTH {
+                self.plot(' ', col, row, blank_color());
+            }
+        }
+    }
+}
+
+/// The VGA text buffer. Rows past the 80x25 buffer are written directly,
+/// in the taller modes.
+#[derive(Default)]
+pub struct VgaScreen;
+
+impl Screen for VgaScreen {
+    fn plot(&mut self, char: char, col: usize, row: usize, color: ColorCode) {
+        if col < BUFFER_WIDTH && row < BUFFER_HEIGHT {
+            vga_buffer::plot(char, col, row, color);
+        } else {
+            text_mode::plot_below(char, col, row, color);
+        }
+    }
+
+    fn set_rows(&mut self, rows: usize) -> bool {
+        TextMode::with_rows(rows).is_some_and(text_mode::set)
+    }
+}
+
+/// A screen held in memory, starting out blank. It has room for the
+/// tallest text mode whichever it is in.
+pub struct MemoryScreen {
+    cells: [[(char, ColorCode); BUFFER_WIDTH]; MAX_TEXT_ROWS]
+}
+
+impl MemoryScreen {
+    pub fn new() -> Self {
+        Self {
+            cells: [[(' ', blank_color()); BUFFER_WIDTH]; MAX_TEXT_ROWS]
+        }
+    }
+
+    pub fn char_at(&self, col: usize, row: usize) -> char {
+        self.cells[row][col].0
+    }
+
+    pub fn color_at(&self, col: usize, row: usize) -> ColorCode {
+        self.cells[row][col].1
+    }
+
+    pub fn text(&self) -> TextGrid {
+        self.cells.map(|cells| cells.map(|(char, _)| char))
+    }
+}
+
+impl Default for MemoryScreen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Screen for MemoryScreen {
+    fn plot(&mut self, char: char, col: usize, row: usize, color: ColorCode) {
+        if col < BUFFER_WIDTH && row < MAX_TEXT_ROWS {
+            self.cells[row][col] = (char, color);
+        }
+    }
+
+    fn set_rows(&mut self, rows: usize) -> bool {
+        TextMode::with_rows(rows).is_some()
+    }
+}
+
+fn blank_color() -> ColorCode {
+    ColorCode::new(Color::Black, Color::Black)
+}
+
+/// Everything the kernel draws goes to `drawn` first. `flush()` then copies
+/// only the cells that differ from what `screen` already shows, so a cell
+/// cleared and redrawn in the same frame is never written at all, and
+/// unchanged parts of the screen are not touched.
+pub(crate) struct Canvas<S: Screen> {
+    drawn: Cells,
+    shown: Cells,
+    /// The cell the mouse pointer is over. It is shown inverted but never
+    /// drawn, so moving it leaves nothing behind.
+    pointer: Option<(usize, usize)>,
+    /// Where drawing is allowed, or everywhere if None.
+    clip: Option<Region>,
+    /// Cells refused since the last flush.
+    strays: usize,
+    /// Rows in the screen's text mode.
+    rows: usize,
+    screen: S
+}
+
+impl<S: Screen> Canvas<S> {
+    pub(crate) fn new(screen: S) -> Self {
+        Self {
+            drawn: [[None; BUFFER_WIDTH]; MAX_TEXT_ROWS],
+            shown: [[None; BUFFER_WIDTH]; MAX_TEXT_ROWS],
+            pointer: None,
+            clip: None,
+            strays: 0,
+            rows: BUFFER_HEIGHT,
+            screen
+        }
+    }
+
+    /// Where the cells end up, as of the last flush.
+    pub(crate) fn screen(&self) -> &S {
+        &self.screen
+    }
+
+    /// Counts a cell drawn where it has no business being. Debug builds
+    /// also write the first each frame to the serial port, so a drawing bug
+    /// shows up there instead of as stray characters over another window.
+    fn refuse(&mut self, col: usize, row: usize) {
+        self.strays += 1;
+        if cfg!(debug_assertions) && self.strays == 1 {
+            let mut buffer: [u8; 48] = [0; 48];
+            let mut line: ByteWriter = ByteWriter::new(&mut buffer);
+            let _ = write!(line, "swim: drawing refused at col {} row {}", col, row);
+            let len: usize = line.len();
+            output::write_serial_line(str::from_utf8(&buffer[0..len]).unwrap_or(""));
+        }
+    }
+
+    /// Refuses drawing outside `region` until `unclip()`.
+    pub(crate) fn clip_to(&mut self, region: Region) {
+        self.clip = Some(region);
+    }
+
+    pub(crate) fn unclip(&mut self) {
+        self.clip = None;
+    }
+
+    /// Shows the mouse pointer over the cell at (`col`, `row`) from the
+    /// next flush, or hides it.
+    pub(crate) fn set_pointer(&mut self, cell: Option<(usize, usize)>) {
+        self.pointer = cell;
+    }
+
+    /// The text drawn so far, colors left out, read back from the cells
+    /// rather than the screen so it is there before `flush()`.
+    pub(crate) fn text_grid(&self) -> TextGrid {
+        let mut grid: TextGrid = [[' '; BUFFER_WIDTH]; MAX_TEXT_ROWS];
+        for (row, cells) in self.drawn.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if let Some((char, _)) = cell {
+                    grid[row][col] = *char;
+                }
+            }
+        }
+        grid
+    }
+
+    /// The text drawn in the `width` cells starting at `col` on `row`.
+    pub(crate) fn text_line(&self, col: usize, row: usize, width: usize) -> TextLine {
+        let mut line: TextLine = TextLine { chars: [' '; BUFFER_WIDTH], len: 0 };
+        if row >= MAX_TEXT_ROWS {
+            return line;
+        }
+        for (i, cell) in self.drawn[row].iter().skip(col).take(width).enumerate() {
+            if let Some((char, _)) = cell {
+                line.chars[i] = *char;
+                if *char != ' ' {
+                    line.len = i + 1;
+                }
+            }
+        }
+        line
+    }
+
+    /// Writes the cells drawn since the last flush that changed to the
+    /// screen. Call once a frame, after everything has been drawn.
+    pub(crate) fn flush(&mut self) {
+        self.strays = 0;
+        for (row, shown_row) in self.shown.iter_mut().enumerate() {
+            for (col, shown_cell) in shown_row.iter_mut().enumerate() {
+                let cell: Cell = visible_cell(&self.drawn, self.pointer, col, row);
+                if cell == *shown_cell {
+                    continue;
+                }
+                let (char, color): (char, ColorCode) = cell.unwrap_or((' ', blank_color()));
+                self.screen.plot(char, col, row, color);
+                *shown_cell = cell;
+            }
+        }
+    }
+}
+
+impl<S: Screen> Screen for Canvas<S> {
+    /// Cells off the edge of the screen, or outside the region set with
+    /// `clip_to()`, are refused.
+    fn plot(&mut self, char: char, col: usize, row: usize, color: ColorCode) {
+        if col >= BUFFER_WIDTH || row >= self.rows || self.clip.is_some_and(|region| !region.contains(col, row)) {
+            self.refuse(col, row);
+            return;
+        }
+        self.drawn[row][col] = Some((char, color));
+    }
+
+    /// The screen is blanked as it switches, and everything has to be
+    /// drawn again.
+    fn set_rows(&mut self, rows: usize) -> bool {
+        if !self.screen.set_rows(rows) {
+            return false;
+        }
+        self.rows = rows;
+        self.screen.clear();
+        self.drawn = [[None; BUFFER_WIDTH]; MAX_TEXT_ROWS];
+        self.shown = [[None; BUFFER_WIDTH]; MAX_TEXT_ROWS];
+        true
+    }
+
+    fn clear(&mut self) {
+        self.drawn = [[None; BUFFER_WIDTH]; MAX_TEXT_ROWS];
+    }
+}
+
+/// The cell drawn at (`col`, `row`), inverted if the pointer is over it.
+fn visible_cell(drawn: &Cells, pointer: Option<(usize, usize)>, col: usize, row: usize) -> Cell {
+    let cell: Cell = drawn[row][col];
+    if pointer == Some((col, row)) {
+        let char: char = cell.map_or(' ', |(char, _)| char);
+        return Some((char, ColorCode::new(Color::Black, Color::LightGray)));
+    }
+    cell
+}
+
+/// One row of drawn text, with the blanks after its last character left
+/// off.
+#[derive(Copy, Clone)]
+pub struct TextLine {
+    chars: [char; BUFFER_WIDTH],
+    len: usize
+}
+
+impl TextLine {
+    pub fn chars(&self) -> &[char] {
+        &self.chars[0..self.len]
+    }
+}
+
+impl PartialEq<str> for TextLine {
+    fn eq(&self, other: &str) -> bool {
+        self.chars().iter().copied().eq(other.chars())
+    }
+}
+
+impl fmt::Display for TextLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for char in self.chars() {
+            f.write_char(*char)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts what reaches the screen.
+    #[derive(Default)]
+    struct CountingScreen {
+        plotted: usize
+    }
+
+    impl Screen for CountingScreen {
+        fn plot(&mut self, _char: char, _col: usize, _row: usize, _color: ColorCode) {
+            self.plotted += 1;
+        }
+    }
+
+    #[test]
+    fn flush_writes_only_changed_cells() {
+        let color: ColorCode = ColorCode::new(Color::White, Color::Black);
+        let mut canvas: Canvas<CountingScreen> = Canvas::new(CountingScreen::default());
+        canvas.plot_str("hello", 0, 0, color);
+        canvas.flush();
+        assert_eq!(canvas.screen().plotted, 5);
+        canvas.clear();
+        canvas.plot_str("help", 0, 0, color);
+        canvas.flush();
+        assert_eq!(canvas.screen().plotted, 7);
+    }
+}
@@ -0,0 +1,42 @@
+use crate::file_name::FileName;
+use crate::MAX_FILES_STORED;
+
+#[derive(Copy, Clone)]
+struct OwnedFile {
+    filename: FileName,
+    owner: usize
+}
+
+/// Records which window created each file since boot. The sample files are
+/// not listed, so no window owns them.
+pub(crate) struct FileOwners {
+    files: [Option<OwnedFile>; MAX_FILES_STORED]
+}
+
+impl FileOwners {
+    pub(crate) fn new() -> Self {
+        Self {
+            files: [None; MAX_FILES_STORED]
+        }
+    }
+
+    pub(crate) fn record(&mut self, filename: &str, owner: usize) {
+        if self.owner_of(filename).is_some() {
+            return;
+        }
+        let file: OwnedFile = OwnedFile {
+            filename: FileName::truncated(filename),
+            owner
+        };
+        if let Some(slot) = self.files.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(file);
+        }
+    }
+
+    pub(crate) fn owner_of(&self, filename: &str) -> Option<usize> {
+        self.files.iter()
+            .flatten()
+            .find(|file| file.filename.as_str() == filename)
+            .map(|file| file.owner)
+    }
+}
@@ -0,0 +1,223 @@
+use core::cmp::min;
+
+use crate::compression;
+use crate::file_name::FileName;
+use crate::progress::Progress;
+use crate::{BLOCK_SIZE, MAX_SAVE_BYTES, MAX_WINDOWS};
+
+const SAVE_QUEUE_CAPACITY: usize = 4;
+const SAVE_CHUNK_BYTES: usize = BLOCK_SIZE;
+pub(crate) const SAVE_TEMP_FILENAME: &str = "~swap";
+
+#[derive(Copy, Clone)]
+pub(crate) struct PendingSave {
+    pub(crate) window: usize,
+    pub(crate) filename: FileName,
+    pub(crate) contents: [u8; MAX_SAVE_BYTES],
+    pub(crate) contents_len: usize,
+    /// Returns the window's editor to its file list once saved.
+    pub(crate) closes_editor: bool
+}
+
+impl PendingSave {
+    pub(crate) fn new(window: usize) -> Self {
+        Self {
+            window,
+            filename: FileName::empty(),
+            contents: [0; MAX_SAVE_BYTES],
+            contents_len: 0,
+            closes_editor: false
+        }
+    }
+
+    pub(crate) fn set_filename(&mut self, filename: &str) {
+        self.filename = FileName::truncated(filename);
+    }
+
+    pub(crate) fn filename(&self) -> &str {
+        self.filename.as_str()
+    }
+
+    pub(crate) fn contents(&self) -> &[u8] {
+        &self.contents[0..self.contents_len]
+    }
+
+    /// Leaves the contents as they are if they would not fit compressed.
+    pub(crate) fn compress_contents(&mut self) {
+        let mut packed: [u8; MAX_SAVE_BYTES] = [0; MAX_SAVE_BYTES];
+        if let Some(packed_len) = compression::compress(self.contents(), &mut packed) {
+            self.contents = packed;
+            self.contents_len = packed_len;
+        }
+    }
+}
+
+/// Saves waiting to be applied to the filesystems, oldest first. Applying them
+/// one at a time from `update()` keeps open/write/close sequences from
+/// different windows from interleaving.
+pub(crate) struct SaveQueue {
+    saves: [Option<PendingSave>; SAVE_QUEUE_CAPACITY],
+    head: usize,
+    len: usize
+}
+
+impl SaveQueue {
+    pub(crate) fn new() -> Self {
+        Self {
+            saves: [None; SAVE_QUEUE_CAPACITY],
+            head: 0,
+            len: 0
+        }
+    }
+
+    pub(crate) fn push(&mut self, save: PendingSave) -> bool {
+        if self.len == SAVE_QUEUE_CAPACITY {
+            return false;
+        }
+        self.saves[(self.head + self.len) % SAVE_QUEUE_CAPACITY] = Some(save);
+        self.len += 1;
+        true
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<PendingSave> {
+        if self.len == 0 {
+            return None;
+        }
+        let save: Option<PendingSave> = self.saves[self.head].take();
+        self.head = (self.head + 1) % SAVE_QUEUE_CAPACITY;
+        self.len -= 1;
+        save
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The `index`th oldest save still waiting.
+    pub(crate) fn get(&self, index: usize) -> Option<&PendingSave> {
+        if index >= self.len {
+            return None;
+        }
+        self.saves[(self.head + index) % SAVE_QUEUE_CAPACITY].as_ref()
+    }
+
+    /// Drops a waiting save, keeping the others in order.
+    pub(crate) fn remove(&mut self, index: usize) -> Option<PendingSave> {
+        if index >= self.len {
+            return None;
+        }
+        let removed: Option<PendingSave> = self.saves[(self.head + index) % SAVE_QUEUE_CAPACITY].take();
+        for i in index..self.len - 1 {
+            let next: Option<PendingSave> = self.saves[(self.head + i + 1) % SAVE_QUEUE_CAPACITY].take();
+            self.saves[(self.head + i) % SAVE_QUEUE_CAPACITY] = next;
+        }
+        self.len -= 1;
+        removed
+    }
+}
+
+/// A save in progress. It is written to `SAVE_TEMP_FILENAME`, which stays
+/// open in every window's filesystem while `SAVE_CHUNK_BYTES` more are
+/// written on each `update()`. Only once all of it is there does it take
+/// the real file's place, so a failed or cancelled save leaves the
+/// original intact.
+pub(crate) struct SaveJob {
+    pub(crate) save: PendingSave,
+    pub(crate) fds: [Option<usize>; MAX_WINDOWS],
+    pub(crate) written: usize,
+    pub(crate) failed: bool,
+    started: usize
+}
+
+impl SaveJob {
+    pub(crate) fn new(save: PendingSave, frame: usize) -> Self {
+        Self {
+            save,
+            fds: [None; MAX_WINDOWS],
+            written: 0,
+            failed: false,
+            started: frame
+        }
+    }
+
+    pub(crate) fn next_chunk(&self) -> &[u8] {
+        let end: usize = min(self.written + SAVE_CHUNK_BYTES, self.save.contents_len);
+        &self.save.contents[self.written..end]
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.written >= self.save.contents_len
+    }
+
+    pub(crate) fn progress(&self, frame: usize) -> Progress {
+        Progress::new(self.started, frame, self.written, self.save.contents_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn save(window: usize) -> PendingSave {
+        PendingSave::new(window)
+    }
+
+    fn windows(queue: &SaveQueue) -> Vec<usize> {
+        (0..queue.len()).filter_map(|i| queue.get(i)).map(|save| save.window).collect()
+    }
+
+    #[test]
+    fn full_queue_refuses_more() {
+        let mut queue: SaveQueue = SaveQueue::new();
+        for window in 0..SAVE_QUEUE_CAPACITY {
+            assert!(queue.push(save(window)));
+        }
+        assert!(!queue.push(save(9)));
+        assert_eq!(queue.len(), SAVE_QUEUE_CAPACITY);
+        assert_eq!(queue.pop().map(|save| save.window), Some(0));
+        assert!(queue.push(save(9)));
+        assert_eq!(windows(&queue), [1, 2, 3, 9]);
+    }
+
+    #[test]
+    fn pushes_and_pops_wrap_around() {
+        let mut queue: SaveQueue = SaveQueue::new();
+        let mut expected: VecDeque<usize> = VecDeque::new();
+        for window in 0..5 * SAVE_QUEUE_CAPACITY {
+            assert!(queue.push(save(window)));
+            expected.push_back(window);
+            if expected.len() == SAVE_QUEUE_CAPACITY - 1 {
+                assert_eq!(queue.pop().map(|save| save.window), expected.pop_front());
+                assert_eq!(queue.pop().map(|save| save.window), expected.pop_front());
+            }
+            assert_eq!(windows(&queue), Vec::from(expected.clone()));
+        }
+        while let Some(window) = expected.pop_front() {
+            assert_eq!(queue.pop().map(|save| save.window), Some(window));
+        }
+        assert!(queue.pop().is_none());
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn removing_across_the_wrap_keeps_the_order() {
+        let mut queue: SaveQueue = SaveQueue::new();
+        for window in 0..SAVE_QUEUE_CAPACITY {
+            queue.push(save(window));
+        }
+        queue.pop();
+        queue.pop();
+        queue.push(save(4));
+        queue.push(save(5));
+        assert_eq!(windows(&queue), [2, 3, 4, 5]);
+        assert_eq!(queue.remove(1).map(|save| save.window), Some(3));
+        assert_eq!(windows(&queue), [2, 4, 5]);
+        assert_eq!(queue.remove(3).map(|save| save.window), None);
+        assert!(queue.push(save(6)));
+        assert_eq!(windows(&queue), [2, 4, 5, 6]);
+        assert_eq!(queue.remove(0).map(|save| save.window), Some(2));
+        assert_eq!(queue.remove(2).map(|save| save.window), Some(6));
+        assert_eq!(windows(&queue), [4, 5]);
+    }
+}
@@ -0,0 +1,108 @@
+use core::arch::x86_64::_rdtsc;
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::render::Screen;
+use crate::WINDOW_WIDTH;
+
+/// Most interpreter ticks handed out in one frame, until changed with
+/// `SwimDocManager::set_quantum()`. Larger quanta get CPU-bound programs
+/// further each frame, at the cost of frames taking longer.
+pub(crate) const DEFAULT_QUANTUM: usize = 10;
+pub(crate) const MIN_QUANTUM: usize = 10;
+pub(crate) const MAX_QUANTUM: usize = 100;
+/// How far the quantum keys move it.
+pub(crate) const QUANTUM_STEP: usize = 10;
+/// Ticks a window gets per turn, in the range 1 to `MAX_PRIORITY`.
+pub(crate) const DEFAULT_PRIORITY: usize = 1;
+pub(crate) const MAX_PRIORITY: usize = 4;
+/// Time stamp counter cycles a frame may spend ticking programs before the
+/// scheduler stops and leaves the rest for the next frame, so key handling
+/// never waits long however many programs are running.
+const FRAME_BUDGET_CYCLES: u64 = 5_000_000;
+const AVERAGE_FRAMES: usize = 64;
+
+/// Measures how long the current frame has spent running programs.
+pub(crate) struct FrameBudget {
+    started: u64
+}
+
+impl FrameBudget {
+    pub(crate) fn start() -> Self {
+        Self {
+            started: read_time_stamp()
+        }
+    }
+
+    pub(crate) fn is_spent(&self) -> bool {
+        read_time_stamp().wrapping_sub(self.started) >= FRAME_BUDGET_CYCLES
+    }
+}
+
+pub(crate) fn read_time_stamp() -> u64 {
+    unsafe { _rdtsc() }
+}
+
+/// How many ticks recent frames managed, for the diagnostics page, along
+/// with how long the last file open for editing took.
+pub(crate) struct SchedulerStats {
+    last: usize,
+    most: usize,
+    recent_ticks: usize,
+    recent_frames: usize,
+    average: usize,
+    last_open_cycles: u64
+}
+
+impl SchedulerStats {
+    pub(crate) fn new() -> Self {
+        Self {
+            last: 0,
+            most: 0,
+            recent_ticks: 0,
+            recent_frames: 0,
+            average: 0,
+            last_open_cycles: 0
+        }
+    }
+
+    /// Frames with nothing to run are left out so the figures describe
+    /// frames where programs were competing for time.
+    pub(crate) fn record(&mut self, ticks: usize) {
+        if ticks == 0 {
+            return;
+        }
+        self.last = ticks;
+        self.most = self.most.max(ticks);
+        self.recent_ticks += ticks;
+        self.recent_frames += 1;
+        if self.recent_frames == AVERAGE_FRAMES {
+            self.average = self.recent_ticks / self.recent_frames;
+            self.recent_ticks = 0;
+            self.recent_frames = 0;
+        }
+    }
+
+    pub(crate) fn record_open(&mut self, started: u64) {
+        self.last_open_cycles = read_time_stamp().wrapping_sub(started);
+    }
+
+    /// `quantum` is the current limit on ticks per frame, and the screen is
+    /// drawn once every `frames_per_refresh` frames.
+    pub(crate) fn draw(&self, screen: &mut dyn Screen, start_col: usize, start_row: usize, rows: usize,
+                       quantum: usize, frames_per_refresh: usize) {
+        screen.plot_str("ticks per frame", start_col, start_row, ColorCode::new(Color::White, Color::Black));
+        let stats: [(&str, usize); 6] = [("last", self.last), ("average", self.average), ("most", self.most),
+                                          ("limit", quantum), ("ui every", frames_per_refresh),
+                                          ("open kcyc", (self.last_open_cycles / 1000) as usize)];
+        for row in 1..rows {
+            for col in start_col..start_col + WINDOW_WIDTH {
+                screen.plot(' ', col, start_row + row, ColorCode::new(Color::Black, Color::Black));
+            }
+            if let Some((label, value)) = stats.get(row - 1) {
+                let color: ColorCode = ColorCode::new(Color::LightGray, Color::Black);
+                screen.plot_str(label, start_col, start_row + row, color);
+                screen.plot_num(*value as isize, start_col + 10, start_row + row, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use file_system_solution::FileSystemError;
+
+use crate::MAX_FILE_BYTES;
+
+/// Most file-sized buffers in use at once. Comparing two files and the line
+/// tools hold two, and reading a compressed file into one of them needs a
+/// third to unpack from.
+const SCRATCH_BUFFERS: usize = 3;
+
+/// File-sized buffers kept out of the kernel stack. Each one is handed out
+/// through a `ScratchBuffer` guard and returned when the guard drops.
+pub(crate) struct ScratchArena {
+    buffers: [UnsafeCell<[u8; MAX_FILE_BYTES]>; SCRATCH_BUFFERS],
+    checked_out: [AtomicBool; SCRATCH_BUFFERS]
+}
+
+// A buffer is only reachable through the guard holding its checkout flag.
+unsafe impl Sync for ScratchArena {}
+
+pub(crate) static SCRATCH: ScratchArena = ScratchArena {
+    buffers: [const { UnsafeCell::new([0; MAX_FILE_BYTES]) }; SCRATCH_BUFFERS],
+    checked_out: [const { AtomicBool::new(false) }; SCRATCH_BUFFERS]
+};
+
+impl ScratchArena {
+    /// A zeroed buffer, as a fresh stack array would be. Fails with
+    /// `TooManyOpen` if every buffer is already checked out, since each one
+    /// holds a file being worked on.
+    pub(crate) fn checkout(&'static self) -> Result<ScratchBuffer, FileSystemError> {
+        let slot: usize = self.checked_out.iter()
+            .position(|flag| flag.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok())
+            .ok_or(FileSystemError::TooManyOpen)?;
+        let mut buffer: ScratchBuffer = ScratchBuffer { arena: self, slot };
+        buffer.fill(0);
+        Ok(buffer)
+    }
+}
+
+pub(crate) struct ScratchBuffer {
+    arena: &'static ScratchArena,
+    slot: usize
+}
+
+impl Deref for ScratchBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { &*self.arena.buffers[self.slot].get() }
+    }
+}
+
+impl DerefMut for ScratchBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { &mut *self.arena.buffers[self.slot].get() }
+    }
+}
+
+impl Drop for ScratchBuffer {
+    fn drop(&mut self) {
+        let was_checked_out: bool = self.arena.checked_out[self.slot].swap(false, Ordering::Release);
+        debug_assert!(was_checked_out, "scratch buffer {} returned twice", self.slot);
+    }
+}
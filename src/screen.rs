@@ -0,0 +1,136 @@
+use pluggable_interrupt_os::vga_buffer::{BUFFER_HEIGHT, BUFFER_WIDTH};
+
+use crate::text_mode::TextMode;
+use crate::{MAX_WINDOWS, TASK_MANAGER_WIDTH, WINDOW_WIDTH};
+
+/// Rows taken by the prompt line and the gap above the first window border.
+const TOP_MARGIN: usize = 2;
+/// The fewest rows inside a window's border that its pages still fit in.
+const MIN_WINDOW_ROWS: usize = 6;
+
+// The layout below is only worked out at run time, so a change to the
+// window or task manager sizes that no longer fits would otherwise draw
+// over the wrong cells without complaint.
+const _: () = assert!(2 * (WINDOW_WIDTH + 2) <= BUFFER_WIDTH - TASK_MANAGER_WIDTH,
+                      "two windows side by side run into the task manager");
+const _: () = assert!((BUFFER_HEIGHT - 1) / grid(MAX_WINDOWS).1 - 2 >= MIN_WINDOW_ROWS,
+                      "the most windows stacked on the smallest screen leave them too short");
+
+/// Windows across and down for a manager with `windows` of them.
+const fn grid(windows: usize) -> (usize, usize) {
+    match windows {
+        1 => (1, 1),
+        2 => (2, 1),
+        3 | 4 => (2, 2),
+        _ => (2, 3)
+    }
+}
+
+/// Size of the text mode the screen is in and how many windows share it,
+/// from which the window and task manager positions are worked out.
+#[derive(Copy, Clone)]
+pub(crate) struct ScreenInfo {
+    pub(crate) cols: usize,
+    pub(crate) rows: usize,
+    pub(crate) windows: usize
+}
+
+impl ScreenInfo {
+    pub(crate) fn new(mode: TextMode, windows: usize) -> ScreenInfo {
+        ScreenInfo {
+            cols: BUFFER_WIDTH,
+            rows: mode.rows(),
+            windows
+        }
+    }
+
+    /// Rows each window gets below the prompt line, border included.
+    fn band_height(&self) -> usize {
+        (self.rows - 1) / grid(self.windows).1
+    }
+
+    /// Rows inside the border of a window that is not zoomed.
+    pub(crate) fn window_rows(&self) -> usize {
+        self.band_height() - 2
+    }
+
+    /// Top-left cell inside the border of `window`. Windows fill the screen
+    /// left to right, then top to bottom, as many across and down as their
+    /// number needs, each row of them getting an even share of the height
+    /// below the prompt line.
+    pub(crate) fn window_origin(&self, window: usize) -> (usize, usize) {
+        let across: usize = grid(self.windows).0;
+        (1 + (window % across) * (WINDOW_WIDTH + 2), TOP_MARGIN + (window / across) * self.band_height())
+    }
+
+    /// The origin nearest to (`col`, `row`) that keeps a window's border
+    /// below the prompt line and clear of the task manager.
+    pub(crate) fn clamp_origin(&self, col: usize, row: usize) -> (usize, usize) {
+        let last_col: usize = self.cols - TASK_MANAGER_WIDTH - 1 - WINDOW_WIDTH;
+        let last_row: usize = self.rows - 1 - self.window_rows();
+        (col.clamp(1, last_col), row.clamp(TOP_MARGIN, last_row))
+    }
+
+    /// Whether windows with these origins would share a cell, borders
+    /// included.
+    pub(crate) fn windows_overlap(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        a.0.abs_diff(b.0) < WINDOW_WIDTH + 2 && a.1.abs_diff(b.1) < self.window_rows() + 2
+    }
+
+    /// Where a zoomed window's text starts: the top-left of the window area.
+    pub(crate) fn zoomed_origin(&self) -> (usize, usize) {
+        (1, TOP_MARGIN)
+    }
+
+    /// Rows inside a zoomed window's border, from below the prompt line to
+    /// the bottom of the screen.
+    pub(crate) fn zoomed_rows(&self) -> usize {
+        self.rows - TOP_MARGIN - 1
+    }
+
+    pub(crate) fn task_manager_col(&self) -> usize {
+        self.cols - TASK_MANAGER_WIDTH + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_fit_without_overlapping() {
+        for windows in [1, 2, 4, 6] {
+            for mode in [TextMode::Text80x25, TextMode::Text80x43, TextMode::Text80x50] {
+                assert_fits(ScreenInfo::new(mode, windows));
+            }
+        }
+    }
+
+    /// Checks every window fits on `screen` without touching another.
+    fn assert_fits(screen: ScreenInfo) {
+        let windows: usize = screen.windows;
+        assert!(screen.window_rows() >= MIN_WINDOW_ROWS, "{} windows", windows);
+        for window in 0..windows {
+            let (col, row): (usize, usize) = screen.window_origin(window);
+            assert!(row >= TOP_MARGIN && row + screen.window_rows() < screen.rows, "{} windows", windows);
+            assert!(col + WINDOW_WIDTH < screen.task_manager_col() - 1, "{} windows", windows);
+            assert_eq!(screen.clamp_origin(col, row), (col, row));
+            for other in 0..window {
+                assert!(!screen.windows_overlap((col, row), screen.window_origin(other)), "{} windows", windows);
+            }
+        }
+    }
+
+    #[test]
+    fn fewer_windows_are_taller() {
+        let rows: [usize; 4] = [1, 2, 4, 6].map(|windows| ScreenInfo::new(TextMode::Text80x25, windows).window_rows());
+        assert_eq!(rows, [22, 22, 10, 6]);
+    }
+
+    #[test]
+    fn taller_modes_give_taller_windows() {
+        let modes: [TextMode; 3] = [TextMode::Text80x25, TextMode::Text80x43, TextMode::Text80x50];
+        let rows: [usize; 3] = modes.map(|mode| ScreenInfo::new(mode, 4).window_rows());
+        assert_eq!(rows, [10, 19, 22]);
+    }
+}
@@ -0,0 +1,67 @@
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode, BUFFER_WIDTH};
+
+use crate::render::Screen;
+
+/// Frames without a keypress before the screen is blanked.
+pub(crate) const SCREENSAVER_IDLE_FRAMES: usize = 6000;
+
+const MESSAGE: &str = "swim - press any key";
+const FRAMES_PER_STEP: usize = 4;
+
+/// Bounces a short message around an otherwise blank screen.
+pub(crate) struct Screensaver {
+    active: bool,
+    col: usize,
+    row: usize,
+    moving_right: bool,
+    moving_down: bool
+}
+
+impl Screensaver {
+    pub(crate) fn new() -> Self {
+        Self {
+            active: false,
+            col: 0,
+            row: 0,
+            moving_right: true,
+            moving_down: true
+        }
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub(crate) fn start(&mut self, screen: &mut dyn Screen) {
+        self.active = true;
+        screen.clear();
+    }
+
+    /// The caller is responsible for repainting the screen afterwards.
+    pub(crate) fn stop(&mut self) {
+        self.active = false;
+    }
+
+    /// Moves the message a step around a screen `rows` tall.
+    pub(crate) fn draw(&mut self, screen: &mut dyn Screen, frame: usize, rows: usize) {
+        if frame % FRAMES_PER_STEP != 0 {
+            return;
+        }
+        for col in self.col..self.col + MESSAGE.len() {
+            screen.plot(' ', col, self.row, ColorCode::new(Color::Black, Color::Black));
+        }
+        if self.moving_right && self.col + MESSAGE.len() >= BUFFER_WIDTH {
+            self.moving_right = false;
+        } else if !self.moving_right && self.col == 0 {
+            self.moving_right = true;
+        }
+        if self.moving_down && self.row + 1 >= rows {
+            self.moving_down = false;
+        } else if !self.moving_down && self.row == 0 {
+            self.moving_down = true;
+        }
+        self.col = if self.moving_right { self.col + 1 } else { self.col - 1 };
+        self.row = if self.moving_down { self.row + 1 } else { self.row - 1 };
+        screen.plot_str(MESSAGE, self.col, self.row, ColorCode::new(Color::LightCyan, Color::Black));
+    }
+}
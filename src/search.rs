@@ -0,0 +1,262 @@
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::file_name::FileName;
+use crate::job::{Job, JobStep};
+use crate::progress::Progress;
+use crate::render::Screen;
+use crate::scratch::{ScratchBuffer, SCRATCH};
+use crate::{plot_abbreviated, SwimDocument, MAX_FILES_STORED, WINDOW_WIDTH};
+
+const MAX_QUERY_BYTES: usize = 24;
+const MAX_HITS: usize = 32;
+const LINE_NUMBER_COL: usize = 18;
+
+/// A search through every file of one window's filesystem, one file per
+/// step.
+pub(crate) struct SearchJob {
+    window: usize,
+    query: SearchQuery,
+    files: [FileName; MAX_FILES_STORED],
+    num_files: usize,
+    next_file: usize,
+    started: usize
+}
+
+impl SearchJob {
+    /// `doc`'s old results are cleared straight away.
+    pub(crate) fn new(window: usize, query: SearchQuery, doc: &mut SwimDocument, frame: usize) -> Self {
+        doc.search_results.clear();
+        let (num_files, files) = doc.list_files();
+        Self {
+            window,
+            query,
+            files,
+            num_files,
+            next_file: 0,
+            started: frame
+        }
+    }
+
+    pub(crate) fn window(&self) -> usize {
+        self.window
+    }
+
+    pub(crate) fn query(&self) -> &SearchQuery {
+        &self.query
+    }
+}
+
+impl Job<SwimDocument> for SearchJob {
+    fn step(&mut self, doc: &mut SwimDocument) -> JobStep {
+        if self.next_file < self.num_files {
+            let filename: FileName = self.files[self.next_file];
+            // With every buffer out, the file is searched on a later step.
+            let mut buffer: ScratchBuffer = match SCRATCH.checkout() {
+                Ok(buffer) => buffer,
+                Err(_) => return JobStep::Continue
+            };
+            let len: usize = doc.read_file(filename.as_str(), &mut buffer).unwrap_or(0);
+            doc.search_results.scan(filename, &buffer[0..len], self.query.as_bytes());
+            self.next_file += 1;
+        }
+        if self.next_file == self.num_files { JobStep::Finished } else { JobStep::Continue }
+    }
+
+    fn progress(&self, frame: usize) -> Progress {
+        Progress::new(self.started, frame, self.next_file, self.num_files)
+    }
+}
+
+/// Text typed at the search prompt. Only ASCII is accepted so matching can
+/// be done on bytes.
+#[derive(Copy, Clone)]
+pub(crate) struct SearchQuery {
+    bytes: [u8; MAX_QUERY_BYTES],
+    len: usize
+}
+
+impl SearchQuery {
+    pub(crate) fn new() -> Self {
+        Self {
+            bytes: [0; MAX_QUERY_BYTES],
+            len: 0
+        }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.bytes[0..self.len]
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        core::str::from_utf8(self.as_bytes()).unwrap_or("")
+    }
+
+    pub(crate) fn push(&mut self, char: char) -> bool {
+        if !char.is_ascii() || self.len == MAX_QUERY_BYTES {
+            return false;
+        }
+        self.bytes[self.len] = char as u8;
+        self.len += 1;
+        true
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.len = self.len.saturating_sub(1);
+    }
+}
+
+#[derive(Copy, Clone)]
+pub(crate) struct SearchHit {
+    pub(crate) filename: FileName,
+    pub(crate) line: usize
+}
+
+/// Every line that matched the last search, with one selected.
+pub(crate) struct SearchResults {
+    hits: [Option<SearchHit>; MAX_HITS],
+    num_hits: usize,
+    truncated: bool,
+    selected: usize
+}
+
+impl SearchResults {
+    pub(crate) fn new() -> Self {
+        Self {
+            hits: [None; MAX_HITS],
+            num_hits: 0,
+            truncated: false,
+            selected: 0
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Records a match of `query` for every line of `contents` containing it.
+    pub(crate) fn scan(&mut self, filename: FileName, contents: &[u8], query: &[u8]) {
+        if query.is_empty() {
+            return;
+        }
+        for (line_num, line) in contents.split(|&byte| byte == b'\n').enumerate() {
+            if !line.windows(query.len()).any(|window| window == query) {
+                continue;
+            }
+            if self.num_hits == MAX_HITS {
+                self.truncated = true;
+                return;
+            }
+            self.hits[self.num_hits] = Some(SearchHit { filename, line: line_num });
+            self.num_hits += 1;
+        }
+    }
+
+    pub(crate) fn num_hits(&self) -> usize {
+        self.num_hits
+    }
+
+    pub(crate) fn selected(&self) -> Option<SearchHit> {
+        self.hits[self.selected]
+    }
+
+    pub(crate) fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub(crate) fn select_next(&mut self) {
+        if self.selected + 1 < self.num_hits {
+            self.selected += 1;
+        }
+    }
+
+    pub(crate) fn draw(&self, screen: &mut dyn Screen, start_col: usize, start_row: usize, rows: usize) {
+        let title_color: ColorCode = ColorCode::new(Color::White, Color::Black);
+        for col in 0..WINDOW_WIDTH {
+            screen.plot(' ', start_col + col, start_row, ColorCode::new(Color::Black, Color::Black));
+        }
+        screen.plot_num(self.num_hits as isize, start_col, start_row, title_color);
+        let title: &str = if self.truncated { "+ matches" } else { " matches" };
+        screen.plot_str(title, start_col + 3, start_row, title_color);
+        let visible_rows: usize = rows - 1;
+        let first: usize = self.selected.saturating_sub(visible_rows - 1);
+        for row in 1..rows {
+            for col in 0..WINDOW_WIDTH {
+                screen.plot(' ', start_col + col, start_row + row, ColorCode::new(Color::Black, Color::Black));
+            }
+            let index: usize = first + row - 1;
+            if let Some(hit) = self.hits.get(index).copied().flatten() {
+                let color: ColorCode = if index == self.selected {
+                    ColorCode::new(Color::Black, Color::White)
+                } else {
+                    ColorCode::new(Color::LightGray, Color::Black)
+                };
+                plot_abbreviated(screen, hit.filename.as_str(), LINE_NUMBER_COL - 1, start_col, start_row + row, color);
+                screen.plot_str("line", start_col + LINE_NUMBER_COL, start_row + row, color);
+                screen.plot_num(hit.line as isize + 1, start_col + LINE_NUMBER_COL + 5, start_row + row, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit_lines(results: &mut SearchResults) -> Vec<usize> {
+        let mut lines: Vec<usize> = Vec::new();
+        for _ in 0..results.num_hits() {
+            lines.extend(results.selected().map(|hit| hit.line));
+            results.select_next();
+        }
+        lines
+    }
+
+    #[test]
+    fn query_takes_only_ascii_up_to_its_limit() {
+        let mut query: SearchQuery = SearchQuery::new();
+        assert!(!query.push('\u{e9}'));
+        for _ in 0..MAX_QUERY_BYTES {
+            assert!(query.push('a'));
+        }
+        assert!(!query.push('a'));
+        query.pop();
+        assert_eq!(query.as_bytes().len(), MAX_QUERY_BYTES - 1);
+        for _ in 0..MAX_QUERY_BYTES {
+            query.pop();
+        }
+        assert_eq!(query.as_str(), "");
+    }
+
+    #[test]
+    fn scan_finds_each_matching_line_once() {
+        let mut results: SearchResults = SearchResults::new();
+        results.scan(FileName::truncated("notes"), b"cat\ndog dog\n\nhotdog\ndo", b"dog");
+        assert_eq!(hit_lines(&mut results), [1, 3]);
+        results.scan(FileName::truncated("notes"), b"dog", b"");
+        assert_eq!(results.num_hits(), 2);
+        results.clear();
+        assert_eq!(results.num_hits(), 0);
+        assert!(results.selected().is_none());
+    }
+
+    #[test]
+    fn selection_stays_within_the_hits() {
+        let mut results: SearchResults = SearchResults::new();
+        results.scan(FileName::truncated("a"), b"x\nx", b"x");
+        results.select_previous();
+        assert_eq!(results.selected().map(|hit| hit.line), Some(0));
+        results.select_next();
+        results.select_next();
+        assert_eq!(results.selected().map(|hit| hit.line), Some(1));
+        assert_eq!(results.selected().map(|hit| hit.filename.to_string()), Some("a".to_string()));
+    }
+
+    #[test]
+    fn scan_stops_at_the_hit_limit() {
+        let contents: Vec<u8> = b"x\n".repeat(MAX_HITS + 5);
+        let mut results: SearchResults = SearchResults::new();
+        results.scan(FileName::truncated("a"), &contents, b"x");
+        assert_eq!(results.num_hits(), MAX_HITS);
+        assert!(results.truncated);
+    }
+}
@@ -0,0 +1,164 @@
+use core::fmt::{self, Write};
+use file_system_solution::FileSystemError;
+use gc_headers::{GarbageCollectingHeap, Pointer, Tracer};
+use simple_interp::{Interpreter, InterpreterOutput, TickStatus};
+
+use crate::block_device::WindowDisk;
+use crate::block_fs::BlockFileSystem;
+use crate::byte_writer::ByteWriter;
+use crate::output;
+use crate::{text_len, SwimFileSystem, SwimHeap, SwimInterpreter, HEAP_SIZE, WINDOW_WIDTH};
+
+const SELF_TEST_FILENAME: &str = "selftest";
+const SELF_TEST_CONTENTS: &[u8] = b"self-test 0123456789";
+const SELF_TEST_PROGRAM: &str = "print((2 + 3))";
+const SELF_TEST_EXPECTED_OUTPUT: &str = "5";
+const SELF_TEST_MAX_TICKS: usize = 1000;
+const SELF_TEST_MARKER: u64 = 0x5e1f_7e57;
+
+type Outcome = Result<(), &'static str>;
+
+/// PASS or FAIL for each subsystem a student reimplements, checked at boot
+/// and on request from the diagnostics pages.
+pub(crate) struct SelfTestReport {
+    file_system: Outcome,
+    interpreter: Outcome,
+    heap: Outcome
+}
+
+impl SelfTestReport {
+    pub(crate) fn run() -> Self {
+        Self {
+            file_system: check_file_system(),
+            interpreter: check_interpreter(),
+            heap: check_heap()
+        }
+    }
+
+    pub(crate) fn passed(&self) -> bool {
+        self.outcomes().iter().all(|(_, outcome)| outcome.is_ok())
+    }
+
+    fn outcomes(&self) -> [(&'static str, Outcome); 3] {
+        [("files", self.file_system), ("programs", self.interpreter), ("heap", self.heap)]
+    }
+
+    /// One line per subsystem on the serial port, with the reason for any
+    /// failure.
+    pub(crate) fn write_serial(&self) {
+        for (name, outcome) in self.outcomes() {
+            let mut buffer: [u8; WINDOW_WIDTH] = [0; WINDOW_WIDTH];
+            let mut line: ByteWriter = ByteWriter::new(&mut buffer);
+            let _ = match outcome {
+                Ok(()) => write!(line, "self-test {}: PASS", name),
+                Err(reason) => write!(line, "self-test {}: FAIL ({})", name, reason)
+            };
+            let len: usize = line.len();
+            output::write_serial_line(core::str::from_utf8(&buffer[0..len]).unwrap_or(""));
+        }
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "self-test:")?;
+        for (name, outcome) in self.outcomes() {
+            match outcome {
+                Ok(()) => write!(f, " {} PASS", name)?,
+                Err(_) => write!(f, " {} FAIL", name)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Works on a filesystem of its own on a fresh RAM disk, so the windows'
+/// files are never touched.
+fn check_file_system() -> Outcome {
+    let mut file_system: SwimFileSystem = BlockFileSystem::new(WindowDisk::memory());
+    write_test_file(&mut file_system).map_err(|_| "could not create and write a file")?;
+    let mut buffer: [u8; SELF_TEST_CONTENTS.len() + 1] = [0; SELF_TEST_CONTENTS.len() + 1];
+    read_test_file(&mut file_system, &mut buffer).map_err(|_| "could not read a file back")?;
+    if &buffer[0..text_len(&buffer)] != SELF_TEST_CONTENTS {
+        return Err("file read back differs from what was written");
+    }
+    Ok(())
+}
+
+fn write_test_file(file_system: &mut SwimFileSystem) -> Result<(), FileSystemError> {
+    let fd: usize = file_system.open_create(SELF_TEST_FILENAME)?;
+    let written: Result<(), FileSystemError> = file_system.write(fd, SELF_TEST_CONTENTS);
+    file_system.close(fd)?;
+    written
+}
+
+fn read_test_file(file_system: &mut SwimFileSystem, buffer: &mut [u8]) -> Result<(), FileSystemError> {
+    let fd: usize = file_system.open_read(SELF_TEST_FILENAME)?;
+    let read: Result<(), FileSystemError> = file_system.read(fd, buffer).map(|_| ());
+    file_system.close(fd)?;
+    read
+}
+
+/// Keeps the last line a program printed, in place of a window.
+struct LastLine {
+    bytes: [u8; WINDOW_WIDTH],
+    len: usize
+}
+
+impl InterpreterOutput for LastLine {
+    fn print(&mut self, chars: &[u8]) {
+        self.len = chars.len().min(WINDOW_WIDTH);
+        self.bytes[0..self.len].copy_from_slice(&chars[0..self.len]);
+    }
+}
+
+fn check_interpreter() -> Outcome {
+    let mut interpreter: SwimInterpreter = Interpreter::new(SELF_TEST_PROGRAM);
+    let mut output: LastLine = LastLine { bytes: [0; WINDOW_WIDTH], len: 0 };
+    for _ in 0..SELF_TEST_MAX_TICKS {
+        match interpreter.tick(&mut output) {
+            TickStatus::Continuing => {},
+            TickStatus::AwaitInput => return Err("program asked for input"),
+            TickStatus::Finished => {
+                let printed: &str = core::str::from_utf8(&output.bytes[0..output.len]).unwrap_or("").trim();
+                return if printed == SELF_TEST_EXPECTED_OUTPUT {
+                    Ok(())
+                } else {
+                    Err("program printed the wrong result")
+                };
+            }
+        }
+    }
+    Err("program did not finish")
+}
+
+/// Marks a single block as live, so everything else is garbage.
+struct KeepOne {
+    kept: Option<Pointer>
+}
+
+impl Tracer for KeepOne {
+    fn trace(&self, blocks_used: &mut [bool]) {
+        if let Some(pointer) = self.kept {
+            blocks_used[pointer.block_num()] = true;
+        }
+    }
+}
+
+/// Allocates more than the heap holds while one value is kept live, so the
+/// collector has to run and must not lose that value.
+fn check_heap() -> Outcome {
+    let mut heap: SwimHeap = SwimHeap::new();
+    let mut roots: KeepOne = KeepOne { kept: None };
+    let kept: Pointer = heap.malloc(1, &roots).map_err(|_| "first allocation failed")?;
+    heap.store(kept, SELF_TEST_MARKER).map_err(|_| "store failed")?;
+    roots.kept = Some(kept);
+    for value in 0..2 * HEAP_SIZE as u64 {
+        let garbage: Pointer = heap.malloc(1, &roots).map_err(|_| "ran out of memory, garbage not collected")?;
+        heap.store(garbage, value).map_err(|_| "store failed")?;
+    }
+    match heap.load(kept) {
+        Ok(SELF_TEST_MARKER) => Ok(()),
+        _ => Err("collection lost a live value")
+    }
+}
@@ -0,0 +1,106 @@
+use core::fmt::{self, Write};
+use core::str;
+
+use crate::byte_writer::ByteWriter;
+use crate::file_name::FileName;
+use crate::output;
+use crate::MAX_WINDOWS;
+
+/// Longest line `Snapshot::write_serial()` writes.
+const LINE_BYTES: usize = 96;
+
+/// What one window was doing when a `Snapshot` was taken.
+#[derive(Copy, Clone)]
+pub struct WindowSnapshot {
+    pub window: usize,
+    /// One word, as the serial port shows mode changes: "files",
+    /// "editing", "running" and so on.
+    pub mode: &'static str,
+    file: FileName,
+    pub row: usize,
+    pub col: usize,
+    pub ticks: usize,
+    pub running: bool
+}
+
+impl WindowSnapshot {
+    /// The file being edited or run, or the highlighted one otherwise.
+    /// Empty if there is none.
+    pub fn file(&self) -> &str {
+        self.file.as_str()
+    }
+
+    pub(crate) fn new(window: usize, mode: &'static str, file: FileName, (row, col): (usize, usize), ticks: usize,
+                      running: bool) -> Self {
+        Self { window, mode, file, row, col, ticks, running }
+    }
+}
+
+impl fmt::Display for WindowSnapshot {
+    /// "F2 mode=editing file=avg row=3 col=5 ticks=0 running=no"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "F{} mode={} file={} row={} col={} ticks={} running={}", self.window + 1, self.mode, self.file,
+               self.row, self.col, self.ticks, yes_no(self.running))
+    }
+}
+
+/// The state of every window at one moment, small enough to copy around
+/// and written as one `key=value` line per window.
+#[derive(Copy, Clone)]
+pub struct Snapshot {
+    pub active_window: usize,
+    pub frozen: bool,
+    pub zoomed: bool,
+    windows: [Option<WindowSnapshot>; MAX_WINDOWS]
+}
+
+impl Snapshot {
+    pub(crate) fn new(active_window: usize, frozen: bool, zoomed: bool) -> Self {
+        Self {
+            active_window,
+            frozen,
+            zoomed,
+            windows: [None; MAX_WINDOWS]
+        }
+    }
+
+    pub(crate) fn add(&mut self, window: WindowSnapshot) {
+        self.windows[window.window] = Some(window);
+    }
+
+    pub fn windows(&self) -> impl Iterator<Item = &WindowSnapshot> {
+        self.windows.iter().flatten()
+    }
+
+    /// Writes the snapshot to the first serial port, each line starting
+    /// "swim: state ".
+    pub fn write_serial(&self) {
+        write_line(format_args!("active=F{} frozen={} zoomed={}", self.active_window + 1, yes_no(self.frozen),
+                                yes_no(self.zoomed)));
+        for window in self.windows() {
+            write_line(format_args!("{}", window));
+        }
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "active=F{} frozen={} zoomed={}", self.active_window + 1, yes_no(self.frozen), yes_no(self.zoomed))?;
+        for window in self.windows() {
+            write!(f, "\n{}", window)?;
+        }
+        Ok(())
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+fn write_line(args: fmt::Arguments) {
+    let mut buffer: [u8; LINE_BYTES] = [0; LINE_BYTES];
+    let mut line: ByteWriter = ByteWriter::new(&mut buffer);
+    let _ = write!(line, "swim: state {}", args);
+    let len: usize = line.len();
+    output::write_serial_line(str::from_utf8(&buffer[0..len]).unwrap_or(""));
+}
@@ -0,0 +1,88 @@
+use crate::speaker::Speaker;
+use crate::MAX_WINDOWS;
+
+/// Programs play a tone by printing a line of the form `!tone <hz> <frames>`.
+const TONE_PREFIX: &str = "!tone ";
+const MIN_FREQUENCY: u32 = 20;
+const MAX_FREQUENCY: u32 = 20_000;
+const MAX_TONE_FRAMES: usize = 36;
+const TONE_GAP_FRAMES: usize = 2;
+const TONE_QUEUE_CAPACITY: usize = 8;
+const MAX_QUEUED_PER_WINDOW: usize = 2;
+
+#[derive(Copy, Clone)]
+pub(crate) struct Tone {
+    frequency: u32,
+    frames: usize,
+    window: usize
+}
+
+impl Tone {
+    /// Reads a tone request out of a line of program output. Frequency and
+    /// duration are clamped to what the speaker can sensibly play.
+    pub(crate) fn parse(line: &str, window: usize) -> Option<Self> {
+        let mut fields = line.strip_prefix(TONE_PREFIX)?.split_whitespace();
+        let frequency: u32 = fields.next()?.parse().ok()?;
+        let frames: usize = fields.next()?.parse().ok()?;
+        if fields.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            frequency: frequency.clamp(MIN_FREQUENCY, MAX_FREQUENCY),
+            frames: frames.clamp(1, MAX_TONE_FRAMES),
+            window
+        })
+    }
+}
+
+/// Tones requested by programs, played one after another with a short gap.
+/// Each window may only have a couple waiting so one program cannot hog the
+/// speaker.
+pub(crate) struct SoundScheduler {
+    queue: [Option<Tone>; TONE_QUEUE_CAPACITY],
+    head: usize,
+    len: usize,
+    queued: [usize; MAX_WINDOWS],
+    gap_left: usize
+}
+
+impl SoundScheduler {
+    pub(crate) fn new() -> Self {
+        Self {
+            queue: [None; TONE_QUEUE_CAPACITY],
+            head: 0,
+            len: 0,
+            queued: [0; MAX_WINDOWS],
+            gap_left: 0
+        }
+    }
+
+    /// Returns false if the tone was dropped.
+    pub(crate) fn push(&mut self, tone: Tone) -> bool {
+        if self.len == TONE_QUEUE_CAPACITY || self.queued[tone.window] >= MAX_QUEUED_PER_WINDOW {
+            return false;
+        }
+        self.queue[(self.head + self.len) % TONE_QUEUE_CAPACITY] = Some(tone);
+        self.len += 1;
+        self.queued[tone.window] += 1;
+        true
+    }
+
+    /// Call once per frame. Starts the next tone once the speaker is free.
+    pub(crate) fn update(&mut self, speaker: &mut Speaker) {
+        if speaker.is_busy() {
+            return;
+        }
+        if self.gap_left > 0 {
+            self.gap_left -= 1;
+            return;
+        }
+        if let Some(tone) = self.queue[self.head].take() {
+            self.head = (self.head + 1) % TONE_QUEUE_CAPACITY;
+            self.len -= 1;
+            self.queued[tone.window] -= 1;
+            speaker.tone(tone.frequency, tone.frames);
+            self.gap_left = TONE_GAP_FRAMES;
+        }
+    }
+}
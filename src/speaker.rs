@@ -0,0 +1,109 @@
+use crate::port::Port;
+
+const PIT_FREQUENCY: u32 = 1_193_182;
+const PIT_COMMAND_PORT: u16 = 0x43;
+const PIT_CHANNEL_2_PORT: u16 = 0x42;
+const SPEAKER_PORT: u16 = 0x61;
+/// Channel 2, low byte then high byte, square wave.
+const CHANNEL_2_SQUARE_WAVE: u8 = 0xb6;
+const SPEAKER_GATE_BITS: u8 = 0x03;
+
+#[derive(Copy, Clone)]
+pub(crate) enum Beep {
+    Error,
+    Finished,
+    InvalidKey
+}
+
+impl Beep {
+    fn frequency(self) -> u32 {
+        match self {
+            Beep::Error => 220,
+            Beep::Finished => 880,
+            Beep::InvalidKey => 440
+        }
+    }
+
+    fn frames(self) -> usize {
+        match self {
+            Beep::Error => 6,
+            Beep::Finished => 3,
+            Beep::InvalidKey => 2
+        }
+    }
+}
+
+/// Short tones on the PC speaker, driven by PIT channel 2. A tone keeps
+/// sounding until `update()` has been called for its number of frames.
+pub(crate) struct Speaker {
+    enabled: bool,
+    frames_left: usize
+}
+
+impl Speaker {
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: true,
+            frames_left: 0
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        if !self.enabled {
+            self.silence();
+        }
+    }
+
+    pub(crate) fn is_busy(&self) -> bool {
+        self.frames_left > 0
+    }
+
+    pub(crate) fn beep(&mut self, beep: Beep) {
+        self.tone(beep.frequency(), beep.frames());
+    }
+
+    pub(crate) fn tone(&mut self, frequency: u32, frames: usize) {
+        if self.enabled {
+            self.play(frequency, frames);
+        }
+    }
+
+    fn play(&mut self, frequency: u32, frames: usize) {
+        self.frames_left = frames;
+        let divisor: u16 = (PIT_FREQUENCY / frequency.max(1)).min(u16::MAX as u32) as u16;
+        let mut command: Port<u8> = Port::new(PIT_COMMAND_PORT);
+        let mut channel_2: Port<u8> = Port::new(PIT_CHANNEL_2_PORT);
+        let mut speaker: Port<u8> = Port::new(SPEAKER_PORT);
+        unsafe {
+            command.write(CHANNEL_2_SQUARE_WAVE);
+            channel_2.write(divisor as u8);
+            channel_2.write((divisor >> 8) as u8);
+            let gate: u8 = speaker.read();
+            speaker.write(gate | SPEAKER_GATE_BITS);
+        }
+    }
+
+    fn silence(&mut self) {
+        self.frames_left = 0;
+        let mut speaker: Port<u8> = Port::new(SPEAKER_PORT);
+        unsafe {
+            let gate: u8 = speaker.read();
+            speaker.write(gate & !SPEAKER_GATE_BITS);
+        }
+    }
+
+    pub(crate) fn update(&mut self) {
+        if self.frames_left == 0 {
+            return;
+        }
+        self.frames_left -= 1;
+        if self.frames_left == 0 {
+            self.silence();
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use core::arch::asm;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::render::Screen;
+use crate::WINDOW_WIDTH;
+
+/// Must match `[package.metadata.bootloader]` in Cargo.toml.
+const KERNEL_STACK_ADDRESS: usize = 0xFFFF_FF80_0000_0000;
+const KERNEL_STACK_PAGES: usize = 1024;
+const PAGE_SIZE: usize = 4096;
+/// The bootloader leaves the lowest page unmapped as a guard.
+const STACK_BOTTOM: usize = KERNEL_STACK_ADDRESS + PAGE_SIZE;
+const STACK_TOP: usize = KERNEL_STACK_ADDRESS + KERNEL_STACK_PAGES * PAGE_SIZE;
+const STACK_SIZE: usize = STACK_TOP - STACK_BOTTOM;
+/// Left alone below the stack pointer when painting, for the calls that
+/// do the painting.
+const PAINT_MARGIN: usize = 256;
+const PAINT_PATTERN: u64 = 0x57AC_57AC_57AC_57AC;
+/// Usage at or above this is drawn as a warning.
+const WARNING_PERCENT: usize = 75;
+
+static PAINTED: AtomicBool = AtomicBool::new(false);
+
+/// Fills the unused part of the kernel stack with a known pattern, so the
+/// diagnostics page can later find the deepest point any call reached.
+/// Call once, as early as possible, before interrupts are enabled.
+pub fn paint_stack() {
+    let stack_pointer: usize;
+    unsafe {
+        asm!("mov {}, rsp", out(reg) stack_pointer);
+    }
+    let paint_top: usize = stack_pointer - PAINT_MARGIN;
+    if !(STACK_BOTTOM..STACK_TOP).contains(&paint_top) {
+        return;
+    }
+    let mut address: usize = STACK_BOTTOM;
+    while address < paint_top {
+        unsafe {
+            ptr::write_volatile(address as *mut u64, PAINT_PATTERN);
+        }
+        address += core::mem::size_of::<u64>();
+    }
+    PAINTED.store(true, Ordering::SeqCst);
+}
+
+/// Bytes of stack used at the deepest point since boot, or `None` if the
+/// stack was never painted.
+fn high_water_mark() -> Option<usize> {
+    if !PAINTED.load(Ordering::SeqCst) {
+        return None;
+    }
+    let mut address: usize = STACK_BOTTOM;
+    while address < STACK_TOP && unsafe { ptr::read_volatile(address as *const u64) } == PAINT_PATTERN {
+        address += core::mem::size_of::<u64>();
+    }
+    Some(STACK_TOP - address)
+}
+
+/// Diagnostics page showing how close the kernel has come to overflowing
+/// its stack.
+pub(crate) fn draw_usage(screen: &mut dyn Screen, start_col: usize, start_row: usize, rows: usize) {
+    screen.plot_str("stack high-water mark", start_col, start_row, ColorCode::new(Color::White, Color::Black));
+    let used: Option<usize> = high_water_mark();
+    for row in 1..rows {
+        for col in start_col..start_col + WINDOW_WIDTH {
+            screen.plot(' ', col, start_row + row, ColorCode::new(Color::Black, Color::Black));
+        }
+    }
+    let color: ColorCode = ColorCode::new(Color::LightGray, Color::Black);
+    let used: usize = match used {
+        Some(used) => used,
+        None => {
+            screen.plot_str("stack was not painted", start_col, start_row + 1, color);
+            return;
+        }
+    };
+    let percent: usize = used * 100 / STACK_SIZE;
+    let color: ColorCode = if percent >= WARNING_PERCENT {
+        ColorCode::new(Color::LightRed, Color::Black)
+    } else {
+        color
+    };
+    let stats: [(&str, usize); 4] = [("used KiB", used / 1024), ("size KiB", STACK_SIZE / 1024),
+                                      ("free KiB", (STACK_SIZE - used) / 1024), ("percent", percent)];
+    for (row, (label, value)) in stats.iter().take(rows - 1).enumerate() {
+        screen.plot_str(label, start_col, start_row + row + 1, color);
+        screen.plot_num(*value as isize, start_col + 10, start_row + row + 1, color);
+    }
+}
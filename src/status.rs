@@ -0,0 +1,95 @@
+use core::fmt::{self, Write};
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::WIN_REGION_WIDTH;
+use crate::render::Screen;
+
+const STATUS_ROW: usize = 0;
+const STATUS_FRAMES: usize = 60;
+const MARQUEE_LABEL: &str = "selected: ";
+const MARQUEE_WIDTH: usize = 12;
+const MARQUEE_GAP: usize = 3;
+const MARQUEE_FRAMES_PER_STEP: usize = 8;
+
+pub(crate) struct StatusLine {
+    text: [u8; WIN_REGION_WIDTH],
+    len: usize,
+    frames_left: usize,
+    marquee_shown: bool
+}
+
+impl Write for StatusLine {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            if self.len < WIN_REGION_WIDTH {
+                self.text[self.len] = byte;
+                self.len += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StatusLine {
+    pub(crate) fn new() -> Self {
+        Self {
+            text: [0; WIN_REGION_WIDTH],
+            len: 0,
+            frames_left: 0,
+            marquee_shown: false
+        }
+    }
+
+    pub(crate) fn notify(&mut self, args: fmt::Arguments) {
+        self.len = 0;
+        let _ = self.write_fmt(args);
+        self.frames_left = STATUS_FRAMES;
+    }
+
+    pub(crate) fn draw(&mut self, screen: &mut dyn Screen) {
+        if self.frames_left == 0 {
+            return;
+        }
+        self.frames_left -= 1;
+        for col in 0..WIN_REGION_WIDTH {
+            if self.frames_left > 0 && col < self.len {
+                screen.plot(self.text[col] as char, col, STATUS_ROW, ColorCode::new(Color::White, Color::Black));
+            } else {
+                screen.plot(' ', col, STATUS_ROW, ColorCode::new(Color::Black, Color::Black));
+            }
+        }
+    }
+
+    pub(crate) fn is_idle(&self) -> bool {
+        self.frames_left == 0
+    }
+
+    /// Scrolls `text` through a field at the start of the line while no
+    /// notification is showing. Passing `None` clears the field.
+    pub(crate) fn draw_marquee(&mut self, screen: &mut dyn Screen, text: Option<&str>, frame: usize) {
+        if self.frames_left > 0 {
+            self.marquee_shown = false;
+            return;
+        }
+        let text: &str = match text {
+            Some(text) => text,
+            None => {
+                if self.marquee_shown {
+                    for col in 0..MARQUEE_LABEL.len() + MARQUEE_WIDTH {
+                        screen.plot(' ', col, STATUS_ROW, ColorCode::new(Color::Black, Color::Black));
+                    }
+                    self.marquee_shown = false;
+                }
+                return;
+            }
+        };
+        self.marquee_shown = true;
+        screen.plot_str(MARQUEE_LABEL, 0, STATUS_ROW, ColorCode::new(Color::White, Color::Black));
+        let loop_len: usize = text.chars().count() + MARQUEE_GAP;
+        let offset: usize = frame / MARQUEE_FRAMES_PER_STEP;
+        for i in 0..MARQUEE_WIDTH {
+            let char: char = text.chars().nth((offset + i) % loop_len).unwrap_or(' ');
+            screen.plot(char, MARQUEE_LABEL.len() + i, STATUS_ROW, ColorCode::new(Color::LightCyan, Color::Black));
+        }
+    }
+}
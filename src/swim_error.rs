@@ -0,0 +1,80 @@
+use file_system_solution::FileSystemError;
+
+use crate::fs_error;
+
+/// Something that went wrong in one window. It is shown on that window's
+/// bottom border or the status line instead of stopping the kernel, and
+/// returned from `SwimDocManager::execute()`.
+#[derive(Copy, Clone)]
+pub enum SwimError {
+    /// Filesystem errors not covered by the variants below.
+    FileSystem(FileSystemError),
+    NoSuchFile,
+    /// A new name that another file already has.
+    FileExists,
+    /// Another window has the file open in its editor.
+    FileInUse,
+    /// The window is sandboxed and the file belongs to another window.
+    Sandboxed,
+    DiskFull,
+    /// A prompt is open, or the window is running a program.
+    WindowBusy,
+    /// The window is showing something the command does not apply to.
+    InvalidMode,
+    NoSuchWindow,
+    InvalidFilename,
+    NotProgram,
+    /// A program file that is not valid UTF-8.
+    NotText,
+    /// The interpreter refused a line of input.
+    InputRejected,
+    /// The program's heap refused a load, store or allocation, so it was
+    /// stopped.
+    HeapFault,
+    /// More tokens than the interpreter has room for.
+    ProgramTooLong,
+    /// A file with more lines, or wider lines, than the editor holds.
+    /// Editing it would lose the rest when saved.
+    TooBigToEdit,
+    /// A compressed file that cannot be unpacked, such as one cut short.
+    Corrupt,
+    /// The screen cannot be switched to the text mode asked for.
+    ModeUnavailable
+}
+
+impl SwimError {
+    /// What went wrong, in words that may be followed by the name of the
+    /// file involved, as with `fs_error::describe()`.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            SwimError::FileSystem(error) => fs_error::describe(error),
+            SwimError::NoSuchFile => "file not found",
+            SwimError::FileExists => "file already exists",
+            SwimError::FileInUse => "file open in an editor",
+            SwimError::Sandboxed => "belongs to another window",
+            SwimError::DiskFull => "disk full",
+            SwimError::WindowBusy => "window busy",
+            SwimError::InvalidMode => "not available here",
+            SwimError::NoSuchWindow => "no such window",
+            SwimError::InvalidFilename => "not a valid filename",
+            SwimError::NotProgram => "not a program",
+            SwimError::NotText => "not a text file",
+            SwimError::InputRejected => "input rejected, program stopped",
+            SwimError::HeapFault => "out of heap, program stopped",
+            SwimError::ProgramTooLong => "program too long to run",
+            SwimError::TooBigToEdit => "too big to edit, view it with v",
+            SwimError::Corrupt => "compressed file is damaged",
+            SwimError::ModeUnavailable => "text mode not available"
+        }
+    }
+}
+
+impl From<FileSystemError> for SwimError {
+    fn from(error: FileSystemError) -> Self {
+        match error {
+            FileSystemError::FileNotFound => SwimError::NoSuchFile,
+            FileSystemError::DiskFull => SwimError::DiskFull,
+            error => SwimError::FileSystem(error)
+        }
+    }
+}
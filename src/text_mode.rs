@@ -0,0 +1,254 @@
+//! The VGA text modes swim can show. All three are 400 scanlines tall and
+//! differ only in how tall a character cell is: 16 scanlines for 80x25, 9
+//! for 80x43 and 8 for 80x50. The shorter cells use the BIOS font squeezed
+//! to 8 lines, kept in font block 1 so block 0 is there to go back to.
+
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use pluggable_interrupt_os::vga_buffer::{ColorCode, BUFFER_HEIGHT, BUFFER_WIDTH};
+
+use crate::port::Port;
+
+/// Rows in the tallest mode, and so the most anything drawn can have.
+pub(crate) const MAX_TEXT_ROWS: usize = 50;
+
+const SEQUENCER_INDEX_PORT: u16 = 0x3c4;
+const SEQUENCER_DATA_PORT: u16 = 0x3c5;
+const GRAPHICS_INDEX_PORT: u16 = 0x3ce;
+const GRAPHICS_DATA_PORT: u16 = 0x3cf;
+const CRTC_INDEX_PORT: u16 = 0x3d4;
+const CRTC_DATA_PORT: u16 = 0x3d5;
+const MAP_MASK_REGISTER: u8 = 0x02;
+const CHARACTER_MAP_REGISTER: u8 = 0x03;
+const MEMORY_MODE_REGISTER: u8 = 0x04;
+const READ_MAP_REGISTER: u8 = 0x04;
+const GRAPHICS_MODE_REGISTER: u8 = 0x05;
+const MISC_GRAPHICS_REGISTER: u8 = 0x06;
+const OVERFLOW_REGISTER: u8 = 0x07;
+const MAX_SCANLINE_REGISTER: u8 = 0x09;
+const VERTICAL_RETRACE_END_REGISTER: u8 = 0x11;
+const VERTICAL_DISPLAY_END_REGISTER: u8 = 0x12;
+
+/// The plane fonts are kept in.
+const FONT_PLANE: u8 = 2;
+/// Sequential addressing over all of video memory, so plane 2 reads and
+/// writes like ordinary memory while the font is copied.
+const SEQUENTIAL_MEMORY_MODE: u8 = 0x06;
+/// Video memory at 0xa0000 for 64 KiB, without odd/even addressing.
+const FONT_MEMORY_MAP: u8 = 0x04;
+const FONT_MEMORY: u64 = 0xa0000;
+const TEXT_MEMORY: u64 = 0xb8000;
+/// Bytes between glyphs in a font block, whatever the cell height.
+const GLYPH_STRIDE: usize = 32;
+const GLYPHS: usize = 256;
+/// Scanlines in a BIOS glyph, and in the cells of 80x25.
+const FONT_LINES: usize = 16;
+/// Where font block 1 starts in plane 2.
+const SQUEEZED_FONT_OFFSET: usize = 0x4000;
+/// Character map select values: both maps from block 0, or from block 1.
+const ORIGINAL_FONT: u8 = 0x00;
+const SQUEEZED_FONT: u8 = 0x05;
+/// Set in the vertical retrace end register to lock CRTC registers 0-7.
+const CRTC_PROTECT_BIT: u8 = 0x80;
+/// Where the overflow register keeps bits 8 and 9 of the vertical display end.
+const DISPLAY_END_BIT_8: u8 = 0x02;
+const DISPLAY_END_BIT_9: u8 = 0x40;
+const MAX_SCANLINE_MASK: u8 = 0x1f;
+
+/// Where the bootloader mapped all of physical memory, or 0 until
+/// `map_physical_memory()`. Without it only the 80x25 part of the text
+/// buffer, which the bootloader maps itself, can be reached.
+static PHYSICAL_MEMORY_OFFSET: AtomicU64 = AtomicU64::new(0);
+/// Rows the card is showing.
+static ROWS: AtomicUsize = AtomicUsize::new(BUFFER_HEIGHT);
+
+/// A text mode the screen can be switched to. All of them are 80 columns.
+#[derive(Copy, Clone, PartialEq)]
+pub enum TextMode {
+    Text80x25,
+    Text80x43,
+    Text80x50
+}
+
+impl TextMode {
+    pub fn rows(self) -> usize {
+        match self {
+            TextMode::Text80x25 => 25,
+            TextMode::Text80x43 => 43,
+            TextMode::Text80x50 => 50
+        }
+    }
+
+    /// Scanlines in each character cell.
+    pub(crate) fn cell_lines(self) -> usize {
+        match self {
+            TextMode::Text80x25 => FONT_LINES,
+            TextMode::Text80x43 => 9,
+            TextMode::Text80x50 => 8
+        }
+    }
+
+    /// The mode `rows` tall, if there is one.
+    pub(crate) fn with_rows(rows: usize) -> Option<Self> {
+        [TextMode::Text80x25, TextMode::Text80x43, TextMode::Text80x50].into_iter().find(|mode| mode.rows() == rows)
+    }
+}
+
+/// Lets the taller modes reach the video memory the bootloader leaves
+/// unmapped, through its mapping of all physical memory at `offset`.
+pub fn map_physical_memory(offset: u64) {
+    PHYSICAL_MEMORY_OFFSET.store(offset, Ordering::Relaxed);
+}
+
+fn video_memory(physical: u64) -> Option<*mut u8> {
+    match PHYSICAL_MEMORY_OFFSET.load(Ordering::Relaxed) {
+        0 => None,
+        offset => Some((offset + physical) as *mut u8)
+    }
+}
+
+/// Switches the card to `mode`. Returns false, leaving the card as it was,
+/// if the video memory the mode needs has not been mapped.
+pub(crate) fn set(mode: TextMode) -> bool {
+    let font: u8 = if mode.cell_lines() == FONT_LINES {
+        ORIGINAL_FONT
+    } else {
+        match video_memory(FONT_MEMORY) {
+            Some(fonts) => unsafe { squeeze_font(fonts) },
+            None => return false
+        }
+        SQUEEZED_FONT
+    };
+    write_sequencer(CHARACTER_MAP_REGISTER, font);
+    set_cell_lines(mode);
+    ROWS.store(mode.rows(), Ordering::Relaxed);
+    true
+}
+
+/// Draws a cell on one of the rows below the 80x25 buffer. Cells outside
+/// the current mode are ignored.
+pub(crate) fn plot_below(char: char, col: usize, row: usize, color: ColorCode) {
+    if col >= BUFFER_WIDTH || row >= ROWS.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(text) = video_memory(TEXT_MEMORY) {
+        // A `ColorCode` is the attribute byte, wrapped; the transmute would
+        // not compile if it were any bigger.
+        let attribute: u8 = unsafe { core::mem::transmute::<ColorCode, u8>(color) };
+        unsafe {
+            let cell: *mut u8 = text.add(2 * (row * BUFFER_WIDTH + col));
+            cell.write_volatile(char as u8);
+            cell.add(1).write_volatile(attribute);
+        }
+    }
+}
+
+/// Copies font block 0 into block 1 with each glyph's lines ORed together
+/// in pairs, so strokes one line thin survive being halved.
+///
+/// # Safety
+/// `fonts` must point at physical address 0xa0000.
+unsafe fn squeeze_font(fonts: *mut u8) {
+    let map_mask: u8 = read_sequencer(MAP_MASK_REGISTER);
+    let memory_mode: u8 = read_sequencer(MEMORY_MODE_REGISTER);
+    let read_map: u8 = read_graphics(READ_MAP_REGISTER);
+    let graphics_mode: u8 = read_graphics(GRAPHICS_MODE_REGISTER);
+    let misc_graphics: u8 = read_graphics(MISC_GRAPHICS_REGISTER);
+    write_sequencer(MAP_MASK_REGISTER, 1 << FONT_PLANE);
+    write_sequencer(MEMORY_MODE_REGISTER, SEQUENTIAL_MEMORY_MODE);
+    write_graphics(READ_MAP_REGISTER, FONT_PLANE);
+    write_graphics(GRAPHICS_MODE_REGISTER, 0);
+    write_graphics(MISC_GRAPHICS_REGISTER, FONT_MEMORY_MAP);
+    for glyph in 0..GLYPHS {
+        let original: *mut u8 = fonts.add(glyph * GLYPH_STRIDE);
+        let squeezed: *mut u8 = fonts.add(SQUEEZED_FONT_OFFSET + glyph * GLYPH_STRIDE);
+        for line in 0..GLYPH_STRIDE {
+            let bits: u8 = if line < FONT_LINES / 2 {
+                original.add(2 * line).read_volatile() | original.add(2 * line + 1).read_volatile()
+            } else {
+                0
+            };
+            squeezed.add(line).write_volatile(bits);
+        }
+    }
+    write_sequencer(MAP_MASK_REGISTER, map_mask);
+    write_sequencer(MEMORY_MODE_REGISTER, memory_mode);
+    write_graphics(READ_MAP_REGISTER, read_map);
+    write_graphics(GRAPHICS_MODE_REGISTER, graphics_mode);
+    write_graphics(MISC_GRAPHICS_REGISTER, misc_graphics);
+}
+
+/// Sets the cell height, and shows as many rows of it as fit in 400
+/// scanlines.
+fn set_cell_lines(mode: TextMode) {
+    let max_scanline: u8 = read_crtc(MAX_SCANLINE_REGISTER) & !MAX_SCANLINE_MASK;
+    write_crtc(MAX_SCANLINE_REGISTER, max_scanline | (mode.cell_lines() - 1) as u8);
+    let display_end: usize = mode.rows() * mode.cell_lines() - 1;
+    let mut overflow: u8 = read_crtc(OVERFLOW_REGISTER) & !(DISPLAY_END_BIT_8 | DISPLAY_END_BIT_9);
+    if display_end & 0x100 != 0 {
+        overflow |= DISPLAY_END_BIT_8;
+    }
+    if display_end & 0x200 != 0 {
+        overflow |= DISPLAY_END_BIT_9;
+    }
+    let retrace_end: u8 = read_crtc(VERTICAL_RETRACE_END_REGISTER);
+    write_crtc(VERTICAL_RETRACE_END_REGISTER, retrace_end & !CRTC_PROTECT_BIT);
+    write_crtc(OVERFLOW_REGISTER, overflow);
+    write_crtc(VERTICAL_DISPLAY_END_REGISTER, display_end as u8);
+    write_crtc(VERTICAL_RETRACE_END_REGISTER, retrace_end);
+}
+
+fn read_sequencer(register: u8) -> u8 {
+    read_register(SEQUENCER_INDEX_PORT, SEQUENCER_DATA_PORT, register)
+}
+
+fn write_sequencer(register: u8, value: u8) {
+    write_register(SEQUENCER_INDEX_PORT, SEQUENCER_DATA_PORT, register, value);
+}
+
+fn read_graphics(register: u8) -> u8 {
+    read_register(GRAPHICS_INDEX_PORT, GRAPHICS_DATA_PORT, register)
+}
+
+fn write_graphics(register: u8, value: u8) {
+    write_register(GRAPHICS_INDEX_PORT, GRAPHICS_DATA_PORT, register, value);
+}
+
+fn read_crtc(register: u8) -> u8 {
+    read_register(CRTC_INDEX_PORT, CRTC_DATA_PORT, register)
+}
+
+fn write_crtc(register: u8, value: u8) {
+    write_register(CRTC_INDEX_PORT, CRTC_DATA_PORT, register, value);
+}
+
+fn read_register(index_port: u16, data_port: u16, register: u8) -> u8 {
+    let mut index: Port<u8> = Port::new(index_port);
+    let mut data: Port<u8> = Port::new(data_port);
+    unsafe {
+        index.write(register);
+        data.read()
+    }
+}
+
+fn write_register(index_port: u16, data_port: u16, register: u8, value: u8) {
+    let mut index: Port<u8> = Port::new(index_port);
+    let mut data: Port<u8> = Port::new(data_port);
+    unsafe {
+        index.write(register);
+        data.write(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_mode_fills_400_scanlines() {
+        for mode in [TextMode::Text80x25, TextMode::Text80x43, TextMode::Text80x50] {
+            assert!(mode.rows() * mode.cell_lines() <= 400 && mode.rows() <= MAX_TEXT_ROWS);
+            assert!(TextMode::with_rows(mode.rows()) == Some(mode));
+        }
+        assert!(TextMode::with_rows(30).is_none());
+    }
+}
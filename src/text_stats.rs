@@ -0,0 +1,50 @@
+/// Line, word, character and byte counts built up one chunk of a file at a
+/// time, so the whole file never has to be in memory.
+#[derive(Copy, Clone)]
+pub(crate) struct TextStats {
+    pub(crate) newlines: usize,
+    pub(crate) words: usize,
+    pub(crate) chars: usize,
+    pub(crate) bytes: usize,
+    in_word: bool,
+    last_byte: Option<u8>
+}
+
+impl TextStats {
+    pub(crate) fn new() -> Self {
+        Self {
+            newlines: 0,
+            words: 0,
+            chars: 0,
+            bytes: 0,
+            in_word: false,
+            last_byte: None
+        }
+    }
+
+    pub(crate) fn feed(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            self.bytes += 1;
+            if byte & 0xc0 != 0x80 {
+                self.chars += 1;
+            }
+            if byte == b'\n' {
+                self.newlines += 1;
+            }
+            let whitespace: bool = byte.is_ascii_whitespace();
+            if !whitespace && !self.in_word {
+                self.words += 1;
+            }
+            self.in_word = !whitespace;
+            self.last_byte = Some(byte);
+        }
+    }
+
+    /// A last line without a trailing newline still counts.
+    pub(crate) fn lines(&self) -> usize {
+        match self.last_byte {
+            Some(b'\n') | None => self.newlines,
+            Some(_) => self.newlines + 1
+        }
+    }
+}
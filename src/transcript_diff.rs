@@ -0,0 +1,125 @@
+use core::fmt::Write;
+use core::str;
+use pluggable_interrupt_os::vga_buffer::{is_drawable, Color, ColorCode};
+
+use crate::byte_writer::ByteWriter;
+use crate::render::Screen;
+use crate::WINDOW_WIDTH;
+
+/// Matching lines shown above the first difference.
+const CONTEXT_LINES: usize = 1;
+
+/// The number of the first line that differs between `old` and `new`, or
+/// `None` if they are the same.
+fn first_difference(old: &[u8], new: &[u8]) -> Option<usize> {
+    let mut old_lines = old.split(|&byte| byte == b'\n');
+    let mut new_lines = new.split(|&byte| byte == b'\n');
+    let mut line: usize = 0;
+    loop {
+        match (old_lines.next(), new_lines.next()) {
+            (None, None) => return None,
+            (old_line, new_line) if old_line == new_line => line += 1,
+            _ => return Some(line)
+        }
+    }
+}
+
+/// The first line where `actual` differs from `expected`, or `None` if they
+/// match. Newlines at the very end do not count, so a hand-written file
+/// without a final newline still matches captured output.
+pub(crate) fn first_mismatch(actual: &[u8], expected: &[u8]) -> Option<usize> {
+    first_difference(without_final_newlines(actual), without_final_newlines(expected))
+}
+
+fn without_final_newlines(bytes: &[u8]) -> &[u8] {
+    let len: usize = bytes.iter().rposition(|&byte| byte != b'\n').map_or(0, |last| last + 1);
+    &bytes[0..len]
+}
+
+/// Draws a unified diff of `old` against `new`, starting just above their
+/// first difference, which is highlighted. Lines are paired by position
+/// rather than aligned. Transcripts of the same program stay in step until
+/// the two runs diverge, so the first difference is where they diverge.
+pub(crate) fn draw(screen: &mut dyn Screen, old: &[u8], new: &[u8], start_col: usize, start_row: usize,
+                   rows: usize) {
+    let header_color: ColorCode = ColorCode::new(Color::White, Color::Black);
+    let first: usize = match first_difference(old, new) {
+        Some(line) => line,
+        None => {
+            screen.plot_str("no differences", start_col, start_row, header_color);
+            return;
+        }
+    };
+    let mut buffer: [u8; WINDOW_WIDTH] = [0; WINDOW_WIDTH];
+    let mut header: ByteWriter = ByteWriter::new(&mut buffer);
+    let _ = write!(header, "line {} differs", first + 1);
+    let len: usize = header.len();
+    screen.plot_str(str::from_utf8(&buffer[0..len]).unwrap_or(""), start_col, start_row, header_color);
+
+    let start: usize = first.saturating_sub(CONTEXT_LINES);
+    let mut old_lines = old.split(|&byte| byte == b'\n').skip(start);
+    let mut new_lines = new.split(|&byte| byte == b'\n').skip(start);
+    let mut row: usize = 1;
+    let mut line: usize = start;
+    while row < rows {
+        let (old_line, new_line): (Option<&[u8]>, Option<&[u8]>) = (old_lines.next(), new_lines.next());
+        let (removed, added): (ColorCode, ColorCode) = if line == first {
+            (ColorCode::new(Color::Black, Color::LightRed), ColorCode::new(Color::Black, Color::LightGreen))
+        } else {
+            (ColorCode::new(Color::LightRed, Color::Black), ColorCode::new(Color::LightGreen, Color::Black))
+        };
+        match (old_line, new_line) {
+            (None, None) => break,
+            (Some(same), _) if old_line == new_line => {
+                draw_line(screen, ' ', same, start_col, start_row + row,
+                          ColorCode::new(Color::LightGray, Color::Black));
+                row += 1;
+            },
+            _ => {
+                if let Some(old_line) = old_line {
+                    draw_line(screen, '-', old_line, start_col, start_row + row, removed);
+                    row += 1;
+                }
+                if let Some(new_line) = new_line {
+                    if row < rows {
+                        draw_line(screen, '+', new_line, start_col, start_row + row, added);
+                        row += 1;
+                    }
+                }
+            }
+        }
+        line += 1;
+    }
+}
+
+/// One diff line: its marker, then as much of `line` as fits.
+fn draw_line(screen: &mut dyn Screen, marker: char, line: &[u8], col: usize, row: usize, color: ColorCode) {
+    screen.plot(marker, col, row, color);
+    for (i, &byte) in line.iter().take(WINDOW_WIDTH - 1).enumerate() {
+        let char: char = if is_drawable(byte as char) { byte as char } else { '.' };
+        screen.plot(char, col + 1 + i, row, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_text_has_no_difference() {
+        assert_eq!(first_difference(b"", b""), None);
+        assert_eq!(first_difference(b"a\nb", b"a\nb"), None);
+        assert_eq!(first_mismatch(b"a\nb\n\n", b"a\nb"), None);
+        assert_eq!(first_mismatch(b"\n", b""), None);
+    }
+
+    #[test]
+    fn finds_the_first_line_that_differs() {
+        assert_eq!(first_difference(b"a\nb\nc", b"a\nx\nc"), Some(1));
+        assert_eq!(first_difference(b"a", b"b"), Some(0));
+        assert_eq!(first_difference(b"a\nb", b"a"), Some(1));
+        assert_eq!(first_difference(b"a", b"a\n"), Some(1));
+        assert_eq!(first_mismatch(b"a\nb\n", b"a\nb\nc\n"), Some(2));
+        assert_eq!(first_mismatch(b"a\n\nb", b"a\nb"), Some(1));
+    }
+}
@@ -0,0 +1,129 @@
+use pluggable_interrupt_os::vga_buffer::{Color, ColorCode};
+
+use crate::notifications::Notification;
+use crate::observer::Observer;
+use crate::render::Screen;
+use crate::WIN_REGION_WIDTH;
+
+const TUTORIAL_ROW: usize = 0;
+const TUTORIAL_COL: usize = 30;
+const TUTORIAL_WIDTH: usize = WIN_REGION_WIDTH - TUTORIAL_COL;
+
+/// Things the manager reports as they happen. Tutorial steps wait for one.
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum TutorialEvent {
+    PromptOpened,
+    FileCreated,
+    EditorOpened,
+    Saved,
+    ProgramStarted,
+    ProgramFinished
+}
+
+/// The part of the screen a step wants the user to look at.
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum Highlight {
+    None,
+    ActiveWindow
+}
+
+/// One line of the script. Text inside `[` and `]` is drawn as a key.
+struct Step {
+    text: &'static str,
+    highlight: Highlight,
+    until: TutorialEvent
+}
+
+static SCRIPT: [Step; 6] = [
+    Step { text: "Press [F5] to create a file", highlight: Highlight::None, until: TutorialEvent::PromptOpened },
+    Step { text: "Type a name, then [Enter]", highlight: Highlight::None, until: TutorialEvent::FileCreated },
+    Step { text: "Select it with [<] [>], press [e]", highlight: Highlight::ActiveWindow, until: TutorialEvent::EditorOpened },
+    Step { text: "Type print(1), press [F6] [F6]", highlight: Highlight::ActiveWindow, until: TutorialEvent::Saved },
+    Step { text: "Select it again and press [r]", highlight: Highlight::ActiveWindow, until: TutorialEvent::ProgramStarted },
+    Step { text: "Watch it run. [F10] ends the tour", highlight: Highlight::ActiveWindow, until: TutorialEvent::ProgramFinished }
+];
+
+/// Walks through `SCRIPT` one step at a time, moving on when the step's
+/// event is reported.
+pub(crate) struct Tutorial {
+    step: Option<usize>,
+    drawn: bool
+}
+
+impl Tutorial {
+    pub(crate) fn new() -> Self {
+        Self {
+            step: None,
+            drawn: false
+        }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.step.is_some()
+    }
+
+    pub(crate) fn start(&mut self) {
+        self.step = Some(0);
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.step = None;
+    }
+
+    pub(crate) fn observe(&mut self, event: TutorialEvent) {
+        if let Some(step) = self.step {
+            if SCRIPT[step].until == event {
+                self.step = if step + 1 < SCRIPT.len() { Some(step + 1) } else { None };
+            }
+        }
+    }
+
+    pub(crate) fn highlight(&self) -> Highlight {
+        match self.step {
+            Some(step) => SCRIPT[step].highlight,
+            None => Highlight::None
+        }
+    }
+
+    pub(crate) fn draw(&mut self, screen: &mut dyn Screen) {
+        let text: &str = match self.step {
+            Some(step) => SCRIPT[step].text,
+            None if self.drawn => "",
+            None => return
+        };
+        self.drawn = self.step.is_some();
+        let mut markup = text.chars();
+        let mut in_key: bool = false;
+        for col in 0..TUTORIAL_WIDTH {
+            let mut char: char = ' ';
+            for next in markup.by_ref() {
+                match next {
+                    '[' => in_key = true,
+                    ']' => in_key = false,
+                    _ => {
+                        char = next;
+                        break;
+                    }
+                }
+            }
+            let color: ColorCode = if char == ' ' {
+                ColorCode::new(Color::Black, Color::Black)
+            } else if in_key {
+                ColorCode::new(Color::Yellow, Color::Black)
+            } else {
+                ColorCode::new(Color::LightGreen, Color::Black)
+            };
+            screen.plot(char, TUTORIAL_COL + col, TUTORIAL_ROW, color);
+        }
+    }
+}
+
+impl Observer for Tutorial {
+    fn on_file_saved(&mut self, _frame: usize, _window: usize, _filename: &str) {
+        self.observe(TutorialEvent::Saved);
+    }
+
+    fn on_program_finished(&mut self, _finished: &Notification, _watched: bool) {
+        self.observe(TutorialEvent::ProgramFinished);
+    }
+}
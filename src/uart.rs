@@ -0,0 +1,72 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::port::Port;
+
+const COM1_PORT: u16 = 0x3f8;
+const INTERRUPT_ENABLE_OFFSET: u16 = 1;
+const FIFO_CONTROL_OFFSET: u16 = 2;
+const LINE_CONTROL_OFFSET: u16 = 3;
+const MODEM_CONTROL_OFFSET: u16 = 4;
+const LINE_STATUS_OFFSET: u16 = 5;
+
+/// Set in the line control register to reach the baud rate divisor.
+const DIVISOR_LATCH: u8 = 0x80;
+const EIGHT_BITS_NO_PARITY: u8 = 0x03;
+const ENABLE_AND_CLEAR_FIFOS: u8 = 0xc7;
+/// Data terminal ready and request to send.
+const READY_TO_SEND: u8 = 0x03;
+const TRANSMIT_EMPTY: u8 = 0x20;
+/// 115200 baud over this gives 38400.
+const BAUD_DIVISOR: u16 = 3;
+/// Status reads before a byte is given up on, so a port with nothing
+/// listening never stops the kernel.
+const MAX_POLLS: usize = 100_000;
+
+/// The 16550 UART behind a serial port, written a byte at a time by
+/// polling. It is set up the first time it is written to.
+pub(crate) struct Uart16550 {
+    base: u16,
+    ready: AtomicBool
+}
+
+pub(crate) static COM1: Uart16550 = Uart16550::new(COM1_PORT);
+
+impl Uart16550 {
+    const fn new(base: u16) -> Self {
+        Self {
+            base,
+            ready: AtomicBool::new(false)
+        }
+    }
+
+    /// 38400 baud, 8 data bits, no parity and one stop bit, with the FIFOs
+    /// on and the UART's interrupts off.
+    fn init(&self) {
+        self.write_register(INTERRUPT_ENABLE_OFFSET, 0);
+        self.write_register(LINE_CONTROL_OFFSET, DIVISOR_LATCH);
+        self.write_register(0, BAUD_DIVISOR as u8);
+        self.write_register(INTERRUPT_ENABLE_OFFSET, (BAUD_DIVISOR >> 8) as u8);
+        self.write_register(LINE_CONTROL_OFFSET, EIGHT_BITS_NO_PARITY);
+        self.write_register(FIFO_CONTROL_OFFSET, ENABLE_AND_CLEAR_FIFOS);
+        self.write_register(MODEM_CONTROL_OFFSET, READY_TO_SEND);
+    }
+
+    fn write_register(&self, offset: u16, value: u8) {
+        let mut port: Port<u8> = Port::new(self.base + offset);
+        unsafe { port.write(value) }
+    }
+
+    fn read_register(&self, offset: u16) -> u8 {
+        let mut port: Port<u8> = Port::new(self.base + offset);
+        unsafe { port.read() }
+    }
+
+    pub(crate) fn write_byte(&self, byte: u8) {
+        if !self.ready.swap(true, Ordering::Relaxed) {
+            self.init();
+        }
+        if (0..MAX_POLLS).any(|_| self.read_register(LINE_STATUS_OFFSET) & TRANSMIT_EMPTY != 0) {
+            self.write_register(0, byte);
+        }
+    }
+}